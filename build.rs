@@ -0,0 +1,9 @@
+fn main() {
+    #[cfg(feature = "server")]
+    {
+        // compiled with protox so building does not need protoc installed
+        let fds = protox::compile(["proto/iron_exec.proto"], ["proto"]).unwrap();
+        tonic_build::configure().compile_fds(fds).unwrap();
+        println!("cargo:rerun-if-changed=proto/iron_exec.proto");
+    }
+}
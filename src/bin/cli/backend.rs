@@ -0,0 +1,251 @@
+use hyper_util::rt::TokioIo;
+use iron_exec::{
+    job::{Command, JobInfo},
+    server::proto::{
+        worker_client::WorkerClient, ListRequest, QueryRequest, SignalRequest, StartRequest,
+        StopRequest, StreamRequest, WaitRequest,
+    },
+    worker::{AsyncWorker, Error, Journal},
+};
+use std::{path::Path, time::Duration};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tower::service_fn;
+use uuid::Uuid;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+// Where the CLI's jobs run: in a worker inside this process, or on a server.
+pub enum Backend {
+    Local {
+        worker: AsyncWorker,
+        owner_id: Uuid,
+        state_dir: &'static str,
+    },
+    Remote {
+        client: WorkerClient<Channel>,
+    },
+}
+
+impl Backend {
+    pub async fn connect(server: &str, ca: &Path, cert: &Path, key: &Path) -> Result<Self, Error> {
+        let tls = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(read_pem(ca)?))
+            .identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+        let channel = Channel::from_shared(server.to_string())
+            .map_err(|e| Error::WorkerErr(format!("invalid server address {}: {}", server, e)))?
+            .tls_config(tls)
+            .map_err(|e| Error::WorkerErr(format!("invalid tls config: {}", e)))?
+            .connect()
+            .await
+            .map_err(|e| Error::WorkerErr(format!("failed to connect to {}: {}", server, e)))?;
+        Ok(Backend::Remote {
+            client: WorkerClient::new(channel),
+        })
+    }
+
+    // Connects to a server's unix socket, where the caller is identified by
+    // their uid rather than a certificate.
+    pub async fn connect_unix(path: &str) -> Result<Self, Error> {
+        let path = path.to_string();
+        // the uri is required but unused, as every connection goes to path
+        let channel =
+            Endpoint::from_static("http://localhost")
+                .connect_with_connector(service_fn(move |_| {
+                    let path = path.clone();
+                    async move {
+                        Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(path).await?))
+                    }
+                }))
+                .await
+                .map_err(|e| Error::WorkerErr(format!("failed to connect to socket: {}", e)))?;
+        Ok(Backend::Remote {
+            client: WorkerClient::new(channel),
+        })
+    }
+
+    pub async fn start(&mut self, command: Command) -> Result<Uuid, Error> {
+        match self {
+            Backend::Local {
+                worker, owner_id, ..
+            } => worker.start(command, *owner_id).await,
+            Backend::Remote { client } => {
+                let request = StartRequest {
+                    command: Some(command.into()),
+                };
+                let job_id = client.start(request).await.map_err(from_status)?;
+                parse_job_id(&job_id.into_inner().job_id)
+            }
+        }
+    }
+
+    pub async fn stop(&mut self, job_id: Uuid, gracefully: bool) -> Result<(), Error> {
+        match self {
+            Backend::Local {
+                worker, owner_id, ..
+            } => worker.stop(job_id, *owner_id, gracefully).await,
+            Backend::Remote { client } => {
+                let request = StopRequest {
+                    job_id: job_id.to_string(),
+                    gracefully,
+                };
+                client.stop(request).await.map_err(from_status)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn signal(&mut self, job_id: Uuid, signal: i32) -> Result<(), Error> {
+        match self {
+            Backend::Local {
+                worker, owner_id, ..
+            } => worker.signal(job_id, *owner_id, signal).await,
+            Backend::Remote { client } => {
+                let request = SignalRequest {
+                    job_id: job_id.to_string(),
+                    signal,
+                };
+                client.signal(request).await.map_err(from_status)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn query(&mut self, job_id: Uuid) -> Result<JobInfo, Error> {
+        match self {
+            Backend::Local {
+                worker, owner_id, ..
+            } => worker.query(job_id, *owner_id).await,
+            Backend::Remote { client } => {
+                let request = QueryRequest {
+                    job_id: job_id.to_string(),
+                };
+                let job_info = client.query(request).await.map_err(from_status)?;
+                job_info.into_inner().try_into()
+            }
+        }
+    }
+
+    pub async fn list(&mut self) -> Result<Vec<JobInfo>, Error> {
+        match self {
+            Backend::Local {
+                worker, owner_id, ..
+            } => worker.list(*owner_id).await,
+            Backend::Remote { client } => {
+                let jobs = client.list(ListRequest {}).await.map_err(from_status)?;
+                jobs.into_inner()
+                    .jobs
+                    .into_iter()
+                    .map(JobInfo::try_from)
+                    .collect()
+            }
+        }
+    }
+
+    pub async fn wait(
+        &mut self,
+        job_id: Uuid,
+        timeout: Option<Duration>,
+    ) -> Result<JobInfo, Error> {
+        match self {
+            Backend::Local {
+                worker,
+                owner_id,
+                state_dir,
+            } => {
+                let job_info = worker.wait(job_id, *owner_id, timeout).await?;
+                // another invocation may have stopped the job, which only the
+                // journal knows about
+                let jobs = Journal::open(state_dir)?.replay()?;
+                match jobs.iter().find(|job| job.id() == job_id) {
+                    Some(job) => job.query(),
+                    None => Ok(job_info),
+                }
+            }
+            Backend::Remote { client } => {
+                let request = WaitRequest {
+                    job_id: job_id.to_string(),
+                    timeout_ms: timeout.map(|timeout| timeout.as_millis() as u64),
+                };
+                let job_info = client.wait(request).await.map_err(from_status)?;
+                job_info.into_inner().try_into()
+            }
+        }
+    }
+
+    // Copies the job's log to out, following it until the job finishes if
+    // follow is set.
+    pub async fn logs(
+        &mut self,
+        job_id: Uuid,
+        follow: bool,
+        out: &mut (impl AsyncWrite + Unpin),
+    ) -> Result<(), Error> {
+        match self {
+            Backend::Local {
+                worker, owner_id, ..
+            } => {
+                let logs = worker.stream(job_id, *owner_id).await?;
+                copy(logs.with_follow(follow), out).await
+            }
+            Backend::Remote { client } => {
+                let request = StreamRequest {
+                    job_id: job_id.to_string(),
+                    follow,
+                };
+                let mut stream = client
+                    .stream(request)
+                    .await
+                    .map_err(from_status)?
+                    .into_inner();
+                while let Some(chunk) = stream.message().await.map_err(from_status)? {
+                    write(out, &chunk.data).await?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn copy(
+    mut logs: impl AsyncRead + Unpin,
+    out: &mut (impl AsyncWrite + Unpin),
+) -> Result<(), Error> {
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = logs
+            .read(&mut buf)
+            .await
+            .map_err(|e| Error::JobStreamErr(format!("failed to read log file: {:?}", e)))?;
+        if n == 0 {
+            return Ok(());
+        }
+        write(out, &buf[..n]).await?;
+    }
+}
+
+async fn write(out: &mut (impl AsyncWrite + Unpin), data: &[u8]) -> Result<(), Error> {
+    out.write_all(data)
+        .await
+        .and(out.flush().await)
+        .map_err(|e| Error::JobStreamErr(format!("failed to write logs: {:?}", e)))
+}
+
+fn from_status(status: tonic::Status) -> Error {
+    let message = status.message().to_string();
+    match status.code() {
+        tonic::Code::NotFound => Error::NotFound(message),
+        tonic::Code::DeadlineExceeded => Error::Timeout(message),
+        _ => Error::WorkerErr(message),
+    }
+}
+
+fn parse_job_id(job_id: &str) -> Result<Uuid, Error> {
+    Uuid::parse_str(job_id).map_err(|_| Error::WorkerErr(format!("invalid job id {:?}", job_id)))
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, Error> {
+    std::fs::read(path)
+        .map_err(|e| Error::WorkerErr(format!("failed to read {}: {:?}", path.display(), e)))
+}
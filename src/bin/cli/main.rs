@@ -0,0 +1,417 @@
+mod backend;
+mod output;
+
+use backend::Backend;
+use clap::{Parser, Subcommand};
+use iron_exec::{
+    job::{
+        Capabilities, Command, Landlock, LandlockFallback, Mount, Network, Rlimits, Rootfs,
+        Seccomp, SeccompProfile, CAPABILITY_NAMES,
+    },
+    worker::{owner_id_from_uid, AsyncWorker, Config, Error},
+};
+use nix::sys::signal::Signal;
+use output::{exit_code, Format, ERROR_EXIT_CODE, TIMEOUT_EXIT_CODE};
+use std::{path::PathBuf, process::exit, time::Duration};
+use uuid::Uuid;
+
+#[derive(Parser)]
+#[command(
+    name = "iron-exec",
+    about = "Start, stop and inspect iron-exec jobs",
+    long_about = "Start, stop and inspect iron-exec jobs, either on a server given with \
+                  --server or --socket, or in a worker inside this process. Local jobs are kept in \
+                  --state-dir, and a local start stays attached until its job finishes."
+)]
+struct Cli {
+    /// Output format
+    #[arg(short, long, global = true, value_enum, default_value_t = Format::Human)]
+    output: Format,
+
+    /// Server to send requests to, e.g. https://localhost:50051
+    #[arg(long, global = true, env = "IRON_EXEC_SERVER")]
+    server: Option<String>,
+    /// PEM file of the CA the server's certificate is signed by
+    #[arg(long, global = true, env = "IRON_EXEC_CA")]
+    ca: Option<PathBuf>,
+    /// PEM file of the client certificate identifying the caller
+    #[arg(long, global = true, env = "IRON_EXEC_CERT")]
+    cert: Option<PathBuf>,
+    /// PEM file of the client certificate's key
+    #[arg(long, global = true, env = "IRON_EXEC_KEY")]
+    key: Option<PathBuf>,
+
+    /// Unix socket of a server on this host, which identifies the caller by uid
+    #[arg(
+        long,
+        global = true,
+        env = "IRON_EXEC_SOCKET",
+        conflicts_with = "server"
+    )]
+    socket: Option<String>,
+
+    /// Directory the local worker keeps its journal and logs in
+    #[arg(long, global = true, env = "IRON_EXEC_STATE_DIR")]
+    state_dir: Option<String>,
+
+    #[command(subcommand)]
+    command: Subcommands,
+}
+
+// parsed once per invocation, so the size of Start is not worth boxing it for
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum Subcommands {
+    /// Start a job, e.g. iron-exec start -- echo hello
+    Start {
+        /// Wait for the job to finish and exit with its exit code
+        #[arg(long)]
+        wait: bool,
+        /// Give the job a mount namespace with a private /tmp
+        #[arg(long)]
+        mount_namespace: bool,
+        /// Remount the job's root read-only, in a mount namespace of its own
+        #[arg(long)]
+        read_only_root: bool,
+        /// Bind mount SOURCE:TARGET[:ro] into the job's mount namespace
+        #[arg(long = "mount", value_name = "SOURCE:TARGET[:ro]", value_parser = parse_mount)]
+        mounts: Vec<Mount>,
+        /// Network the job may use: host, or loopback to keep it offline
+        #[arg(long, default_value = "host")]
+        network: Network,
+        /// Run the job inside this root filesystem directory
+        #[arg(long, value_name = "DIR")]
+        rootfs: Option<String>,
+        /// Run the job inside a root filesystem unpacked from this tar archive
+        #[arg(long, value_name = "FILE", conflicts_with = "rootfs")]
+        rootfs_tar: Option<String>,
+        /// Kill the job on system calls denied by a built-in seccomp profile:
+        /// default-deny-dangerous, no-network-syscalls or no-ptrace
+        #[arg(long, value_name = "PROFILE")]
+        seccomp: Option<SeccompProfile>,
+        /// Kill the job on system calls denied by the allow or deny list in
+        /// this file
+        #[arg(long, value_name = "FILE", conflicts_with = "seccomp")]
+        seccomp_file: Option<String>,
+        /// Drop every capability from the job's bounding set but these, e.g.
+        /// CAP_CHOWN,CAP_NET_BIND_SERVICE, or all of them with ''
+        #[arg(long, value_name = "CAPS", value_parser = parse_capabilities)]
+        cap_bounding: Option<CapabilityList>,
+        /// Start the job with only these effective capabilities
+        #[arg(long, value_name = "CAPS", value_parser = parse_capabilities)]
+        cap_effective: Option<CapabilityList>,
+        /// Raise these capabilities in the job's ambient set
+        #[arg(long, value_name = "CAPS", value_parser = parse_capabilities)]
+        cap_ambient: Option<CapabilityList>,
+        /// Keep the job from gaining privileges through setuid binaries
+        #[arg(long)]
+        no_new_privs: bool,
+        /// Confine the job to reading and executing beneath this path, and
+        /// the others given with --landlock-ro or --landlock-rw
+        #[arg(long = "landlock-ro", value_name = "PATH")]
+        landlock_ro: Vec<String>,
+        /// Let the confined job do anything beneath this path
+        #[arg(long = "landlock-rw", value_name = "PATH")]
+        landlock_rw: Vec<String>,
+        /// What to do when the kernel lacks landlock: fail, or warn and run
+        /// the job unconfined
+        #[arg(long, value_name = "FALLBACK", default_value = "fail")]
+        landlock_unsupported: LandlockFallback,
+        /// Limit the job's open file descriptors
+        #[arg(long, value_name = "N")]
+        rlimit_nofile: Option<u64>,
+        /// Limit the size of the job's core dumps, 0 for none
+        #[arg(long, value_name = "BYTES")]
+        rlimit_core: Option<u64>,
+        /// Limit the job's virtual memory
+        #[arg(long = "rlimit-as", value_name = "BYTES")]
+        rlimit_address_space: Option<u64>,
+        /// Kill the job with SIGXCPU once it has used this much CPU time
+        #[arg(long, value_name = "SECONDS")]
+        rlimit_cpu: Option<u64>,
+        /// Limit the processes of the job's user, which root is exempt from
+        #[arg(long, value_name = "N")]
+        rlimit_nproc: Option<u64>,
+        /// Run the job in a UTS namespace with this hostname
+        #[arg(long, value_name = "NAME")]
+        hostname: Option<String>,
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Stop a job with SIGTERM, or SIGKILL with --force
+    Stop {
+        job_id: Uuid,
+        #[arg(long)]
+        force: bool,
+    },
+    /// Send a signal other than SIGTERM and SIGKILL to a job, e.g. HUP or 10
+    Signal {
+        job_id: Uuid,
+        #[arg(value_parser = parse_signal)]
+        signal: i32,
+    },
+    /// Show a job's status
+    Status { job_id: Uuid },
+    /// Print a job's output
+    Logs {
+        job_id: Uuid,
+        /// Keep printing output until the job finishes
+        #[arg(short, long)]
+        follow: bool,
+    },
+    /// List jobs
+    List,
+    /// Wait for a job to finish and exit with its exit code
+    Wait {
+        job_id: Uuid,
+        /// Give up after this many seconds
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    let cli = Cli::parse();
+    // the backend is kept alive until exit, as dropping a local worker would
+    // wait for jobs it merely adopted from an earlier invocation
+    let mut backend = match backend(&cli).await {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("iron-exec: {}", e.as_str());
+            exit(ERROR_EXIT_CODE);
+        }
+    };
+    let code = match run(cli.command, cli.output, &mut backend).await {
+        Ok(code) => code,
+        Err(Error::Timeout(e)) => {
+            eprintln!("iron-exec: {}", e);
+            TIMEOUT_EXIT_CODE
+        }
+        Err(e) => {
+            eprintln!("iron-exec: {}", e.as_str());
+            ERROR_EXIT_CODE
+        }
+    };
+    exit(code);
+}
+
+async fn run(command: Subcommands, format: Format, backend: &mut Backend) -> Result<i32, Error> {
+    match command {
+        Subcommands::Start {
+            wait,
+            mount_namespace,
+            read_only_root,
+            mounts,
+            network,
+            rootfs,
+            rootfs_tar,
+            seccomp,
+            seccomp_file,
+            cap_bounding,
+            cap_effective,
+            cap_ambient,
+            no_new_privs,
+            landlock_ro,
+            landlock_rw,
+            landlock_unsupported,
+            rlimit_nofile,
+            rlimit_core,
+            rlimit_address_space,
+            rlimit_cpu,
+            rlimit_nproc,
+            hostname,
+            command,
+        } => {
+            let mut args = command.into_iter();
+            let name = args.next().unwrap_or_default();
+            let command = Command::new(name, args.collect())
+                .with_mounts(mounts)
+                .with_network(network);
+            let command = match mount_namespace {
+                true => command.with_mount_namespace(),
+                false => command,
+            };
+            let command = match read_only_root {
+                true => command.with_read_only_root(),
+                false => command,
+            };
+            let command = match (rootfs, rootfs_tar) {
+                (Some(path), _) => command.with_rootfs(Rootfs::Directory(path)),
+                (_, Some(path)) => command.with_rootfs(Rootfs::Tarball(path)),
+                _ => command,
+            };
+            let command = match (seccomp, seccomp_file) {
+                (Some(profile), _) => command.with_seccomp(Seccomp::Profile(profile)),
+                (_, Some(path)) => command.with_seccomp(Seccomp::load(&path)?),
+                _ => command,
+            };
+            let mut capabilities = Capabilities::default();
+            if let Some(CapabilityList(bounding)) = cap_bounding {
+                capabilities = capabilities.with_bounding(bounding);
+            }
+            if let Some(CapabilityList(effective)) = cap_effective {
+                capabilities = capabilities.with_effective(effective);
+            }
+            if let Some(CapabilityList(ambient)) = cap_ambient {
+                capabilities = capabilities.with_ambient(ambient);
+            }
+            let command = match capabilities.is_restricted() {
+                true => command.with_capabilities(capabilities),
+                false => command,
+            };
+            let command = match no_new_privs {
+                true => command.with_no_new_privs(),
+                false => command,
+            };
+            let command = match landlock_ro.is_empty() && landlock_rw.is_empty() {
+                true => command,
+                false => command.with_landlock(
+                    Landlock::new(landlock_ro, landlock_rw).with_unsupported(landlock_unsupported),
+                ),
+            };
+            let mut rlimits = Rlimits::default();
+            if let Some(nofile) = rlimit_nofile {
+                rlimits = rlimits.with_nofile(nofile);
+            }
+            if let Some(core) = rlimit_core {
+                rlimits = rlimits.with_core(core);
+            }
+            if let Some(address_space) = rlimit_address_space {
+                rlimits = rlimits.with_address_space(address_space);
+            }
+            if let Some(cpu) = rlimit_cpu {
+                rlimits = rlimits.with_cpu(cpu);
+            }
+            if let Some(nproc) = rlimit_nproc {
+                rlimits = rlimits.with_nproc(nproc);
+            }
+            let command = match rlimits.is_limited() {
+                true => command.with_rlimits(rlimits),
+                false => command,
+            };
+            let command = match hostname {
+                Some(hostname) => command.with_hostname(hostname),
+                None => command,
+            };
+            let job_id = backend.start(command).await?;
+            format.job_id(job_id);
+            // a local job is supervised by this process, so it cannot be left
+            // behind
+            if wait || matches!(*backend, Backend::Local { .. }) {
+                let job_info = backend.wait(job_id, None).await?;
+                return Ok(exit_code(&job_info));
+            }
+        }
+        Subcommands::Stop { job_id, force } => backend.stop(job_id, !force).await?,
+        Subcommands::Signal { job_id, signal } => backend.signal(job_id, signal).await?,
+        Subcommands::Status { job_id } => format.job_info(&backend.query(job_id).await?),
+        Subcommands::Logs { job_id, follow } => {
+            backend
+                .logs(job_id, follow, &mut tokio::io::stdout())
+                .await?
+        }
+        Subcommands::List => format.job_infos(&backend.list().await?),
+        Subcommands::Wait { job_id, timeout } => {
+            let job_info = backend
+                .wait(job_id, timeout.map(Duration::from_secs))
+                .await?;
+            format.job_info(&job_info);
+            return Ok(exit_code(&job_info));
+        }
+    }
+    Ok(0)
+}
+
+async fn backend(cli: &Cli) -> Result<Backend, Error> {
+    if let Some(server) = &cli.server {
+        let (Some(ca), Some(cert), Some(key)) = (&cli.ca, &cli.cert, &cli.key) else {
+            return Err(Error::WorkerErr(
+                "--ca, --cert and --key are required with --server".into(),
+            ));
+        };
+        return Backend::connect(server, ca, cert, key).await;
+    }
+    if let Some(socket) = &cli.socket {
+        return Backend::connect_unix(socket).await;
+    }
+
+    let state_dir = match &cli.state_dir {
+        Some(state_dir) => state_dir.clone(),
+        None => default_state_dir()?,
+    };
+    let log_dir = format!("{}/logs", state_dir);
+    let state_dir = leak(state_dir);
+    let cfg = Config::new(leak(log_dir), Config::default().thread_count())
+        .with_state_dir(state_dir)
+        .with_orphan_recovery();
+    // local jobs belong to the user running the CLI
+    let owner_id = owner_id_from_uid(nix::unistd::getuid().as_raw());
+    Ok(Backend::Local {
+        worker: AsyncWorker::new(cfg)?,
+        owner_id,
+        state_dir,
+    })
+}
+
+fn default_state_dir() -> Result<String, Error> {
+    if let Ok(state_home) = std::env::var("XDG_STATE_HOME") {
+        return Ok(format!("{}/iron-exec", state_home));
+    }
+    std::env::var("HOME")
+        .map(|home| format!("{}/.local/state/iron-exec", home))
+        .map_err(|_| Error::WorkerErr("set --state-dir, HOME or XDG_STATE_HOME".into()))
+}
+
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+#[derive(Clone)]
+struct CapabilityList(Vec<String>);
+
+fn parse_capabilities(s: &str) -> Result<CapabilityList, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let name = name.to_uppercase();
+            let name = match name.starts_with("CAP_") {
+                true => name,
+                false => format!("CAP_{}", name),
+            };
+            match CAPABILITY_NAMES.contains(&name.as_str()) {
+                true => Ok(name),
+                false => Err(format!("unknown capability {:?}", name)),
+            }
+        })
+        .collect::<Result<_, _>>()
+        .map(CapabilityList)
+}
+
+fn parse_mount(s: &str) -> Result<Mount, String> {
+    match s.split(':').collect::<Vec<_>>()[..] {
+        [source, target] => Ok(Mount::new(source.into(), target.into())),
+        [source, target, "ro"] => Ok(Mount::new(source.into(), target.into()).with_read_only()),
+        _ => Err(format!(
+            "expected SOURCE:TARGET or SOURCE:TARGET:ro, got {:?}",
+            s
+        )),
+    }
+}
+
+// Takes a signal's number or its name, with or without the SIG prefix.
+fn parse_signal(s: &str) -> Result<i32, String> {
+    if let Ok(signal) = s.parse() {
+        return Ok(signal);
+    }
+    let name = s.to_uppercase();
+    let name = match name.starts_with("SIG") {
+        true => name,
+        false => format!("SIG{}", name),
+    };
+    name.parse::<Signal>()
+        .map(|signal| signal as i32)
+        .map_err(|_| format!("unknown signal {:?}", s))
+}
@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use iron_exec::job::{JobInfo, Status};
+use serde_json::json;
+use uuid::Uuid;
+
+// Exit code of the CLI itself failing, as opposed to the job's.
+pub const ERROR_EXIT_CODE: i32 = 125;
+// Exit code of a wait that timed out while the job was still running.
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+impl Format {
+    pub fn job_id(&self, job_id: Uuid) {
+        match self {
+            Format::Human => println!("{}", job_id),
+            Format::Json => println!("{}", json!({ "job_id": job_id })),
+        }
+    }
+
+    pub fn job_info(&self, job_info: &JobInfo) {
+        match self {
+            Format::Human => {
+                println!("job:       {}", job_info.id());
+                println!("status:    {}", job_info.status());
+                if let Some(exit_code) = job_info.exit_code() {
+                    println!("exit code: {}", exit_code);
+                }
+                println!("pid:       {}", job_info.pid());
+                println!("command:   {}", command_line(job_info));
+                println!("isolation: {}", job_info.isolation());
+            }
+            Format::Json => println!("{}", json!(job_info)),
+        }
+    }
+
+    pub fn job_infos(&self, job_infos: &[JobInfo]) {
+        match self {
+            Format::Human => {
+                println!(
+                    "{:<36}  {:<24}  {:>4}  {:>7}  COMMAND",
+                    "JOB", "STATUS", "EXIT", "PID"
+                );
+                for job_info in job_infos {
+                    let exit_code = job_info
+                        .exit_code()
+                        .map(|exit_code| exit_code.to_string())
+                        .unwrap_or_default();
+                    println!(
+                        "{:<36}  {:<24}  {:>4}  {:>7}  {}",
+                        job_info.id(),
+                        job_info.status(),
+                        exit_code,
+                        job_info.pid(),
+                        command_line(job_info)
+                    );
+                }
+            }
+            Format::Json => println!("{}", json!(job_infos)),
+        }
+    }
+}
+
+fn command_line(job_info: &JobInfo) -> String {
+    let command = job_info.command();
+    std::iter::once(command.name())
+        .chain(command.args())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Maps a finished job onto the exit code a shell would report for it: its own
+// exit code, or 128 plus the signal it was stopped or killed by seccomp with.
+pub fn exit_code(job_info: &JobInfo) -> i32 {
+    match job_info.status() {
+        Status::Stopped(stop_type) => 128 + stop_type.sig() as i32,
+        Status::KilledBySeccomp => 128 + nix::libc::SIGSYS,
+        Status::KilledByCpuLimit => 128 + nix::libc::SIGXCPU,
+        _ => job_info.exit_code().unwrap_or(1),
+    }
+}
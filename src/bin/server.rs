@@ -0,0 +1,148 @@
+use iron_exec::{
+    server::{self, ServerConfig},
+    worker::{AsyncWorker, Error},
+};
+use nix::unistd::Gid;
+use std::{
+    fs,
+    future::Future,
+    os::unix::{
+        fs::{chown, PermissionsExt},
+        net::UnixStream,
+    },
+    path::Path,
+    process::exit,
+};
+use tokio::{
+    net::{TcpListener, UnixListener},
+    signal::unix::{signal, SignalKind},
+    sync::watch,
+};
+
+const USAGE: &str = "usage: iron-exec-server [--config <path>]";
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+    if let Err(e) = run().await {
+        eprintln!("iron-exec-server: {}", e.as_str());
+        exit(1);
+    }
+}
+
+async fn run() -> Result<(), Error> {
+    let cfg = ServerConfig::load(config_path()?.as_deref())?;
+    let tls = cfg.tls_config()?;
+    if tls.is_none() && cfg.socket().is_none() {
+        return Err(Error::WorkerErr(
+            "set tls_cert, tls_key and tls_client_ca to listen on tcp, or socket to listen on \
+             a unix socket"
+                .into(),
+        ));
+    }
+    let worker = AsyncWorker::new(cfg.worker_config()?)?;
+    let (stop, stopped) = watch::channel(false);
+    let until_stopped = |mut stopped: watch::Receiver<bool>| async move {
+        let _ = stopped.wait_for(|stopped| *stopped).await;
+    };
+
+    let tcp = match tls {
+        Some(tls) => {
+            let listener = TcpListener::bind(cfg.listen()).await.map_err(|e| {
+                Error::WorkerErr(format!("failed to bind {}: {:?}", cfg.listen(), e))
+            })?;
+            let addr = listener
+                .local_addr()
+                .map_err(|e| Error::WorkerErr(format!("failed to read listen address: {:?}", e)))?;
+            // printed so callers binding port 0 can find the server
+            println!("listening on {}", addr);
+            Some(server::serve(
+                worker.clone(),
+                listener,
+                tls,
+                until_stopped(stopped.clone()),
+            ))
+        }
+        None => None,
+    };
+    let unix = match cfg.socket() {
+        Some(path) => {
+            let listener = bind_unix(path, cfg.socket_group()?)?;
+            println!("listening on unix:{}", path);
+            Some(server::serve_unix(
+                worker.clone(),
+                listener,
+                until_stopped(stopped),
+            ))
+        }
+        None => None,
+    };
+
+    tokio::spawn(async move {
+        shutdown().await;
+        let _ = stop.send(true);
+    });
+    let served = tokio::try_join!(optional(tcp), optional(unix)).map(|_| ());
+    if let Some(path) = cfg.socket() {
+        let _ = fs::remove_file(path);
+    }
+    served
+}
+
+async fn optional(server: Option<impl Future<Output = Result<(), Error>>>) -> Result<(), Error> {
+    match server {
+        Some(server) => server.await,
+        None => Ok(()),
+    }
+}
+
+// Binds path, replacing a socket left behind by a server that is no longer
+// running. Only the server's user, and group if one is given, may connect, as
+// callers the policy does not list get its default role.
+fn bind_unix(path: &str, group: Option<Gid>) -> Result<UnixListener, Error> {
+    if Path::new(path).exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(Error::WorkerErr(format!("{} is already in use", path)));
+        }
+        fs::remove_file(path)
+            .map_err(|e| Error::WorkerErr(format!("failed to remove stale {}: {:?}", path, e)))?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|e| Error::WorkerErr(format!("failed to bind {}: {:?}", path, e)))?;
+    let mode = match group {
+        Some(gid) => {
+            chown(path, None, Some(gid.as_raw())).map_err(|e| {
+                Error::WorkerErr(format!("failed to set group of {}: {:?}", path, e))
+            })?;
+            0o660
+        }
+        None => 0o600,
+    };
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| Error::WorkerErr(format!("failed to set permissions of {}: {:?}", path, e)))?;
+    Ok(listener)
+}
+
+fn config_path() -> Result<Option<String>, Error> {
+    let mut args = std::env::args().skip(1);
+    match (args.next().as_deref(), args.next(), args.next()) {
+        (None, _, _) => Ok(None),
+        (Some("--config"), Some(path), None) => Ok(Some(path)),
+        _ => Err(Error::WorkerErr(USAGE.into())),
+    }
+}
+
+async fn shutdown() {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            log::error!("failed to listen for SIGTERM: {:?}", e);
+            return std::future::pending().await;
+        }
+    };
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    log::info!("shutting down");
+}
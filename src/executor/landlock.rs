@@ -0,0 +1,143 @@
+use crate::{job::Landlock, worker::Error};
+use nix::{errno::Errno, libc};
+use std::{
+    ffi::CString,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+use syscalls::{syscall, Sysno};
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+// the only rights a rule on a file rather than a directory may grant
+const ACCESS_FILE: u64 = ACCESS_FS_EXECUTE
+    | ACCESS_FS_WRITE_FILE
+    | ACCESS_FS_READ_FILE
+    | ACCESS_FS_TRUNCATE
+    | ACCESS_FS_IOCTL_DEV;
+const ACCESS_READ: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+// struct landlock_ruleset_attr up to handled_access_fs, which every ABI
+// accepts
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+// struct landlock_path_beneath_attr
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+// The landlock ABI version of the running kernel, None when it lacks
+// landlock or has it disabled.
+pub fn abi() -> Option<u32> {
+    unsafe {
+        syscall!(
+            Sysno::landlock_create_ruleset,
+            0,
+            0,
+            LANDLOCK_CREATE_RULESET_VERSION
+        )
+    }
+    .ok()
+    .map(|abi| abi as u32)
+}
+
+// Every filesystem access right the kernel's ABI knows, so that landlock
+// denies all of them that no rule grants.
+fn handled_access(abi: u32) -> u64 {
+    match abi {
+        1 => (1 << 13) - 1,
+        2 => (1 << 14) - 1,
+        3 | 4 => (1 << 15) - 1,
+        _ => (1 << 16) - 1,
+    }
+}
+
+// A ruleset created before the child is cloned, whose rules are added by the
+// child as paths must be resolved as the job sees them.
+pub struct Ruleset {
+    fd: OwnedFd,
+    paths: Vec<(CString, u64)>,
+}
+
+impl Ruleset {
+    pub fn new(landlock: &Landlock) -> Result<Self, Error> {
+        let abi = abi().ok_or_else(|| {
+            Error::JobStartErr("landlock is not supported by the worker's kernel".into())
+        })?;
+        let handled = handled_access(abi);
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        let fd = unsafe {
+            syscall!(
+                Sysno::landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0
+            )
+        }
+        .map_err(|e| Error::JobStartErr(format!("failed to create landlock ruleset: {:?}", e)))?;
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+        let paths = landlock
+            .read_only()
+            .iter()
+            .map(|path| (path, ACCESS_READ))
+            .chain(landlock.read_write().iter().map(|path| (path, handled)))
+            .map(|(path, access)| {
+                CString::new(path.as_str())
+                    .map(|path| (path, access))
+                    .map_err(|e| {
+                        Error::JobStartErr(format!("invalid landlock path {:?}: {:?}", path, e))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Ruleset { fd, paths })
+    }
+
+    // Adds a rule for each path and restricts the calling thread, which then
+    // can no longer gain privileges through execve(2) either.
+    pub unsafe fn apply(&self) -> Result<(), Errno> {
+        for (path, access) in &self.paths {
+            let parent_fd = libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC);
+            if parent_fd < 0 {
+                return Err(Errno::last());
+            }
+            let mut stat: libc::stat = std::mem::zeroed();
+            let is_dir = libc::fstat(parent_fd, &mut stat) == 0
+                && stat.st_mode & libc::S_IFMT == libc::S_IFDIR;
+            let attr = PathBeneathAttr {
+                allowed_access: match is_dir {
+                    true => *access,
+                    false => access & ACCESS_FILE,
+                },
+                parent_fd,
+            };
+            let res = syscall!(
+                Sysno::landlock_add_rule,
+                self.fd.as_raw_fd(),
+                LANDLOCK_RULE_PATH_BENEATH,
+                &attr as *const PathBeneathAttr,
+                0
+            );
+            libc::close(parent_fd);
+            res.map_err(|e| Errno::from_i32(e.into_raw()))?;
+        }
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(Errno::last());
+        }
+        syscall!(Sysno::landlock_restrict_self, self.fd.as_raw_fd(), 0)
+            .map(|_| ())
+            .map_err(|e| Errno::from_i32(e.into_raw()))
+    }
+}
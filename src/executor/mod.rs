@@ -0,0 +1,64 @@
+mod landlock;
+mod native;
+mod privileges;
+mod rlimits;
+mod sandbox;
+mod scripted;
+mod seccomp;
+pub use native::*;
+pub use sandbox::Sandboxed;
+pub use scripted::*;
+
+use crate::{
+    job::{Command, Isolation, Status},
+    worker::Error,
+};
+use std::fs::File;
+use uuid::Uuid;
+
+// Receives the status of a process once it has exited: its exit code, None
+// when it was killed by a signal or its code could not be collected, or
+// whether its seccomp filter or CPU time limit killed it.
+pub type ExitCallback = Box<dyn FnOnce(Result<Status, Error>) + Send>;
+
+// Executor is how the worker spawns, signals and waits on job processes.
+// NativeExecutor runs real Linux processes, ScriptedExecutor plays back
+// scripted outcomes so worker logic can be tested without them.
+pub trait Executor: Send + Sync + 'static {
+    type Process: Send + 'static;
+
+    // The isolation job_id's command is spawned with.
+    fn isolation(&self, job_id: Uuid, command: &Command) -> Isolation;
+
+    // Spawns job_id's command with stdout and stderr writing to log_file,
+    // returning the pid of the new process alongside the handle needed to
+    // wait on it.
+    fn spawn(
+        &self,
+        job_id: Uuid,
+        command: &Command,
+        log_file: &File,
+    ) -> Result<(u32, Self::Process), Error>;
+
+    // Identifies the process behind pid, so a recycled pid can be told apart
+    // from the job that originally held it.
+    fn start_time(&self, pid: u32) -> Option<u64>;
+
+    // Sends signal to job_id's process pid, returning the reason when it
+    // could not be sent. A process that has been reaped is never signalled,
+    // as its pid may belong to another one by then.
+    fn signal(&self, job_id: Uuid, pid: u32, signal: usize) -> Result<(), String>;
+
+    // Calls on_exit once job_id's spawned process has exited. on_exit must
+    // not block, it may run on a thread shared with other jobs.
+    fn wait(&self, job_id: Uuid, process: Self::Process, on_exit: ExitCallback);
+
+    // Takes over waiting on a process spawned by a previous worker for
+    // job_id. Returns false, dropping on_exit, when no such process is
+    // running any more.
+    fn adopt(&self, job_id: Uuid, pid: u32, start_time: Option<u64>, on_exit: ExitCallback)
+        -> bool;
+
+    // Blocks until every process being waited on has exited.
+    fn join(&self);
+}
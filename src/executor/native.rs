@@ -0,0 +1,274 @@
+use super::{landlock, sandbox, Executor, ExitCallback, Sandboxed};
+use crate::{
+    job::{process_start_time, Command, Isolation, LandlockFallback, PidFd, Rootfs, Status},
+    worker::{Error, Reaper},
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+    process::{Child, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+use syscalls::{syscall, Sysno};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct NativeExecutor {
+    reaper: Reaper,
+    isolation: Isolation,
+    mount_sources: Vec<PathBuf>,
+    // the pidfd of every job process being waited on, by job id, or None when
+    // the kernel could not open one
+    pidfds: Arc<Mutex<HashMap<Uuid, Option<Arc<PidFd>>>>>,
+}
+
+impl NativeExecutor {
+    pub fn new() -> Result<Self, Error> {
+        Ok(NativeExecutor {
+            reaper: Reaper::new()?,
+            isolation: Isolation::default(),
+            mount_sources: Vec::new(),
+            pidfds: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn with_isolation(mut self, isolation: Isolation) -> Self {
+        self.isolation = isolation;
+        self
+    }
+
+    pub fn with_mount_sources(mut self, mount_sources: Vec<String>) -> Self {
+        self.mount_sources = mount_sources.into_iter().map(PathBuf::from).collect();
+        self
+    }
+
+    // Bind mount sources must resolve, symlinks and all, to a path beneath
+    // one of the worker's allowed roots.
+    fn check_mount_sources(&self, command: &Command) -> Result<(), Error> {
+        for mount in command.mounts() {
+            let source = std::fs::canonicalize(mount.source()).map_err(|e| {
+                Error::JobStartErr(format!(
+                    "failed to read mount source {}: {:?}",
+                    mount.source(),
+                    e
+                ))
+            })?;
+            if !self.is_allowed_source(&source) {
+                return Err(Error::JobStartErr(format!(
+                    "mount source {} is outside of the worker's allowed mount sources",
+                    mount.source()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // A root filesystem comes from the host just like a bind mount, so it
+    // must come from beneath the same allowed roots.
+    fn check_rootfs_source(&self, command: &Command) -> Result<(), Error> {
+        let path = match command.rootfs() {
+            Some(Rootfs::Directory(path)) | Some(Rootfs::Tarball(path)) => path,
+            None => return Ok(()),
+        };
+        let source = std::fs::canonicalize(&path).map_err(|e| {
+            Error::JobStartErr(format!("failed to read root filesystem {}: {:?}", path, e))
+        })?;
+        if !self.is_allowed_source(&source) {
+            return Err(Error::JobStartErr(format!(
+                "root filesystem {} is outside of the worker's allowed mount sources",
+                path
+            )));
+        }
+        Ok(())
+    }
+
+    fn is_allowed_source(&self, source: &Path) -> bool {
+        self.mount_sources
+            .iter()
+            .any(|root| std::fs::canonicalize(root).is_ok_and(|root| source.starts_with(root)))
+    }
+
+    // Keeps job_id's pidfd for signalling until on_exit has run, by which
+    // time the process has been reaped.
+    fn track(
+        &self,
+        job_id: Uuid,
+        pidfd: Option<Arc<PidFd>>,
+        on_exit: Box<dyn FnOnce() + Send>,
+    ) -> Box<dyn FnOnce() + Send> {
+        self.pidfds.lock().unwrap().insert(job_id, pidfd);
+        let pidfds = self.pidfds.clone();
+        Box::new(move || {
+            on_exit();
+            pidfds.lock().unwrap().remove(&job_id);
+        })
+    }
+}
+
+// A process spawned by std, or into namespaces of its own when isolation is
+// enabled.
+pub enum NativeProcess {
+    Child(Child),
+    Sandboxed(Sandboxed),
+}
+
+impl Executor for NativeExecutor {
+    type Process = NativeProcess;
+
+    fn isolation(&self, job_id: Uuid, command: &Command) -> Isolation {
+        let isolation = self.isolation.for_command(job_id, command);
+        let optional = isolation
+            .landlock()
+            .iter()
+            .all(|ruleset| ruleset.unsupported() == LandlockFallback::Warn);
+        match optional && !isolation.landlock().is_empty() && landlock::abi().is_none() {
+            true => isolation.without_landlock(),
+            false => isolation,
+        }
+    }
+
+    fn spawn(
+        &self,
+        job_id: Uuid,
+        command: &Command,
+        log_file: &File,
+    ) -> Result<(u32, NativeProcess), Error> {
+        let isolation = self.isolation(job_id, command);
+        if !command.mounts().is_empty() && !isolation.mount_namespace() {
+            return Err(Error::JobStartErr(
+                "bind mounts need the job to have a mount namespace".into(),
+            ));
+        }
+        self.check_mount_sources(command)?;
+        if let (Some(worker), Some(seccomp)) = (self.isolation.seccomp(), command.seccomp()) {
+            if !seccomp.is_as_strict_as(worker) {
+                return Err(Error::JobStartErr(format!(
+                    "seccomp filter {} is less strict than the worker's {}",
+                    seccomp, worker
+                )));
+            }
+        }
+        if command.rootfs().is_some() && !isolation.mount_namespace() {
+            return Err(Error::JobStartErr(
+                "a root filesystem needs the job to have a mount namespace".into(),
+            ));
+        }
+        self.check_rootfs_source(command)?;
+        if let Some(hostname) = command.hostname().filter(|hostname| !is_hostname(hostname)) {
+            return Err(Error::JobStartErr(format!(
+                "invalid hostname {:?}",
+                hostname
+            )));
+        }
+        let requested = command.landlock().is_some() || !self.isolation.landlock().is_empty();
+        if requested && isolation.landlock().is_empty() {
+            log::warn!("landlock is not supported by the kernel, starting job without it");
+        }
+        if isolation.is_enabled() {
+            let process = sandbox::spawn(command, log_file, &isolation)?;
+            return Ok((process.pid(), NativeProcess::Sandboxed(process)));
+        }
+        let (stdout, stderr) = match (log_file.try_clone(), log_file.try_clone()) {
+            (Ok(stdout), Ok(stderr)) => (stdout, stderr),
+            (Err(e), _) | (_, Err(e)) => {
+                return Err(Error::JobStartErr(format!(
+                    "failed to duplicate log file handle: {:?}",
+                    e
+                )))
+            }
+        };
+        let child = std::process::Command::new(command.name())
+            .stdout(Stdio::from(stdout))
+            .stderr(Stdio::from(stderr))
+            .args(command.args())
+            .spawn()
+            .map_err(|e| Error::JobStartErr(format!("failed to spawn child process: {:?}", e)))?;
+        Ok((child.id(), NativeProcess::Child(child)))
+    }
+
+    fn start_time(&self, pid: u32) -> Option<u64> {
+        process_start_time(pid)
+    }
+
+    fn signal(&self, job_id: Uuid, pid: u32, signal: usize) -> Result<(), String> {
+        match self.pidfds.lock().unwrap().get(&job_id) {
+            Some(Some(pidfd)) => pidfd.send_signal(signal),
+            // without a pidfd the pid is all there is to signal
+            Some(None) => unsafe { syscall!(Sysno::kill, pid, signal) }.map(|_| ()),
+            None => Err(syscalls::Errno::ESRCH),
+        }
+        .map_err(|e| format!("{:?}", e))
+    }
+
+    fn wait(&self, job_id: Uuid, process: NativeProcess, on_exit: ExitCallback) {
+        let (pid, on_exit): (u32, Box<dyn FnOnce() + Send>) = match process {
+            NativeProcess::Child(mut child) => (
+                child.id(),
+                Box::new(move || {
+                    let res = child
+                        .wait()
+                        .map(|status| Status::Exited(status.code()))
+                        .map_err(|e| Error::WorkerErr(format!("child process failed: {:?}", e)));
+                    on_exit(res)
+                }),
+            ),
+            NativeProcess::Sandboxed(process) => {
+                (process.pid(), Box::new(move || on_exit(process.wait())))
+            }
+        };
+        let pidfd = match PidFd::open(pid) {
+            Ok(pidfd) => Arc::new(pidfd),
+            Err(e) => {
+                log::warn!("falling back to a blocking wait: {}", e);
+                thread::spawn(self.track(job_id, None, on_exit));
+                return;
+            }
+        };
+        let on_exit = self.track(job_id, Some(pidfd.clone()), on_exit);
+        if let Err(on_exit) = self.reaper.watch(pidfd, on_exit) {
+            thread::spawn(on_exit);
+        }
+    }
+
+    fn adopt(
+        &self,
+        job_id: Uuid,
+        pid: u32,
+        start_time: Option<u64>,
+        on_exit: ExitCallback,
+    ) -> bool {
+        // the pidfd pins the process, so a start time that still matches
+        // after opening it cannot belong to a recycled pid
+        let pidfd = match PidFd::open(pid) {
+            Ok(pidfd) if start_time.is_some() && process_start_time(pid) == start_time => {
+                Arc::new(pidfd)
+            }
+            _ => return false,
+        };
+        let on_exit = self.track(
+            job_id,
+            Some(pidfd.clone()),
+            Box::new(move || on_exit(Ok(Status::Exited(None)))),
+        );
+        if self.reaper.watch(pidfd, on_exit).is_err() {
+            self.pidfds.lock().unwrap().remove(&job_id);
+            return false;
+        }
+        true
+    }
+
+    fn join(&self) {
+        self.reaper.join();
+    }
+}
+
+// Letters, digits, hyphens and dots, up to the kernel's 64 bytes.
+fn is_hostname(hostname: &str) -> bool {
+    !hostname.is_empty()
+        && hostname.len() <= 64
+        && hostname
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
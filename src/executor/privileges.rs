@@ -0,0 +1,138 @@
+use crate::{
+    job::{Isolation, CAPABILITY_NAMES},
+    worker::Error,
+};
+use nix::{errno::Errno, libc};
+use syscalls::{syscall, Sysno};
+
+const LINUX_CAPABILITY_VERSION_3: u32 = 0x20080522;
+
+// struct __user_cap_header_struct and __user_cap_data_struct of capget(2)
+#[repr(C)]
+struct CapHeader {
+    version: u32,
+    pid: libc::c_int,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct CapData {
+    effective: u32,
+    permitted: u32,
+    inheritable: u32,
+}
+
+// Capability sets as masks, and whether to set no_new_privs, prepared before
+// the child is cloned.
+pub struct Privileges {
+    bounding: Option<u64>,
+    effective: Option<u64>,
+    ambient: Option<u64>,
+    no_new_privs: bool,
+}
+
+impl Privileges {
+    pub fn new(isolation: &Isolation) -> Result<Self, Error> {
+        let capabilities = isolation.capabilities();
+        Ok(Privileges {
+            bounding: capabilities.bounding().map(mask).transpose()?,
+            effective: capabilities.effective().map(mask).transpose()?,
+            ambient: capabilities.ambient().map(mask).transpose()?,
+            no_new_privs: isolation.no_new_privs(),
+        })
+    }
+
+    // Drops what is not in the bounding set, sets the effective and ambient
+    // sets, and sets no_new_privs, in that order as each step needs the
+    // privileges the one before gives up. The effective, permitted and
+    // inheritable sets are bounded too, as dropping a capability from the
+    // bounding set leaves them be.
+    pub unsafe fn apply(&self) -> Result<(), Errno> {
+        if let Some(bounding) = self.bounding {
+            for cap in 0..64 {
+                if bounding & (1 << cap) != 0 {
+                    continue;
+                }
+                match libc::prctl(libc::PR_CAPBSET_DROP, cap, 0, 0, 0) {
+                    0 => {}
+                    // past the last capability the kernel knows
+                    _ if Errno::last() == Errno::EINVAL => break,
+                    _ => return Err(Errno::last()),
+                }
+            }
+        }
+        if self.bounding.is_some() || self.effective.is_some() || self.ambient.is_some() {
+            let mut header = CapHeader {
+                version: LINUX_CAPABILITY_VERSION_3,
+                pid: 0,
+            };
+            let mut data = [CapData::default(); 2];
+            syscall!(
+                Sysno::capget,
+                &mut header as *mut CapHeader,
+                data.as_mut_ptr()
+            )
+            .map_err(|e| Errno::from_i32(e.into_raw()))?;
+            let get =
+                |set: fn(&CapData) -> u32| set(&data[0]) as u64 | (set(&data[1]) as u64) << 32;
+            let mut effective = get(|data| data.effective);
+            let mut permitted = get(|data| data.permitted);
+            let mut inheritable = get(|data| data.inheritable);
+            if let Some(ambient) = self.ambient {
+                // an ambient capability must be permitted and inheritable
+                inheritable = ambient;
+                permitted |= ambient;
+            }
+            if let Some(wanted) = self.effective {
+                effective = wanted;
+                permitted = wanted | self.ambient.unwrap_or(0);
+            }
+            let bounding = self.bounding.unwrap_or(u64::MAX);
+            effective &= bounding;
+            permitted &= bounding;
+            inheritable &= bounding;
+            for (i, data) in data.iter_mut().enumerate() {
+                *data = CapData {
+                    effective: (effective >> (32 * i)) as u32,
+                    permitted: (permitted >> (32 * i)) as u32,
+                    inheritable: (inheritable >> (32 * i)) as u32,
+                };
+            }
+            syscall!(Sysno::capset, &mut header as *mut CapHeader, data.as_ptr())
+                .map_err(|e| Errno::from_i32(e.into_raw()))?;
+        }
+        if let Some(ambient) = self
+            .ambient
+            .map(|ambient| ambient & self.bounding.unwrap_or(u64::MAX))
+        {
+            if libc::prctl(
+                libc::PR_CAP_AMBIENT,
+                libc::PR_CAP_AMBIENT_CLEAR_ALL,
+                0,
+                0,
+                0,
+            ) != 0
+            {
+                return Err(Errno::last());
+            }
+            for cap in (0..64).filter(|cap| ambient & (1 << cap) != 0) {
+                if libc::prctl(libc::PR_CAP_AMBIENT, libc::PR_CAP_AMBIENT_RAISE, cap, 0, 0) != 0 {
+                    return Err(Errno::last());
+                }
+            }
+        }
+        if self.no_new_privs && libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(Errno::last());
+        }
+        Ok(())
+    }
+}
+
+fn mask(names: &[String]) -> Result<u64, Error> {
+    names.iter().try_fold(0, |mask, name| {
+        match CAPABILITY_NAMES.iter().position(|known| known == name) {
+            Some(cap) => Ok(mask | 1 << cap),
+            None => Err(Error::JobStartErr(format!("unknown capability {:?}", name))),
+        }
+    })
+}
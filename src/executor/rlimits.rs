@@ -0,0 +1,53 @@
+use crate::job::Rlimits;
+use nix::{errno::Errno, libc};
+use syscalls::{syscall, Sysno};
+
+// Resource limits as prlimit(2) takes them, prepared before the child is
+// cloned.
+pub struct Limits(Vec<(libc::__rlimit_resource_t, libc::rlimit64)>);
+
+impl Limits {
+    pub fn new(rlimits: &Rlimits) -> Self {
+        let both = |limit: u64| libc::rlimit64 {
+            rlim_cur: limit,
+            rlim_max: limit,
+        };
+        let limits = [
+            (libc::RLIMIT_NOFILE, rlimits.nofile().map(both)),
+            (libc::RLIMIT_CORE, rlimits.core().map(both)),
+            (libc::RLIMIT_AS, rlimits.address_space().map(both)),
+            // the hard limit is a second past the soft one, as reaching it
+            // sends SIGKILL rather than SIGXCPU
+            (
+                libc::RLIMIT_CPU,
+                rlimits.cpu().map(|cpu| libc::rlimit64 {
+                    rlim_cur: cpu,
+                    rlim_max: cpu.saturating_add(1),
+                }),
+            ),
+            (libc::RLIMIT_NPROC, rlimits.nproc().map(both)),
+        ];
+        Limits(
+            limits
+                .into_iter()
+                .filter_map(|(resource, limit)| limit.map(|limit| (resource, limit)))
+                .collect(),
+        )
+    }
+
+    // Sets every limit of the calling process, which needs CAP_SYS_RESOURCE
+    // for any above the worker's own hard limits.
+    pub unsafe fn apply(&self) -> Result<(), Errno> {
+        for (resource, limit) in &self.0 {
+            syscall!(
+                Sysno::prlimit64,
+                0,
+                *resource,
+                limit as *const libc::rlimit64,
+                0
+            )
+            .map_err(|e| Errno::from_i32(e.into_raw()))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,898 @@
+use super::{landlock::Ruleset, privileges::Privileges, rlimits::Limits, seccomp::Filter};
+use crate::{
+    job::{Command, IdMap, Isolation, Network, Rootfs, Status, UserNamespace},
+    worker::Error,
+};
+use nix::{
+    errno::Errno,
+    libc,
+    sched::CloneFlags,
+    sys::{
+        signal::{SigSet, SigmaskHow, Signal},
+        wait::{waitpid, WaitStatus},
+    },
+    unistd::Pid,
+};
+use std::{
+    cell::Cell,
+    ffi::{CStr, CString},
+    fs::{DirBuilder, File},
+    io::{Read, Write},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        raw::c_char,
+        unix::fs::DirBuilderExt,
+    },
+    path::{Path, PathBuf},
+    ptr,
+};
+use syscalls::{syscall, Sysno};
+
+const MOUNT_ATTR_RDONLY: u64 = 0x1;
+const OPEN_TREE_CLONE: u32 = 0x1;
+const MOVE_MOUNT_F_EMPTY_PATH: u32 = 0x4;
+
+// A job process spawned into namespaces of its own. With a pid namespace the
+// process is the job's init, which reports the job's own wait status through
+// status before exiting.
+pub struct Sandboxed {
+    pid: u32,
+    status: Option<File>,
+    seccomp: bool,
+    cpu_limit: bool,
+    _rootfs: Option<RootfsDir>,
+}
+
+impl Sandboxed {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    // Blocks until the process has exited, returning how the job exited.
+    pub fn wait(mut self) -> Result<Status, Error> {
+        let status = waitpid(Pid::from_raw(self.pid as i32), None)
+            .map_err(|e| Error::WorkerErr(format!("child process failed: {:?}", e)))?;
+        let mut raw = [0u8; 4];
+        let job_status = match self
+            .status
+            .as_mut()
+            .map(|status| status.read_exact(&mut raw))
+        {
+            Some(Ok(())) => {
+                WaitStatus::from_raw(Pid::from_raw(self.pid as i32), i32::from_ne_bytes(raw))
+                    .unwrap_or(status)
+            }
+            // the init was killed before the job was, e.g. by SIGKILL
+            _ => status,
+        };
+        match job_status {
+            WaitStatus::Exited(_, code) => Ok(Status::Exited(Some(code))),
+            WaitStatus::Signaled(_, Signal::SIGSYS, _) if self.seccomp => {
+                Ok(Status::KilledBySeccomp)
+            }
+            WaitStatus::Signaled(_, Signal::SIGXCPU, _) if self.cpu_limit => {
+                Ok(Status::KilledByCpuLimit)
+            }
+            _ => Ok(Status::Exited(None)),
+        }
+    }
+}
+
+// Steps the child takes before the job runs, reported to the parent with the
+// errno of the one that failed.
+#[derive(Clone, Copy)]
+enum Step {
+    Exec,
+    Mounts,
+    Network,
+    UserNamespace,
+    Seccomp,
+    Privileges,
+    Landlock,
+    Rlimits,
+    Hostname,
+}
+
+impl Step {
+    fn describe(code: i32) -> &'static str {
+        match code {
+            c if c == Step::Mounts as i32 => "failed to set up the job's mounts",
+            c if c == Step::Network as i32 => "failed to set up the job's network",
+            c if c == Step::UserNamespace as i32 => "failed to set up the job's user namespace",
+            c if c == Step::Seccomp as i32 => "failed to set up the job's seccomp filter",
+            c if c == Step::Privileges as i32 => "failed to drop the job's privileges",
+            c if c == Step::Landlock as i32 => "failed to apply the job's landlock ruleset",
+            c if c == Step::Rlimits as i32 => "failed to set the job's resource limits",
+            c if c == Step::Hostname as i32 => "failed to set the job's hostname",
+            _ => "failed to spawn child process",
+        }
+    }
+}
+
+// What the child needs prepared before it is cloned, as a clone of a
+// multithreaded process must not allocate.
+struct Exec {
+    program: CString,
+    _args: Vec<CString>,
+    argv: Vec<*const c_char>,
+}
+
+impl Exec {
+    fn new(command: &Command) -> Result<Self, Error> {
+        let program = cstring(command.name())?;
+        let args = command
+            .args()
+            .into_iter()
+            .map(cstring)
+            .collect::<Result<Vec<_>, _>>()?;
+        let argv = std::iter::once(program.as_ptr())
+            .chain(args.iter().map(|arg| arg.as_ptr()))
+            .chain(std::iter::once(ptr::null()))
+            .collect();
+        Ok(Exec {
+            program,
+            _args: args,
+            argv,
+        })
+    }
+}
+
+struct BindMount {
+    source: CString,
+    // the source's mount tree, cloned before the job's root is changed
+    tree: Cell<RawFd>,
+    target: CString,
+    // targets on the job's private /tmp are created when missing, as a
+    // directory when is_dir is set
+    create: bool,
+    is_dir: bool,
+    read_only: bool,
+}
+
+// Device nodes a job in a root filesystem of its own gets on its /dev.
+const DEVICES: [&CStr; 6] = [
+    c"/dev/null",
+    c"/dev/zero",
+    c"/dev/full",
+    c"/dev/random",
+    c"/dev/urandom",
+    c"/dev/tty",
+];
+
+// A job's root filesystem: an overlay on a tmpfs of the job's own, so that
+// nothing the job writes, nor the mount points it needs, reaches the
+// directory or tarball it comes from.
+struct RootfsMount {
+    // the tmpfs's mount point, and the overlay's upper, work and root
+    // directories on it
+    stage: CString,
+    upper: CString,
+    work: CString,
+    root: CString,
+    options: CString,
+    devices: [Cell<RawFd>; DEVICES.len()],
+    // removed once the job is done with it
+    dir: RootfsDir,
+}
+
+// A directory private to a single job, holding the tmpfs's mount point and a
+// tarball's unpacked contents. Only the job's root may enter it, so that
+// nothing unpacked from a tarball can be used by anybody else on the host.
+struct RootfsDir(PathBuf);
+
+impl RootfsDir {
+    fn new(isolation: &Isolation) -> Result<Self, Error> {
+        let dir = RootfsDir(
+            std::env::temp_dir().join(format!("iron-exec-rootfs-{}", uuid::Uuid::new_v4())),
+        );
+        let dir_err = |e: std::io::Error| {
+            Error::JobStartErr(format!(
+                "failed to create root filesystem directory {:?}: {:?}",
+                dir.0, e
+            ))
+        };
+        DirBuilder::new()
+            .mode(0o700)
+            .create(&dir.0)
+            .map_err(dir_err)?;
+        std::fs::create_dir(dir.0.join("stage")).map_err(dir_err)?;
+        // the job's root is somebody else on the host in a user namespace
+        if let Some(user_namespace) = isolation.user_namespace() {
+            let root = |maps: &[IdMap]| {
+                maps.iter()
+                    .find(|map| map.inside() == 0)
+                    .map(|map| map.outside())
+            };
+            std::os::unix::fs::chown(
+                &dir.0,
+                root(user_namespace.uid_map()),
+                root(user_namespace.gid_map()),
+            )
+            .map_err(dir_err)?;
+        }
+        Ok(dir)
+    }
+
+    // Unpacks tarball as the owner and with the permissions of the worker,
+    // so that no setuid bits survive.
+    fn unpack(&self, tarball: &str) -> Result<PathBuf, Error> {
+        let unpack_err = |e: String| {
+            Error::JobStartErr(format!(
+                "failed to unpack root filesystem {}: {}",
+                tarball, e
+            ))
+        };
+        let unpacked = self.0.join("unpacked");
+        std::fs::create_dir(&unpacked).map_err(|e| unpack_err(format!("{:?}", e)))?;
+        let output = std::process::Command::new("tar")
+            .args(["--no-same-owner", "--no-same-permissions", "-xf"])
+            .arg(tarball)
+            .arg("-C")
+            .arg(&unpacked)
+            .output()
+            .map_err(|e| unpack_err(format!("{:?}", e)))?;
+        if !output.status.success() {
+            return Err(unpack_err(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(unpacked)
+    }
+}
+
+impl Drop for RootfsDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            log::warn!("failed to remove root filesystem {:?}: {:?}", self.0, e);
+        }
+    }
+}
+
+// The mount namespace a job is given: everything it inherits, or its own root
+// filesystem with a minimal /dev, possibly made read-only, then a private
+// /tmp, its bind mounts, and a /proc of its own pid namespace.
+struct Mounts {
+    rootfs: Option<RootfsMount>,
+    read_only_root: bool,
+    binds: Vec<BindMount>,
+    proc: bool,
+}
+
+impl Mounts {
+    fn new(command: &Command, isolation: &Isolation) -> Result<Self, Error> {
+        let binds = command
+            .mounts()
+            .into_iter()
+            .map(|mount| {
+                let target = Path::new(mount.target());
+                if !target.is_absolute() {
+                    return Err(Error::JobStartErr(format!(
+                        "mount target {} is not an absolute path",
+                        mount.target()
+                    )));
+                }
+                let metadata = std::fs::metadata(mount.source()).map_err(|e| {
+                    Error::JobStartErr(format!(
+                        "failed to read mount source {}: {:?}",
+                        mount.source(),
+                        e
+                    ))
+                })?;
+                Ok(BindMount {
+                    source: cstring(mount.source().into())?,
+                    tree: Cell::new(-1),
+                    target: cstring(mount.target().into())?,
+                    create: target.starts_with("/tmp") && target != Path::new("/tmp"),
+                    is_dir: metadata.is_dir(),
+                    // a read-only root keeps the host's files read-only
+                    // wherever they are bound into the job
+                    read_only: mount.read_only() || isolation.read_only_root(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let rootfs = match command.rootfs() {
+            None => None,
+            Some(Rootfs::Directory(path)) => match std::fs::metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    let dir = RootfsDir::new(isolation)?;
+                    Some(RootfsMount::new(PathBuf::from(path), dir, isolation)?)
+                }
+                Ok(_) => {
+                    return Err(Error::JobStartErr(format!(
+                        "root filesystem {} is not a directory",
+                        path
+                    )))
+                }
+                Err(e) => {
+                    return Err(Error::JobStartErr(format!(
+                        "failed to read root filesystem {}: {:?}",
+                        path, e
+                    )))
+                }
+            },
+            Some(Rootfs::Tarball(tarball)) => {
+                let dir = RootfsDir::new(isolation)?;
+                let unpacked = dir.unpack(&tarball)?;
+                Some(RootfsMount::new(unpacked, dir, isolation)?)
+            }
+        };
+        Ok(Mounts {
+            rootfs,
+            read_only_root: isolation.read_only_root(),
+            binds,
+            proc: isolation.pid_namespace(),
+        })
+    }
+
+    unsafe fn apply(&self) -> Result<(), Errno> {
+        // nothing mounted from here on may propagate back to the host
+        mount(None, c"/", None, libc::MS_REC | libc::MS_PRIVATE, None)?;
+        // whatever comes from the host is cloned while it is still reachable
+        // and writable
+        for bind in &self.binds {
+            bind.tree.set(clone_tree(&bind.source)?);
+        }
+        if let Some(rootfs) = &self.rootfs {
+            for (device, tree) in DEVICES.iter().zip(&rootfs.devices) {
+                tree.set(clone_tree(device)?);
+            }
+            rootfs.mount()?;
+            rootfs.pivot()?;
+        }
+        if self.read_only_root {
+            set_read_only(c"/")?;
+        }
+        mount(
+            Some(c"tmpfs"),
+            c"/tmp",
+            Some(c"tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some(c"mode=1777"),
+        )?;
+        if let Some(rootfs) = &self.rootfs {
+            mount(
+                Some(c"tmpfs"),
+                c"/dev",
+                Some(c"tmpfs"),
+                libc::MS_NOSUID | libc::MS_NOEXEC,
+                Some(c"mode=755"),
+            )?;
+            for (device, tree) in DEVICES.iter().zip(&rootfs.devices) {
+                create(device, false)?;
+                attach_tree(tree.get(), device)?;
+            }
+        }
+        for bind in &self.binds {
+            let target = bind.target.as_c_str();
+            if bind.create {
+                create(target, bind.is_dir)?;
+            }
+            attach_tree(bind.tree.get(), target)?;
+            if bind.read_only {
+                set_read_only(target)?;
+            }
+        }
+        if self.proc {
+            mount(
+                Some(c"proc"),
+                c"/proc",
+                Some(c"proc"),
+                libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+                None,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl RootfsMount {
+    fn new(lower: PathBuf, dir: RootfsDir, isolation: &Isolation) -> Result<Self, Error> {
+        let lower = lower.to_string_lossy().into_owned();
+        // overlayfs takes these as separators in its options
+        if lower.contains([',', ':', '\\']) {
+            return Err(Error::JobStartErr(format!(
+                "root filesystem {} has a path overlayfs cannot mount",
+                lower
+            )));
+        }
+        let stage = dir.0.join("stage");
+        let path = |name: &str| cstring(stage.join(name).to_string_lossy().into_owned());
+        let mut options = format!(
+            "lowerdir={},upperdir={},workdir={}",
+            lower,
+            stage.join("upper").to_string_lossy(),
+            stage.join("work").to_string_lossy()
+        );
+        // the trusted xattrs overlayfs keeps its state in are out of reach
+        // in a user namespace
+        if isolation.user_namespace().is_some() {
+            options.push_str(",userxattr");
+        }
+        Ok(RootfsMount {
+            stage: path("")?,
+            upper: path("upper")?,
+            work: path("work")?,
+            root: path("root")?,
+            options: cstring(options)?,
+            devices: Default::default(),
+            dir,
+        })
+    }
+
+    // Mounts the overlay, its mount points included, without devices or
+    // setuid binaries.
+    unsafe fn mount(&self) -> Result<(), Errno> {
+        mount(
+            Some(c"tmpfs"),
+            &self.stage,
+            Some(c"tmpfs"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some(c"mode=700"),
+        )?;
+        for dir in [&self.upper, &self.work, &self.root] {
+            create(dir, true)?;
+        }
+        mount(
+            Some(c"overlay"),
+            &self.root,
+            Some(c"overlay"),
+            libc::MS_NOSUID | libc::MS_NODEV,
+            Some(&self.options),
+        )
+    }
+
+    // Makes the overlay the job's /, leaving nothing of the host's mounted.
+    unsafe fn pivot(&self) -> Result<(), Errno> {
+        if libc::chdir(self.root.as_ptr()) != 0 {
+            return Err(Errno::last());
+        }
+        // mount points the rootfs may lack, which only the overlay gets
+        for dir in [c"tmp", c"dev", c"proc"] {
+            create(dir, true)?;
+        }
+        syscall!(Sysno::pivot_root, c".".as_ptr(), c".".as_ptr())
+            .map_err(|e| Errno::from_i32(e.into_raw()))?;
+        // the old root is stacked on top of the new one until detached
+        if libc::umount2(c".".as_ptr(), libc::MNT_DETACH) != 0 {
+            return Err(Errno::last());
+        }
+        match libc::chdir(c"/".as_ptr()) {
+            0 => Ok(()),
+            _ => Err(Errno::last()),
+        }
+    }
+}
+
+// Clones the mount tree at path, to be attached with attach_tree.
+unsafe fn clone_tree(path: &CStr) -> Result<RawFd, Errno> {
+    syscall!(
+        Sysno::open_tree,
+        libc::AT_FDCWD,
+        path.as_ptr(),
+        OPEN_TREE_CLONE | libc::O_CLOEXEC as u32 | libc::AT_RECURSIVE as u32
+    )
+    .map(|tree| tree as RawFd)
+    .map_err(|e| Errno::from_i32(e.into_raw()))
+}
+
+unsafe fn attach_tree(tree: RawFd, target: &CStr) -> Result<(), Errno> {
+    let res = syscall!(
+        Sysno::move_mount,
+        tree,
+        c"".as_ptr(),
+        libc::AT_FDCWD,
+        target.as_ptr(),
+        MOVE_MOUNT_F_EMPTY_PATH
+    );
+    libc::close(tree);
+    res.map(|_| ()).map_err(|e| Errno::from_i32(e.into_raw()))
+}
+
+// Creates a directory or an empty file to mount over, unless path exists.
+unsafe fn create(path: &CStr, is_dir: bool) -> Result<(), Errno> {
+    let res = match is_dir {
+        true => libc::mkdir(path.as_ptr(), 0o755),
+        false => libc::mknod(path.as_ptr(), libc::S_IFREG | 0o644, 0),
+    };
+    match res {
+        0 => Ok(()),
+        _ if Errno::last() == Errno::EEXIST => Ok(()),
+        _ => Err(Errno::last()),
+    }
+}
+
+unsafe fn mount(
+    source: Option<&CStr>,
+    target: &CStr,
+    fstype: Option<&CStr>,
+    flags: libc::c_ulong,
+    data: Option<&CStr>,
+) -> Result<(), Errno> {
+    let ptr_or_null = |s: Option<&CStr>| s.map_or(ptr::null(), |s| s.as_ptr());
+    match libc::mount(
+        ptr_or_null(source),
+        target.as_ptr(),
+        ptr_or_null(fstype),
+        flags,
+        ptr_or_null(data).cast(),
+    ) {
+        0 => Ok(()),
+        _ => Err(Errno::last()),
+    }
+}
+
+// Switches to root of the user namespace, whichever ids it is mapped to, with
+// no supplementary groups where the namespace still allows dropping them.
+// Made as raw syscalls, as the libc wrappers wait for every thread of the
+// worker to follow, which the clone does not have.
+unsafe fn become_root() -> Result<(), Errno> {
+    let _ = syscall!(Sysno::setgroups, 0, 0);
+    syscall!(Sysno::setresgid, 0, 0, 0)
+        .and_then(|_| syscall!(Sysno::setresuid, 0, 0, 0))
+        .map(|_| ())
+        .map_err(|e| Errno::from_i32(e.into_raw()))
+}
+
+// Sets the hostname of the job's uts namespace.
+unsafe fn set_hostname(hostname: &CStr) -> Result<(), Errno> {
+    let hostname = hostname.to_bytes();
+    syscall!(Sysno::sethostname, hostname.as_ptr(), hostname.len())
+        .map(|_| ())
+        .map_err(|e| Errno::from_i32(e.into_raw()))
+}
+
+// Brings up loopback, the only interface of a new network namespace.
+unsafe fn loopback_up() -> Result<(), Errno> {
+    let socket = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+    if socket < 0 {
+        return Err(Errno::last());
+    }
+    let mut request: libc::ifreq = std::mem::zeroed();
+    for (name, byte) in request.ifr_name.iter_mut().zip(b"lo") {
+        *name = *byte as c_char;
+    }
+    request.ifr_ifru.ifru_flags = libc::IFF_UP as libc::c_short;
+    let res = match libc::ioctl(socket, libc::SIOCSIFFLAGS, &request) {
+        0 => Ok(()),
+        _ => Err(Errno::last()),
+    };
+    libc::close(socket);
+    res
+}
+
+// Makes the mount at path and every mount below it read-only.
+unsafe fn set_read_only(path: &CStr) -> Result<(), Errno> {
+    // struct mount_attr of mount_setattr(2)
+    let attr: [u64; 4] = [MOUNT_ATTR_RDONLY, 0, 0, 0];
+    syscall!(
+        Sysno::mount_setattr,
+        libc::AT_FDCWD,
+        path.as_ptr(),
+        libc::AT_RECURSIVE,
+        attr.as_ptr(),
+        std::mem::size_of_val(&attr)
+    )
+    .map(|_| ())
+    .map_err(|e| Errno::from_i32(e.into_raw()))
+}
+
+pub fn spawn(
+    command: &Command,
+    log_file: &File,
+    isolation: &Isolation,
+) -> Result<Sandboxed, Error> {
+    let exec = Exec::new(command)?;
+    let seccomp = isolation.seccomp().map(Filter::new).transpose()?;
+    let privileges = Privileges::new(isolation)?;
+    let landlock = isolation
+        .landlock()
+        .iter()
+        .map(Ruleset::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    let limits = isolation.rlimits().map(Limits::new);
+    let hostname = isolation
+        .hostname()
+        .map(|hostname| cstring(hostname.into()))
+        .transpose()?;
+    let mounts = match isolation.mount_namespace() {
+        true => Some(Mounts::new(command, isolation)?),
+        false => None,
+    };
+    // closed by a successful exec, or carries the step that failed and its
+    // errno
+    let (err_read, err_write) = pipe()?;
+    let (status_read, status_write) = match isolation.pid_namespace() {
+        true => {
+            let (read, write) = pipe()?;
+            (Some(read), Some(write))
+        }
+        false => (None, None),
+    };
+    // holds the child back until its user namespace is mapped
+    let sync = match isolation.user_namespace() {
+        Some(_) => Some(pipe()?),
+        None => None,
+    };
+
+    let mut flags = CloneFlags::empty();
+    if isolation.user_namespace().is_some() {
+        flags |= CloneFlags::CLONE_NEWUSER;
+    }
+    if isolation.pid_namespace() {
+        flags |= CloneFlags::CLONE_NEWPID;
+    }
+    if isolation.mount_namespace() {
+        flags |= CloneFlags::CLONE_NEWNS;
+    }
+    if !isolation.network().is_host() {
+        flags |= CloneFlags::CLONE_NEWNET;
+    }
+    if isolation.uts_namespace() {
+        flags |= CloneFlags::CLONE_NEWUTS;
+    }
+    let pid = match unsafe { fork(flags) }
+        .map_err(|e| Error::JobStartErr(format!("failed to spawn child process: {:?}", e)))?
+    {
+        0 => {
+            let child = Child {
+                exec: &exec,
+                mounts: mounts.as_ref(),
+                seccomp: seccomp.as_ref(),
+                privileges: &privileges,
+                landlock: &landlock,
+                limits: limits.as_ref(),
+                hostname: hostname.as_deref(),
+                loopback: isolation.network() == Network::Loopback,
+                log_fd: log_file.as_raw_fd(),
+                err_fd: err_write.as_raw_fd(),
+                status_fd: status_write.as_ref().map(|fd| fd.as_raw_fd()),
+                sync_fds: sync
+                    .as_ref()
+                    .map(|(read, write)| (read.as_raw_fd(), write.as_raw_fd())),
+            };
+            unsafe { child.run() }
+        }
+        pid => pid,
+    };
+    drop(err_write);
+    drop(status_write);
+    if let (Some(user_namespace), Some((_, sync_write))) = (isolation.user_namespace(), sync) {
+        // a child whose sync pipe is closed unwritten gives up
+        if let Err(e) = write_id_maps(pid, user_namespace) {
+            drop(sync_write);
+            let _ = waitpid(Pid::from_raw(pid as i32), None);
+            return Err(e);
+        }
+        let _ = File::from(sync_write).write_all(&[0]);
+    }
+
+    let mut report = [0u8; 8];
+    let mut err_read = File::from(err_read);
+    match err_read.read(&mut report) {
+        Ok(0) => Ok(Sandboxed {
+            pid: pid as u32,
+            status: status_read.map(File::from),
+            seccomp: seccomp.is_some(),
+            cpu_limit: isolation
+                .rlimits()
+                .and_then(|rlimits| rlimits.cpu())
+                .is_some(),
+            _rootfs: mounts
+                .and_then(|mounts| mounts.rootfs)
+                .map(|rootfs| rootfs.dir),
+        }),
+        res => {
+            let _ = waitpid(Pid::from_raw(pid as i32), None);
+            let (step, errno) = report.split_at(4);
+            let step = i32::from_ne_bytes(step.try_into().unwrap_or_default());
+            let errno = i32::from_ne_bytes(errno.try_into().unwrap_or_default());
+            Err(Error::JobStartErr(format!(
+                "{}: {:?}",
+                Step::describe(step),
+                match res {
+                    Ok(_) => std::io::Error::from_raw_os_error(errno),
+                    Err(e) => e,
+                }
+            )))
+        }
+    }
+}
+
+// Writes the uid and gid maps of the user namespace of the child pid. An
+// unprivileged worker can only map its own ids itself, anything else takes
+// the setuid newuidmap and newgidmap helpers, which check the maps against
+// /etc/subuid and /etc/subgid.
+fn write_id_maps(pid: usize, user_namespace: &UserNamespace) -> Result<(), Error> {
+    let map_err = |e: String| Error::JobStartErr(format!("failed to map the job's ids: {}", e));
+    let own_id =
+        |maps: &[IdMap], id: u32| matches!(maps, [map] if map.outside() == id && map.count() == 1);
+    let (uid_map, gid_map) = (user_namespace.uid_map(), user_namespace.gid_map());
+    let privileged = nix::unistd::geteuid().is_root();
+    if privileged
+        || (own_id(uid_map, nix::unistd::geteuid().as_raw())
+            && own_id(gid_map, nix::unistd::getegid().as_raw()))
+    {
+        let write = |file: &str, contents: String| {
+            std::fs::write(format!("/proc/{}/{}", pid, file), contents)
+                .map_err(|e| map_err(format!("{}: {:?}", file, e)))
+        };
+        let lines = |maps: &[IdMap]| {
+            maps.iter()
+                .map(|map| format!("{} {} {}\n", map.inside(), map.outside(), map.count()))
+                .collect::<String>()
+        };
+        // the gid map of an unprivileged worker is only accepted once the
+        // job can no longer drop groups with setgroups(2)
+        if !privileged {
+            write("setgroups", "deny".into())?;
+        }
+        write("gid_map", lines(gid_map))?;
+        return write("uid_map", lines(uid_map));
+    }
+    for (helper, maps) in [("newgidmap", gid_map), ("newuidmap", uid_map)] {
+        let output = std::process::Command::new(helper)
+            .arg(pid.to_string())
+            .args(maps.iter().flat_map(|map| {
+                [map.inside(), map.outside(), map.count()].map(|id| id.to_string())
+            }))
+            .output()
+            .map_err(|e| map_err(format!("{}: {:?}", helper, e)))?;
+        if !output.status.success() {
+            return Err(map_err(format!(
+                "{}: {}",
+                helper,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn cstring(s: String) -> Result<CString, Error> {
+    CString::new(s)
+        .map_err(|e| Error::JobStartErr(format!("failed to spawn child process: {:?}", e)))
+}
+
+fn pipe() -> Result<(OwnedFd, OwnedFd), Error> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(Error::JobStartErr(format!(
+            "failed to create pipe: {:?}",
+            Errno::last()
+        )));
+    }
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+// fork(2) with namespace flags, made as a raw syscall so that no atfork
+// handlers run: they take locks another thread of the worker may hold.
+unsafe fn fork(flags: CloneFlags) -> Result<usize, Errno> {
+    syscall!(Sysno::clone, flags.bits() | libc::SIGCHLD, 0, 0, 0, 0)
+        .map_err(|e| Errno::from_i32(e.into_raw()))
+}
+
+// The cloned side of spawn, which only makes system calls.
+struct Child<'a> {
+    exec: &'a Exec,
+    mounts: Option<&'a Mounts>,
+    seccomp: Option<&'a Filter>,
+    privileges: &'a Privileges,
+    landlock: &'a [Ruleset],
+    limits: Option<&'a Limits>,
+    hostname: Option<&'a CStr>,
+    loopback: bool,
+    log_fd: RawFd,
+    err_fd: RawFd,
+    status_fd: Option<RawFd>,
+    sync_fds: Option<(RawFd, RawFd)>,
+}
+
+impl Child<'_> {
+    unsafe fn run(&self) -> ! {
+        libc::dup2(self.log_fd, libc::STDOUT_FILENO);
+        libc::dup2(self.log_fd, libc::STDERR_FILENO);
+        if let Some((sync_read, sync_write)) = self.sync_fds {
+            libc::close(sync_write);
+            let mut mapped = 0u8;
+            if libc::read(sync_read, (&mut mapped as *mut u8).cast(), 1) != 1 {
+                libc::_exit(127)
+            }
+            libc::close(sync_read);
+            if let Err(errno) = become_root() {
+                self.fail(Step::UserNamespace, errno)
+            }
+        }
+        if let Some(Err(errno)) = self.mounts.map(|mounts| mounts.apply()) {
+            self.fail(Step::Mounts, errno)
+        }
+        if let Some(hostname) = self.hostname {
+            if let Err(errno) = set_hostname(hostname) {
+                self.fail(Step::Hostname, errno)
+            }
+        }
+        if self.loopback {
+            if let Err(errno) = loopback_up() {
+                self.fail(Step::Network, errno)
+            }
+        }
+        match self.status_fd {
+            Some(status_fd) => self.init(status_fd),
+            None => self.exec(),
+        }
+    }
+
+    unsafe fn fail(&self, step: Step, errno: Errno) -> ! {
+        let report = [step as i32, errno as i32];
+        libc::write(self.err_fd, report.as_ptr().cast(), 8);
+        libc::_exit(127)
+    }
+
+    unsafe fn exec(&self) -> ! {
+        // undo what the worker's runtime changed, as std does for its children
+        let _ = SigSet::empty().thread_set_mask();
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        // first, as raising a limit may take privileges dropped next
+        if let Some(Err(errno)) = self.limits.map(|limits| limits.apply()) {
+            self.fail(Step::Rlimits, errno)
+        }
+        if let Err(errno) = self.privileges.apply() {
+            self.fail(Step::Privileges, errno)
+        }
+        // each ruleset is a layer of its own, so the job only gets what
+        // every one of them grants
+        for ruleset in self.landlock {
+            if let Err(errno) = ruleset.apply() {
+                self.fail(Step::Landlock, errno)
+            }
+        }
+        // last, so that nothing but the exec has to pass the filter
+        if let Some(Err(errno)) = self.seccomp.map(|seccomp| seccomp.install()) {
+            self.fail(Step::Seccomp, errno)
+        }
+        libc::execvp(self.exec.program.as_ptr(), self.exec.argv.as_ptr());
+        self.fail(Step::Exec, Errno::last())
+    }
+
+    // Runs as pid 1 of the new namespace: forwards every signal it is sent to
+    // the job, reaps whatever gets orphaned to it, and exits once the job has.
+    unsafe fn init(&self, status_fd: RawFd) -> ! {
+        let all = SigSet::all();
+        let _ = nix::sys::signal::sigprocmask(SigmaskHow::SIG_SETMASK, Some(&all), None);
+        let job = match fork(CloneFlags::empty()) {
+            Ok(0) => {
+                libc::close(status_fd);
+                self.exec()
+            }
+            Ok(job) => job as libc::pid_t,
+            Err(errno) => self.fail(Step::Exec, errno),
+        };
+        // keep only the status pipe, the worker's descriptors are not the
+        // init's to hold open
+        libc::dup2(status_fd, 3);
+        let _ = syscall!(Sysno::close_range, 4, u32::MAX, 0);
+        libc::close(libc::STDIN_FILENO);
+
+        loop {
+            match all.wait() {
+                Ok(Signal::SIGCHLD) => loop {
+                    let mut status = 0;
+                    match libc::waitpid(-1, &mut status, libc::WNOHANG) {
+                        pid if pid <= 0 => break,
+                        pid if pid == job => {
+                            libc::write(3, status.to_ne_bytes().as_ptr().cast(), 4);
+                            libc::_exit(match libc::WIFEXITED(status) {
+                                true => libc::WEXITSTATUS(status),
+                                false => 128 + libc::WTERMSIG(status),
+                            })
+                        }
+                        _ => {}
+                    }
+                },
+                Ok(signal) => {
+                    libc::kill(job, signal as libc::c_int);
+                }
+                Err(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use super::{Executor, ExitCallback};
+use crate::{
+    job::{Command, Isolation, Status, StopType},
+    worker::Error,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+// How a scripted process behaves, chosen by the name of the spawned command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Script {
+    // exits with the given code as soon as it is waited on
+    Exit(Option<i32>),
+    // runs until it is signaled or exited through ScriptedExecutor::exit
+    Run,
+    // accepts signals but keeps running until exited through ScriptedExecutor::exit
+    IgnoreSignals,
+    // fails to spawn with the given error message
+    FailSpawn(String),
+}
+
+// Executor that never touches the host: processes only exist in memory and
+// play back the Script registered for their command name. Commands without
+// a script exit with 0 straight away.
+#[derive(Clone, Default)]
+pub struct ScriptedExecutor {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    scripts: HashMap<String, Script>,
+    next_pid: u32,
+    processes: HashMap<u32, Process>,
+    signals: Vec<(u32, usize)>,
+}
+
+struct Process {
+    script: Script,
+    start_time: u64,
+    exit_code: Option<Option<i32>>,
+    on_exit: Option<ExitCallback>,
+}
+
+impl ScriptedExecutor {
+    pub fn new() -> Self {
+        ScriptedExecutor::default()
+    }
+
+    pub fn script(&self, command_name: &str, script: Script) {
+        self.state
+            .lock()
+            .unwrap()
+            .scripts
+            .insert(command_name.into(), script);
+    }
+
+    // Makes the process exit with exit_code, returning false when it had
+    // already exited or never existed.
+    pub fn exit(&self, pid: u32, exit_code: Option<i32>) -> bool {
+        let on_exit = {
+            let mut state = self.state.lock().unwrap();
+            match state.processes.get_mut(&pid) {
+                Some(process) if process.exit_code.is_none() => {
+                    process.exit_code = Some(exit_code);
+                    process.on_exit.take()
+                }
+                _ => return false,
+            }
+        };
+        if let Some(on_exit) = on_exit {
+            on_exit(Ok(Status::Exited(exit_code)));
+        }
+        true
+    }
+
+    pub fn signals(&self) -> Vec<(u32, usize)> {
+        self.state.lock().unwrap().signals.clone()
+    }
+
+    pub fn running(&self) -> Vec<u32> {
+        let state = self.state.lock().unwrap();
+        let mut pids: Vec<u32> = state
+            .processes
+            .iter()
+            .filter(|(_, process)| process.exit_code.is_none())
+            .map(|(pid, _)| *pid)
+            .collect();
+        pids.sort();
+        pids
+    }
+}
+
+impl Executor for ScriptedExecutor {
+    type Process = u32;
+
+    fn isolation(&self, _job_id: Uuid, _command: &Command) -> Isolation {
+        Isolation::default()
+    }
+
+    fn spawn(
+        &self,
+        _job_id: Uuid,
+        command: &Command,
+        _log_file: &File,
+    ) -> Result<(u32, u32), Error> {
+        let mut state = self.state.lock().unwrap();
+        let script = state
+            .scripts
+            .get(&command.name())
+            .cloned()
+            .unwrap_or(Script::Exit(Some(0)));
+        if let Script::FailSpawn(e) = script {
+            return Err(Error::JobStartErr(e));
+        }
+        state.next_pid += 1;
+        let pid = state.next_pid;
+        let exit_code = match script {
+            Script::Exit(exit_code) => Some(exit_code),
+            _ => None,
+        };
+        state.processes.insert(
+            pid,
+            Process {
+                script,
+                start_time: pid as u64,
+                exit_code,
+                on_exit: None,
+            },
+        );
+        Ok((pid, pid))
+    }
+
+    fn start_time(&self, pid: u32) -> Option<u64> {
+        let state = self.state.lock().unwrap();
+        state
+            .processes
+            .get(&pid)
+            .filter(|process| process.exit_code.is_none())
+            .map(|process| process.start_time)
+    }
+
+    fn signal(&self, _job_id: Uuid, pid: u32, signal: usize) -> Result<(), String> {
+        let on_exit = {
+            let mut state = self.state.lock().unwrap();
+            state.signals.push((pid, signal));
+            match state.processes.get_mut(&pid) {
+                Some(process) if process.exit_code.is_none() => {
+                    // only the stop signals end a scripted process
+                    let stops = [StopType::Term.sig(), StopType::Kill.sig()].contains(&signal);
+                    if !stops || process.script == Script::IgnoreSignals {
+                        return Ok(());
+                    }
+                    process.exit_code = Some(None);
+                    process.on_exit.take()
+                }
+                _ => return Err("ESRCH".into()),
+            }
+        };
+        if let Some(on_exit) = on_exit {
+            on_exit(Ok(Status::Exited(None)));
+        }
+        Ok(())
+    }
+
+    fn wait(&self, _job_id: Uuid, pid: u32, on_exit: ExitCallback) {
+        let exit_code = {
+            let mut state = self.state.lock().unwrap();
+            match state.processes.get_mut(&pid) {
+                Some(process) => match process.exit_code {
+                    Some(exit_code) => exit_code,
+                    None => {
+                        process.on_exit = Some(on_exit);
+                        return;
+                    }
+                },
+                None => {
+                    drop(state);
+                    on_exit(Err(Error::WorkerErr(format!(
+                        "no process with pid {}",
+                        pid
+                    ))));
+                    return;
+                }
+            }
+        };
+        on_exit(Ok(Status::Exited(exit_code)));
+    }
+
+    fn adopt(
+        &self,
+        _job_id: Uuid,
+        pid: u32,
+        start_time: Option<u64>,
+        on_exit: ExitCallback,
+    ) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.processes.get_mut(&pid) {
+            Some(process)
+                if process.exit_code.is_none() && Some(process.start_time) == start_time =>
+            {
+                process.on_exit = Some(on_exit);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn join(&self) {}
+}
@@ -0,0 +1,174 @@
+use crate::{
+    job::{Seccomp, SeccompProfile},
+    worker::Error,
+};
+use nix::{errno::Errno, libc};
+use syscalls::Sysno;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc00000b7;
+// x32 system calls share the x86_64 audit arch, so they are told apart by
+// this bit of their number
+#[cfg(target_arch = "x86_64")]
+const X32_SYSCALL_BIT: u32 = 0x40000000;
+
+// every CLONE_NEW* flag clone(2) takes; CLONE_NEWTIME shares its bit with
+// the exit signal there and is only taken by clone3(2) and unshare(2)
+const CLONE_NEW: u32 = (libc::CLONE_NEWNS
+    | libc::CLONE_NEWCGROUP
+    | libc::CLONE_NEWUTS
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUSER
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWNET) as u32;
+
+// offsets into struct seccomp_data
+const NR: u32 = 0;
+const ARCH: u32 = 4;
+#[cfg(target_endian = "little")]
+const ARG0: u32 = 16;
+#[cfg(target_endian = "big")]
+const ARG0: u32 = 20;
+
+// A seccomp filter compiled to classic BPF, which kills the job on the first
+// system call it denies.
+pub struct Filter(Vec<libc::sock_filter>);
+
+impl Filter {
+    pub fn new(seccomp: &Seccomp) -> Result<Self, Error> {
+        let mut filter = Filter(vec![]);
+        // anything but the worker's own architecture could bypass the filter
+        filter.load(ARCH);
+        filter.jump(libc::BPF_JEQ, AUDIT_ARCH, 1, 0);
+        filter.ret(libc::SECCOMP_RET_KILL_PROCESS);
+        filter.load(NR);
+        #[cfg(target_arch = "x86_64")]
+        {
+            filter.jump(libc::BPF_JGE, X32_SYSCALL_BIT, 0, 1);
+            filter.ret(libc::SECCOMP_RET_KILL_PROCESS);
+        }
+        match seccomp {
+            Seccomp::Profile(profile) => {
+                // some calls only exist on some architectures
+                for sysno in profile
+                    .denied()
+                    .iter()
+                    .filter_map(|name| name.parse::<Sysno>().ok())
+                {
+                    filter.deny(sysno);
+                }
+                if *profile == SeccompProfile::DefaultDenyDangerous {
+                    filter.deny_namespace_clones();
+                }
+                if *profile == SeccompProfile::NoNetworkSyscalls {
+                    filter.deny_sockets();
+                }
+                filter.ret(libc::SECCOMP_RET_ALLOW);
+            }
+            Seccomp::Allow(names) => {
+                filter.allow(Sysno::execve);
+                for sysno in sysnos(names)? {
+                    filter.allow(sysno);
+                }
+                filter.ret(libc::SECCOMP_RET_KILL_PROCESS);
+            }
+            Seccomp::Deny(names) => {
+                for sysno in sysnos(names)? {
+                    filter.deny(sysno);
+                }
+                filter.ret(libc::SECCOMP_RET_ALLOW);
+            }
+        }
+        Ok(filter)
+    }
+
+    // Attaches the filter to the calling thread, which then can no longer
+    // gain privileges through execve(2) either.
+    pub unsafe fn install(&self) -> Result<(), Errno> {
+        let prog = libc::sock_fprog {
+            len: self.0.len() as u16,
+            filter: self.0.as_ptr() as *mut _,
+        };
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(Errno::last());
+        }
+        match libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &prog) {
+            0 => Ok(()),
+            _ => Err(Errno::last()),
+        }
+    }
+
+    fn load(&mut self, offset: u32) {
+        self.0.push(libc::sock_filter {
+            code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+            jt: 0,
+            jf: 0,
+            k: offset,
+        });
+    }
+
+    fn jump(&mut self, op: u32, k: u32, jt: u8, jf: u8) {
+        self.0.push(libc::sock_filter {
+            code: (libc::BPF_JMP | op | libc::BPF_K) as u16,
+            jt,
+            jf,
+            k,
+        });
+    }
+
+    fn ret(&mut self, action: u32) {
+        self.0.push(libc::sock_filter {
+            code: (libc::BPF_RET | libc::BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: action,
+        });
+    }
+
+    fn allow(&mut self, sysno: Sysno) {
+        self.jump(libc::BPF_JEQ, sysno.id() as u32, 0, 1);
+        self.ret(libc::SECCOMP_RET_ALLOW);
+    }
+
+    fn deny(&mut self, sysno: Sysno) {
+        self.jump(libc::BPF_JEQ, sysno.id() as u32, 0, 1);
+        self.ret(libc::SECCOMP_RET_KILL_PROCESS);
+    }
+
+    // Denies clone(2) into any new namespace, which unshare(2) being denied
+    // does not cover. clone3(2) passes its flags in memory the filter cannot
+    // read, so it fails with ENOSYS instead, which makes libc fall back to
+    // clone(2).
+    fn deny_namespace_clones(&mut self) {
+        self.jump(libc::BPF_JEQ, Sysno::clone.id() as u32, 0, 4);
+        self.load(ARG0);
+        self.jump(libc::BPF_JSET, CLONE_NEW, 0, 1);
+        self.ret(libc::SECCOMP_RET_KILL_PROCESS);
+        self.load(NR);
+        self.jump(libc::BPF_JEQ, Sysno::clone3.id() as u32, 0, 1);
+        self.ret(libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32);
+    }
+
+    // Denies socket(2) for every domain but AF_UNIX. The calls that use a
+    // socket need not be filtered, as there is no other way to make one.
+    fn deny_sockets(&mut self) {
+        self.jump(libc::BPF_JEQ, Sysno::socket.id() as u32, 0, 4);
+        self.load(ARG0);
+        self.jump(libc::BPF_JEQ, libc::AF_UNIX as u32, 1, 0);
+        self.ret(libc::SECCOMP_RET_KILL_PROCESS);
+        self.load(NR);
+    }
+}
+
+fn sysnos(names: &[String]) -> Result<Vec<Sysno>, Error> {
+    names
+        .iter()
+        .map(|name| {
+            name.parse::<Sysno>().map_err(|_| {
+                Error::JobStartErr(format!("unknown system call in seccomp filter: {:?}", name))
+            })
+        })
+        .collect()
+}
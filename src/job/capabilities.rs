@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+
+// Names of the Linux capabilities, indexed by their number.
+pub const CAPABILITY_NAMES: [&str; 41] = [
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_DAC_READ_SEARCH",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETPCAP",
+    "CAP_LINUX_IMMUTABLE",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_NET_BROADCAST",
+    "CAP_NET_ADMIN",
+    "CAP_NET_RAW",
+    "CAP_IPC_LOCK",
+    "CAP_IPC_OWNER",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_CHROOT",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_PACCT",
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_NICE",
+    "CAP_SYS_RESOURCE",
+    "CAP_SYS_TIME",
+    "CAP_SYS_TTY_CONFIG",
+    "CAP_MKNOD",
+    "CAP_LEASE",
+    "CAP_AUDIT_WRITE",
+    "CAP_AUDIT_CONTROL",
+    "CAP_SETFCAP",
+    "CAP_MAC_OVERRIDE",
+    "CAP_MAC_ADMIN",
+    "CAP_SYSLOG",
+    "CAP_WAKE_ALARM",
+    "CAP_BLOCK_SUSPEND",
+    "CAP_AUDIT_READ",
+    "CAP_PERFMON",
+    "CAP_BPF",
+    "CAP_CHECKPOINT_RESTORE",
+];
+
+// Capability sets a job is started with, by name. A set that is not given is
+// left as the job inherits it from the worker. They are applied right before
+// the job is executed, after which the kernel's rules for execve(2) apply: a
+// job running as root regains its whole bounding set, any other job keeps
+// only its ambient capabilities.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    // the most the job can ever hold, e.g. through setuid binaries; every
+    // other capability is dropped for good
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bounding: Option<Vec<String>>,
+    // what the job holds when it is executed, which also limits its permitted
+    // set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    effective: Option<Vec<String>>,
+    // what the job keeps across execve(2) without being root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ambient: Option<Vec<String>>,
+}
+
+impl Capabilities {
+    pub fn with_bounding(mut self, bounding: Vec<String>) -> Self {
+        self.bounding = Some(bounding);
+        self
+    }
+
+    pub fn with_effective(mut self, effective: Vec<String>) -> Self {
+        self.effective = Some(effective);
+        self
+    }
+
+    pub fn with_ambient(mut self, ambient: Vec<String>) -> Self {
+        self.ambient = Some(ambient);
+        self
+    }
+
+    pub fn bounding(&self) -> Option<&[String]> {
+        self.bounding.as_deref()
+    }
+
+    pub fn effective(&self) -> Option<&[String]> {
+        self.effective.as_deref()
+    }
+
+    pub fn ambient(&self) -> Option<&[String]> {
+        self.ambient.as_deref()
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        self.bounding.is_some() || self.effective.is_some() || self.ambient.is_some()
+    }
+
+    // Intersects every set with the one other gives, so that other can only
+    // take capabilities away, and bounds the effective and ambient sets by
+    // the bounding set, which a job could otherwise hold until it is
+    // executed.
+    pub fn restrict(&self, other: &Capabilities) -> Capabilities {
+        let intersect = |own: &Option<Vec<String>>, other: &Option<Vec<String>>| match (own, other)
+        {
+            (Some(own), Some(other)) => Some(
+                own.iter()
+                    .filter(|name| other.contains(name))
+                    .cloned()
+                    .collect(),
+            ),
+            (own, other) => own.clone().or_else(|| other.clone()),
+        };
+        let bounding = intersect(&self.bounding, &other.bounding);
+        let bound = |set: Option<Vec<String>>| match (set, &bounding) {
+            (Some(set), Some(bounding)) => Some(
+                set.into_iter()
+                    .filter(|name| bounding.contains(name))
+                    .collect(),
+            ),
+            (set, _) => set,
+        };
+        Capabilities {
+            effective: bound(intersect(&self.effective, &other.effective)),
+            ambient: bound(intersect(&self.ambient, &other.ambient)),
+            bounding,
+        }
+    }
+}
@@ -1,12 +1,118 @@
-#[derive(Clone, Debug)]
+use super::{Capabilities, Network, Rlimits, Seccomp};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Command {
     name: String,
     args: Vec<String>,
+    // a mount namespace with a private /tmp, even when the worker gives jobs
+    // none, and its root remounted read-only with read_only_root
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    mount_namespace: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    read_only_root: bool,
+    // bind mounts into the job's mount namespace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mounts: Vec<Mount>,
+    // the least isolated network the job may run with
+    #[serde(default, skip_serializing_if = "Network::is_host")]
+    network: Network,
+    // root filesystem to pivot the job into, which also needs a mount
+    // namespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rootfs: Option<Rootfs>,
+    // system call filter attached to the job before it is executed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seccomp: Option<Seccomp>,
+    // capability sets the job is started with, within the worker's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capabilities: Option<Capabilities>,
+    // keep the job from gaining privileges through execve(2)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    no_new_privs: bool,
+    // landlock ruleset confining the job to the paths it lists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    landlock: Option<Landlock>,
+    // resource limits set right before the job is executed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rlimits: Option<Rlimits>,
+    // hostname of the job, which gives it a uts namespace of its own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
 }
 
 impl Command {
     pub fn new(name: String, args: Vec<String>) -> Self {
-        Command { name, args }
+        Command {
+            name,
+            args,
+            mount_namespace: false,
+            read_only_root: false,
+            mounts: vec![],
+            network: Network::Host,
+            rootfs: None,
+            seccomp: None,
+            capabilities: None,
+            no_new_privs: false,
+            landlock: None,
+            rlimits: None,
+            hostname: None,
+        }
+    }
+
+    pub fn with_mount_namespace(mut self) -> Self {
+        self.mount_namespace = true;
+        self
+    }
+
+    pub fn with_read_only_root(mut self) -> Self {
+        self.read_only_root = true;
+        self
+    }
+
+    pub fn with_mounts(mut self, mounts: Vec<Mount>) -> Self {
+        self.mounts = mounts;
+        self
+    }
+
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn with_rootfs(mut self, rootfs: Rootfs) -> Self {
+        self.rootfs = Some(rootfs);
+        self
+    }
+
+    pub fn with_seccomp(mut self, seccomp: Seccomp) -> Self {
+        self.seccomp = Some(seccomp);
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = Some(capabilities);
+        self
+    }
+
+    pub fn with_no_new_privs(mut self) -> Self {
+        self.no_new_privs = true;
+        self
+    }
+
+    pub fn with_landlock(mut self, landlock: Landlock) -> Self {
+        self.landlock = Some(landlock);
+        self
+    }
+
+    pub fn with_rlimits(mut self, rlimits: Rlimits) -> Self {
+        self.rlimits = Some(rlimits);
+        self
+    }
+
+    pub fn with_hostname(mut self, hostname: String) -> Self {
+        self.hostname = Some(hostname);
+        self
     }
 
     pub fn name(&self) -> String {
@@ -16,4 +122,168 @@ impl Command {
     pub fn args(&self) -> Vec<String> {
         self.args.clone()
     }
+
+    pub fn mount_namespace(&self) -> bool {
+        self.mount_namespace || self.read_only_root
+    }
+
+    pub fn read_only_root(&self) -> bool {
+        self.read_only_root
+    }
+
+    pub fn mounts(&self) -> Vec<Mount> {
+        self.mounts.clone()
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn rootfs(&self) -> Option<Rootfs> {
+        self.rootfs.clone()
+    }
+
+    pub fn seccomp(&self) -> Option<Seccomp> {
+        self.seccomp.clone()
+    }
+
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.capabilities.clone()
+    }
+
+    pub fn no_new_privs(&self) -> bool {
+        self.no_new_privs
+    }
+
+    pub fn landlock(&self) -> Option<Landlock> {
+        self.landlock.clone()
+    }
+
+    pub fn rlimits(&self) -> Option<Rlimits> {
+        self.rlimits.clone()
+    }
+
+    pub fn hostname(&self) -> Option<String> {
+        self.hostname.clone()
+    }
+}
+
+// Root filesystem on the worker's host: a prepared directory, shared by every
+// job started in it, or a tar archive unpacked for the job alone.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rootfs {
+    Directory(String),
+    Tarball(String),
+}
+
+// Landlock ruleset of a job: it may read and execute beneath the read-only
+// paths, do anything beneath the read-write ones, and nothing anywhere else.
+// Paths are resolved as the job sees them, e.g. inside its root filesystem.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Landlock {
+    #[serde(default)]
+    read_only: Vec<String>,
+    #[serde(default)]
+    read_write: Vec<String>,
+    // what to do when the worker's kernel lacks landlock
+    #[serde(default)]
+    unsupported: LandlockFallback,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LandlockFallback {
+    // refuse to start the job
+    #[default]
+    Fail,
+    // start the job without the ruleset, logging a warning
+    Warn,
+}
+
+impl Landlock {
+    pub fn new(read_only: Vec<String>, read_write: Vec<String>) -> Self {
+        Landlock {
+            read_only,
+            read_write,
+            unsupported: LandlockFallback::Fail,
+        }
+    }
+
+    pub fn with_unsupported(mut self, unsupported: LandlockFallback) -> Self {
+        self.unsupported = unsupported;
+        self
+    }
+
+    pub fn read_only(&self) -> &[String] {
+        &self.read_only
+    }
+
+    pub fn read_write(&self) -> &[String] {
+        &self.read_write
+    }
+
+    pub fn unsupported(&self) -> LandlockFallback {
+        self.unsupported
+    }
+}
+
+impl LandlockFallback {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LandlockFallback::Fail => "fail",
+            LandlockFallback::Warn => "warn",
+        }
+    }
+}
+
+impl std::str::FromStr for LandlockFallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "fail" => Ok(LandlockFallback::Fail),
+            "warn" => Ok(LandlockFallback::Warn),
+            _ => Err(format!(
+                "unknown landlock fallback {:?}, expected fail or warn",
+                s
+            )),
+        }
+    }
+}
+
+// Bind mount of a host path over a path the job sees.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mount {
+    source: String,
+    target: String,
+    #[serde(default)]
+    read_only: bool,
+}
+
+impl Mount {
+    pub fn new(source: String, target: String) -> Self {
+        Mount {
+            source,
+            target,
+            read_only: false,
+        }
+    }
+
+    pub fn with_read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
 }
@@ -0,0 +1,458 @@
+use super::{Capabilities, Command, Landlock, Rlimits, Seccomp};
+use crate::worker::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+pub const SUBUID_PATH: &str = "/etc/subuid";
+pub const SUBGID_PATH: &str = "/etc/subgid";
+
+// Kernel-level isolation of the jobs a NativeExecutor spawns. Nothing is
+// isolated by default, so jobs run like any other child of the worker.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Isolation {
+    // run each job under a minimal init as pid 1 of its own pid namespace
+    #[serde(default)]
+    pid_namespace: bool,
+    // give each job a mount namespace with a private tmpfs on /tmp, and the
+    // bind mounts of its command
+    #[serde(default)]
+    mount_namespace: bool,
+    // make every mount the job inherits read-only, which implies a mount
+    // namespace
+    #[serde(default)]
+    read_only_root: bool,
+    #[serde(default)]
+    network: Network,
+    // give each job a uts namespace, so that it can have a hostname of its
+    // own
+    #[serde(default)]
+    uts_namespace: bool,
+    // hostname of the job's uts namespace, which for_command takes from its
+    // command or derives from its job id
+    #[serde(default)]
+    hostname: Option<String>,
+    // create a user namespace before any other, which lets an unprivileged
+    // worker create them too
+    #[serde(default)]
+    user_namespace: Option<UserNamespace>,
+    // system call filter every job is started with, which for_command only
+    // replaces with a command's filter that is at least as strict
+    #[serde(default)]
+    seccomp: Option<Seccomp>,
+    // capability sets every job is started with at most
+    #[serde(default)]
+    capabilities: Capabilities,
+    // keep jobs from gaining privileges through execve(2), e.g. of setuid
+    // binaries
+    #[serde(default)]
+    no_new_privs: bool,
+    // landlock rulesets every job is confined by, to which for_command adds
+    // its command's, so that the kernel only grants what all of them do
+    #[serde(default)]
+    landlock: Vec<Landlock>,
+    // resource limits every job is started with at most, which for_command
+    // lowers to those of its command
+    #[serde(default)]
+    rlimits: Option<Rlimits>,
+}
+
+// uid and gid maps of a job's user namespace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserNamespace {
+    uid_map: Vec<IdMap>,
+    gid_map: Vec<IdMap>,
+}
+
+// count ids from outside, the worker's view, mapped to ids from inside in the
+// job's user namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IdMap {
+    inside: u32,
+    outside: u32,
+    count: u32,
+}
+
+// Network a job is given, ordered from least to most isolated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Network {
+    // the worker's own network
+    #[default]
+    Host,
+    // a network namespace of its own with nothing but loopback up
+    Loopback,
+}
+
+impl Network {
+    pub fn is_host(&self) -> bool {
+        *self == Network::Host
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Host => "host",
+            Network::Loopback => "loopback",
+        }
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "host" => Ok(Network::Host),
+            "loopback" => Ok(Network::Loopback),
+            _ => Err(format!(
+                "unknown network {:?}, expected host or loopback",
+                s
+            )),
+        }
+    }
+}
+
+impl Isolation {
+    pub fn with_pid_namespace(mut self) -> Self {
+        self.pid_namespace = true;
+        self
+    }
+
+    pub fn with_mount_namespace(mut self) -> Self {
+        self.mount_namespace = true;
+        self
+    }
+
+    pub fn with_read_only_root(mut self) -> Self {
+        self.read_only_root = true;
+        self
+    }
+
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.network = network;
+        self
+    }
+
+    pub fn with_uts_namespace(mut self) -> Self {
+        self.uts_namespace = true;
+        self
+    }
+
+    // Sets the hostname of a job's uts namespace, which implies one. Without
+    // it, each job is named after its job id.
+    pub fn with_hostname(mut self, hostname: String) -> Self {
+        self.uts_namespace = true;
+        self.hostname = Some(hostname);
+        self
+    }
+
+    pub fn with_user_namespace(mut self, user_namespace: UserNamespace) -> Self {
+        self.user_namespace = Some(user_namespace);
+        self
+    }
+
+    pub fn with_seccomp(mut self, seccomp: Seccomp) -> Self {
+        self.seccomp = Some(seccomp);
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn with_no_new_privs(mut self) -> Self {
+        self.no_new_privs = true;
+        self
+    }
+
+    pub fn with_landlock(mut self, landlock: Landlock) -> Self {
+        self.landlock.push(landlock);
+        self
+    }
+
+    pub fn with_rlimits(mut self, rlimits: Rlimits) -> Self {
+        self.rlimits = Some(rlimits);
+        self
+    }
+
+    // Drops the landlock rulesets, for a job started without them on a kernel
+    // that lacks landlock.
+    pub fn without_landlock(mut self) -> Self {
+        self.landlock.clear();
+        self
+    }
+
+    pub fn pid_namespace(&self) -> bool {
+        self.pid_namespace
+    }
+
+    pub fn mount_namespace(&self) -> bool {
+        self.mount_namespace || self.read_only_root
+    }
+
+    pub fn read_only_root(&self) -> bool {
+        self.read_only_root
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn uts_namespace(&self) -> bool {
+        self.uts_namespace
+    }
+
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    pub fn user_namespace(&self) -> Option<&UserNamespace> {
+        self.user_namespace.as_ref()
+    }
+
+    pub fn seccomp(&self) -> Option<&Seccomp> {
+        self.seccomp.as_ref()
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn no_new_privs(&self) -> bool {
+        self.no_new_privs
+    }
+
+    pub fn landlock(&self) -> &[Landlock] {
+        &self.landlock
+    }
+
+    pub fn rlimits(&self) -> Option<&Rlimits> {
+        self.rlimits.as_ref()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.pid_namespace
+            || self.mount_namespace()
+            || !self.network.is_host()
+            || self.uts_namespace
+            || self.user_namespace.is_some()
+            || self.seccomp.is_some()
+            || self.capabilities.is_restricted()
+            || self.no_new_privs
+            || !self.landlock.is_empty()
+            || self.rlimits.is_some()
+    }
+
+    // The isolation job_id's command gets: the worker's, tightened by what
+    // the command asks for but never loosened.
+    pub fn for_command(&self, job_id: Uuid, command: &Command) -> Isolation {
+        let mut isolation = self.clone();
+        isolation.mount_namespace |= command.mount_namespace();
+        isolation.read_only_root |= command.read_only_root();
+        isolation.network = isolation.network.max(command.network());
+        if let Some(hostname) = command.hostname() {
+            isolation = isolation.with_hostname(hostname);
+        } else if isolation.uts_namespace && isolation.hostname.is_none() {
+            isolation.hostname = Some(job_id.to_string());
+        }
+        if let Some(seccomp) = command.seccomp().filter(|seccomp| {
+            self.seccomp
+                .as_ref()
+                .is_none_or(|worker| seccomp.is_as_strict_as(worker))
+        }) {
+            isolation.seccomp = Some(seccomp);
+        }
+        isolation.capabilities = isolation
+            .capabilities
+            .restrict(&command.capabilities().unwrap_or_default());
+        isolation.no_new_privs |= command.no_new_privs();
+        if let Some(landlock) = command.landlock() {
+            isolation.landlock.push(landlock);
+        }
+        if let Some(rlimits) = command.rlimits() {
+            isolation.rlimits = match &self.rlimits {
+                Some(worker) => Some(worker.restrict(&rlimits)),
+                None => Some(rlimits),
+            }
+            .filter(Rlimits::is_limited);
+        }
+        isolation
+    }
+}
+
+impl fmt::Display for Isolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut levels = vec![];
+        if self.user_namespace.is_some() {
+            levels.push("user".to_string());
+        }
+        if self.pid_namespace {
+            levels.push("pid".to_string());
+        }
+        match (self.mount_namespace(), self.read_only_root) {
+            (true, true) => levels.push("mount (read-only root)".into()),
+            (true, false) => levels.push("mount".into()),
+            _ => {}
+        }
+        if !self.network.is_host() {
+            levels.push(format!("network ({})", self.network.as_str()));
+        }
+        match (self.uts_namespace, &self.hostname) {
+            (true, Some(hostname)) => levels.push(format!("uts ({})", hostname)),
+            (true, None) => levels.push("uts".into()),
+            _ => {}
+        }
+        if let Some(seccomp) = &self.seccomp {
+            levels.push(format!("seccomp ({})", seccomp));
+        }
+        if self.capabilities.is_restricted() {
+            levels.push("capabilities".into());
+        }
+        if self.no_new_privs {
+            levels.push("no new privileges".into());
+        }
+        if !self.landlock.is_empty() {
+            levels.push("landlock".into());
+        }
+        if self.rlimits.is_some() {
+            levels.push("resource limits".into());
+        }
+        match levels.is_empty() {
+            true => write!(f, "none"),
+            false => write!(f, "{}", levels.join(", ")),
+        }
+    }
+}
+
+impl UserNamespace {
+    // Maps the worker's own uid and gid to root, which is all an unprivileged
+    // worker may map without help.
+    pub fn new() -> Self {
+        UserNamespace {
+            uid_map: vec![IdMap::new(0, nix::unistd::getuid().as_raw(), 1)],
+            gid_map: vec![IdMap::new(0, nix::unistd::getgid().as_raw(), 1)],
+        }
+    }
+
+    pub fn with_uid_map(mut self, uid_map: Vec<IdMap>) -> Self {
+        self.uid_map = uid_map;
+        self
+    }
+
+    pub fn with_gid_map(mut self, gid_map: Vec<IdMap>) -> Self {
+        self.gid_map = gid_map;
+        self
+    }
+
+    // Maps the worker's own ids to root as new() does, followed from 1 on by
+    // the subordinate ranges user is given in subuid and subgid, the format
+    // of /etc/subuid and /etc/subgid.
+    pub fn with_subordinate_ids(
+        mut self,
+        user: &str,
+        subuid: &str,
+        subgid: &str,
+    ) -> Result<Self, Error> {
+        let uid = nix::unistd::getuid().as_raw();
+        let gid = nix::unistd::getgid().as_raw();
+        self.uid_map = subordinate_ids(subuid, user, uid)?;
+        self.gid_map = subordinate_ids(subgid, user, gid)?;
+        Ok(self)
+    }
+
+    pub fn uid_map(&self) -> &[IdMap] {
+        &self.uid_map
+    }
+
+    pub fn gid_map(&self) -> &[IdMap] {
+        &self.gid_map
+    }
+}
+
+impl Default for UserNamespace {
+    fn default() -> Self {
+        UserNamespace::new()
+    }
+}
+
+// Reads the ranges given to user, named or by id, from a subordinate id file
+// and maps them after id as root.
+fn subordinate_ids(path: &str, user: &str, id: u32) -> Result<Vec<IdMap>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::WorkerErr(format!("failed to read {}: {:?}", path, e)))?;
+    let malformed =
+        |line: &str| Error::WorkerErr(format!("malformed line in {}: {:?}", path, line));
+    let mut maps = vec![IdMap::new(0, id, 1)];
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let [name, outside, count] = line.split(':').collect::<Vec<_>>()[..] else {
+            return Err(malformed(line));
+        };
+        if name != user && name != id.to_string() {
+            continue;
+        }
+        let (Ok(outside), Ok(count)) = (outside.parse(), count.parse()) else {
+            return Err(malformed(line));
+        };
+        let inside = maps
+            .iter()
+            .map(|map| map.inside + map.count)
+            .max()
+            .unwrap_or(0);
+        maps.push(IdMap::new(inside, outside, count));
+    }
+    match maps.len() {
+        1 => Err(Error::WorkerErr(format!(
+            "{} has no subordinate ids for {}",
+            path, user
+        ))),
+        _ => Ok(maps),
+    }
+}
+
+impl IdMap {
+    pub fn new(inside: u32, outside: u32, count: u32) -> Self {
+        IdMap {
+            inside,
+            outside,
+            count,
+        }
+    }
+
+    pub fn inside(&self) -> u32 {
+        self.inside
+    }
+
+    pub fn outside(&self) -> u32 {
+        self.outside
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+impl fmt::Display for IdMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.inside, self.outside, self.count)
+    }
+}
+
+// Parses inside:outside:count, as IdMap is displayed.
+impl std::str::FromStr for IdMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let ids = s
+            .split(':')
+            .map(|id| id.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>();
+        match ids.as_deref() {
+            Ok([inside, outside, count]) => Ok(IdMap::new(*inside, *outside, *count)),
+            _ => Err(format!("expected inside:outside:count, got {:?}", s)),
+        }
+    }
+}
@@ -1,29 +1,96 @@
+mod capabilities;
 mod command;
+mod isolation;
+mod process;
+mod rlimits;
+mod seccomp;
 mod status;
+pub use capabilities::*;
 pub use command::*;
-use crossbeam::channel::Sender;
+pub use isolation::*;
+pub use process::*;
+pub use rlimits::*;
+pub use seccomp::*;
+use serde::{Deserialize, Serialize};
 pub use status::*;
-use syscalls::{syscall, Sysno};
 
+use crate::executor::Executor;
+use nix::sys::signal::Signal;
 use std::{
     fs::File,
     io::BufReader,
-    os::fd::FromRawFd,
-    process::Stdio,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 use uuid::Uuid;
 
-#[derive(Debug)]
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Where job_id's log is written. Only the file name of the command is used,
+// so that a name with directories in it cannot place the log outside of
+// log_dir.
+pub fn log_path(log_dir: &str, command: &Command, job_id: Uuid) -> PathBuf {
+    let name = command.name();
+    match Path::new(&name).file_name().and_then(|name| name.to_str()) {
+        Some(name) => Path::new(log_dir).join(format!("{}_{}.log", name, job_id)),
+        None => Path::new(log_dir).join(format!("{}.log", job_id)),
+    }
+}
+
+fn schema_version() -> u32 {
+    SCHEMA_VERSION
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JobInfo {
-    status: String,
+    #[serde(default = "schema_version")]
+    version: u32,
+    id: Uuid,
+    status: Status,
     pid: u32,
     exit_code: Option<i32>,
     command: Command,
+    // what the job was isolated with when it was started
+    #[serde(default)]
+    isolation: Isolation,
 }
 
 impl JobInfo {
-    pub fn status(&self) -> String {
+    pub fn new(
+        id: Uuid,
+        status: Status,
+        pid: u32,
+        exit_code: Option<i32>,
+        command: Command,
+    ) -> Self {
+        JobInfo {
+            version: SCHEMA_VERSION,
+            id,
+            status,
+            pid,
+            exit_code,
+            command,
+            isolation: Isolation::default(),
+        }
+    }
+
+    pub fn with_isolation(mut self, isolation: Isolation) -> Self {
+        self.isolation = isolation;
+        self
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn status(&self) -> Status {
         self.status.clone()
     }
 
@@ -38,6 +105,10 @@ impl JobInfo {
     pub fn command(&self) -> Command {
         self.command.clone()
     }
+
+    pub fn isolation(&self) -> Isolation {
+        self.isolation.clone()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -47,6 +118,9 @@ pub struct Job {
     pid: u32,
     status: Arc<Mutex<Status>>,
     owner_id: Uuid,
+    start_time: Option<u64>,
+    isolation: Isolation,
+    finished: Arc<AtomicBool>,
 }
 
 impl Job {
@@ -63,6 +137,9 @@ impl Job {
             pid,
             status,
             owner_id,
+            start_time: None,
+            isolation: Isolation::default(),
+            finished: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -86,32 +163,44 @@ impl Job {
         self.owner_id
     }
 
+    pub fn start_time(&self) -> Option<u64> {
+        self.start_time
+    }
+
+    pub fn isolation(&self) -> Isolation {
+        self.isolation.clone()
+    }
+
     pub fn set_pid(&mut self, pid: u32) {
         self.pid = pid;
     }
 
-    pub fn start(
+    pub fn set_start_time(&mut self, start_time: Option<u64>) {
+        self.start_time = start_time;
+    }
+
+    pub fn set_isolation(&mut self, isolation: Isolation) {
+        self.isolation = isolation;
+    }
+
+    // A job is finished once the worker no longer tracks its process, either
+    // because the exit has been handled or because it was lost on restart.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    pub fn set_finished(&self, finished: bool) {
+        self.finished.store(finished, Ordering::Release);
+    }
+
+    pub fn start<E: Executor>(
         job_id: Uuid,
         command: Command,
         owner_id: Uuid,
-        log_file_fd: i32,
-    ) -> Result<(Self, std::process::Child), crate::worker::Error> {
-        let mut cmd: &mut std::process::Command = &mut std::process::Command::new(command.name());
-        unsafe {
-            cmd = cmd
-                .stdout(Stdio::from_raw_fd(log_file_fd))
-                .stderr(Stdio::from_raw_fd(log_file_fd));
-        }
-        let child_cmd = cmd.args(command.args());
-        let child_proc = match child_cmd.spawn() {
-            Ok(child) => child,
-            Err(e) => {
-                return Err(crate::worker::Error::JobStartErr(format!(
-                    "failed to spawn child process: {:?}",
-                    e
-                )));
-            }
-        };
+        log_file: &File,
+        executor: &E,
+    ) -> Result<(Self, E::Process), crate::worker::Error> {
+        let (pid, process) = executor.spawn(job_id, &command, log_file)?;
 
         let status = Arc::new(Mutex::new(Status::UnknownState));
 
@@ -122,49 +211,84 @@ impl Job {
             ))
         })? = Status::Running;
 
-        Ok((
-            Job::new(job_id, command, child_proc.id(), status.clone(), owner_id),
-            child_proc,
-        ))
+        let mut job = Job::new(job_id, command, pid, status.clone(), owner_id);
+        job.set_start_time(executor.start_time(pid));
+        job.set_isolation(executor.isolation(job_id, &job.command));
+        Ok((job, process))
     }
 
-    pub fn stop(
+    pub fn stop<E: Executor>(
         &self,
         gracefully: bool,
-        sender: Sender<Result<(Uuid, bool), crate::worker::Error>>,
+        executor: &E,
     ) -> Result<(), crate::worker::Error> {
         let stop_type = match gracefully {
             true => StopType::Term,
             false => StopType::Kill,
         };
-        unsafe {
-            match syscall!(Sysno::kill, self.pid, stop_type.sig()) {
-                Ok(_) => {
-                    *self.status().lock().map_err(|e| {
-                        crate::worker::Error::JobStartErr(format!(
-                            "failed to lock status mutex for job {}: {:?}",
-                            self.id, e
-                        ))
-                    })? = Status::Stopped(stop_type);
-                    if let Err(e) = sender.send(Ok((self.id, true))) {
-                        panic!("failed to send stop result: {:?}", e);
-                    }
-                    Ok(())
-                }
-                Err(e) => {
-                    if let Err(e) = sender.send(Err(crate::worker::Error::JobStopErr(
-                        String::from("failed to stop job"),
-                    ))) {
-                        panic!("failed to send stop error: {:?}", e);
-                    };
-                    Err(crate::worker::Error::JobStopErr(format!(
-                        "failed to send SIG{} to job: {:?}",
-                        stop_type.as_str().to_uppercase(),
-                        e
-                    )))
-                }
+        // hold the status lock across the signal so a waiter that observes the
+        // exit cannot record it before the stop
+        let mut status = self.status.lock().map_err(|e| {
+            crate::worker::Error::JobStopErr(format!(
+                "failed to lock status mutex for job {}: {:?}",
+                self.id, e
+            ))
+        })?;
+        // the pid of a finished job, or of one lost on restart, may have been
+        // reused by an unrelated process
+        if self.is_finished() || *status != Status::Running {
+            return Err(crate::worker::Error::JobStopErr(format!(
+                "job {} has already finished",
+                self.id
+            )));
+        }
+        match executor.signal(self.id, self.pid, stop_type.sig()) {
+            Ok(_) => {
+                *status = Status::Stopped(stop_type);
+                Ok(())
             }
+            Err(e) => Err(crate::worker::Error::JobStopErr(format!(
+                "failed to send SIG{} to job: {}",
+                stop_type.as_str().to_uppercase(),
+                e
+            ))),
+        }
+    }
+
+    pub fn signal<E: Executor>(
+        &self,
+        signal: i32,
+        executor: &E,
+    ) -> Result<(), crate::worker::Error> {
+        let signal = Signal::try_from(signal).map_err(|_| {
+            crate::worker::Error::JobSignalErr(format!("invalid signal {}", signal))
+        })?;
+        if [Signal::SIGTERM, Signal::SIGKILL].contains(&signal) {
+            return Err(crate::worker::Error::JobSignalErr(format!(
+                "{} stops the job, which is what stop is for",
+                signal
+            )));
         }
+        let status = self.status.lock().map_err(|e| {
+            crate::worker::Error::JobSignalErr(format!(
+                "failed to lock status mutex for job {}: {:?}",
+                self.id, e
+            ))
+        })?;
+        if self.is_finished() || *status != Status::Running {
+            return Err(crate::worker::Error::JobSignalErr(format!(
+                "job {} has already finished",
+                self.id
+            )));
+        }
+        executor
+            .signal(self.id, self.pid, signal as usize)
+            .map_err(|e| {
+                crate::worker::Error::JobSignalErr(format!(
+                    "failed to send {} to job: {}",
+                    signal, e
+                ))
+            })
     }
 
     pub fn query(&self) -> Result<JobInfo, crate::worker::Error> {
@@ -182,20 +306,22 @@ impl Job {
             _ => None,
         };
         Ok(JobInfo {
+            version: SCHEMA_VERSION,
+            id: self.id,
             pid: self.pid,
             command: self.command.clone(),
-            status: status.to_string(),
+            status: status.clone(),
             exit_code,
+            isolation: self.isolation.clone(),
         })
     }
 
+    pub fn log_path(&self, log_dir: &str) -> PathBuf {
+        log_path(log_dir, &self.command, self.id)
+    }
+
     pub fn stream(&self, log_dir: &str) -> Result<std::io::BufReader<File>, crate::worker::Error> {
-        match std::fs::File::open(format!(
-            "{}/{}_{}.log",
-            log_dir,
-            self.command.name(),
-            self.id,
-        )) {
+        match std::fs::File::open(self.log_path(log_dir)) {
             Ok(log_file) => Ok(BufReader::new(log_file)),
             Err(e) => Err(crate::worker::Error::JobStreamErr(format!(
                 "failed to open log file: {:?}",
@@ -204,21 +330,13 @@ impl Job {
         }
     }
 
-    pub fn wait(&self, proc: std::process::Child) -> Result<(Uuid, bool), crate::worker::Error> {
-        let output = match proc.wait_with_output() {
-            Ok(out) => out,
-            Err(e) => {
-                return Err(crate::worker::Error::JobErr(
-                    self.id,
-                    format!("child process failed: {:?}", e),
-                ))
-            }
-        };
-        match self.status.lock() {
+    pub fn exited(&self, exit_status: Status) -> Result<(Uuid, bool), crate::worker::Error> {
+        let stopped = match self.status.lock() {
             Ok(mut status) => {
                 if !status.is_stopped() {
-                    *status = Status::Exited(output.status.code())
+                    *status = exit_status
                 }
+                status.is_stopped()
             }
             Err(e) => {
                 return Err(crate::worker::Error::JobErr(
@@ -226,7 +344,7 @@ impl Job {
                     format!("failed to lock status mutex to update exit code: {:?}", e),
                 ))
             }
-        }
-        Ok((self.id, false))
+        };
+        Ok((self.id, stopped))
     }
 }
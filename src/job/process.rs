@@ -0,0 +1,54 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+use syscalls::{syscall, Sysno};
+
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the command name may itself contain spaces or parentheses, so fields
+    // are counted from the last closing parenthesis (field 2)
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[derive(Debug)]
+pub struct PidFd {
+    fd: OwnedFd,
+}
+
+impl PidFd {
+    pub fn open(pid: u32) -> Result<Self, crate::worker::Error> {
+        let fd = unsafe { syscall!(Sysno::pidfd_open, pid, 0) }.map_err(|e| {
+            crate::worker::Error::WorkerErr(format!("failed to open pidfd for {}: {:?}", pid, e))
+        })?;
+        Ok(PidFd {
+            fd: unsafe { OwnedFd::from_raw_fd(fd as RawFd) },
+        })
+    }
+
+    // Signals the process the pidfd was opened for, which fails with ESRCH
+    // once it has been reaped rather than reaching whatever reused its pid.
+    pub fn send_signal(&self, sig: usize) -> Result<(), syscalls::Errno> {
+        unsafe {
+            syscall!(
+                Sysno::pidfd_send_signal,
+                self.fd.as_raw_fd(),
+                sig,
+                std::ptr::null::<u8>(),
+                0
+            )
+        }
+        .map(|_| ())
+    }
+}
+
+impl AsFd for PidFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+// POSIX resource limits a job is started with. Each is set as both the soft
+// and the hard limit, so the job cannot raise it again, except the CPU time,
+// whose hard limit is a second above the soft one so that SIGXCPU arrives
+// before SIGKILL. Every limit is inherited by everything the job starts. A
+// limit that is not given is left as the job inherits it from the worker.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rlimits {
+    // open file descriptors, RLIMIT_NOFILE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nofile: Option<u64>,
+    // bytes of a core dump, RLIMIT_CORE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    core: Option<u64>,
+    // bytes of virtual memory, RLIMIT_AS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address_space: Option<u64>,
+    // seconds of CPU time, RLIMIT_CPU; the job is sent SIGXCPU once it runs
+    // out, and SIGKILL a second later if it survives that
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cpu: Option<u64>,
+    // processes of the job's real user, RLIMIT_NPROC, which the kernel does
+    // not enforce on root
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nproc: Option<u64>,
+}
+
+impl Rlimits {
+    pub fn with_nofile(mut self, nofile: u64) -> Self {
+        self.nofile = Some(nofile);
+        self
+    }
+
+    pub fn with_core(mut self, core: u64) -> Self {
+        self.core = Some(core);
+        self
+    }
+
+    pub fn with_address_space(mut self, address_space: u64) -> Self {
+        self.address_space = Some(address_space);
+        self
+    }
+
+    pub fn with_cpu(mut self, cpu: u64) -> Self {
+        self.cpu = Some(cpu);
+        self
+    }
+
+    pub fn with_nproc(mut self, nproc: u64) -> Self {
+        self.nproc = Some(nproc);
+        self
+    }
+
+    pub fn nofile(&self) -> Option<u64> {
+        self.nofile
+    }
+
+    pub fn core(&self) -> Option<u64> {
+        self.core
+    }
+
+    pub fn address_space(&self) -> Option<u64> {
+        self.address_space
+    }
+
+    pub fn cpu(&self) -> Option<u64> {
+        self.cpu
+    }
+
+    pub fn nproc(&self) -> Option<u64> {
+        self.nproc
+    }
+
+    // Takes the lower of each limit and the one other gives, so that other
+    // can only tighten them.
+    pub fn restrict(&self, other: &Rlimits) -> Rlimits {
+        let lower = |own: Option<u64>, other: Option<u64>| match (own, other) {
+            (Some(own), Some(other)) => Some(own.min(other)),
+            (own, other) => own.or(other),
+        };
+        Rlimits {
+            nofile: lower(self.nofile, other.nofile),
+            core: lower(self.core, other.core),
+            address_space: lower(self.address_space, other.address_space),
+            cpu: lower(self.cpu, other.cpu),
+            nproc: lower(self.nproc, other.nproc),
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.nofile.is_some()
+            || self.core.is_some()
+            || self.address_space.is_some()
+            || self.cpu.is_some()
+            || self.nproc.is_some()
+    }
+}
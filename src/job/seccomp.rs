@@ -0,0 +1,193 @@
+use crate::worker::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const DANGEROUS: &[&str] = &[
+    "acct",
+    "add_key",
+    "bpf",
+    "clock_adjtime",
+    "clock_settime",
+    "create_module",
+    "delete_module",
+    "finit_module",
+    "fsconfig",
+    "fsmount",
+    "fsopen",
+    "fspick",
+    "get_kernel_syms",
+    "init_module",
+    "ioperm",
+    "iopl",
+    "kexec_file_load",
+    "kexec_load",
+    "keyctl",
+    "lookup_dcookie",
+    "mount",
+    "mount_setattr",
+    "move_mount",
+    "name_to_handle_at",
+    "nfsservctl",
+    "open_by_handle_at",
+    "open_tree",
+    "perf_event_open",
+    "pivot_root",
+    "query_module",
+    "quotactl",
+    "reboot",
+    "request_key",
+    "setns",
+    "settimeofday",
+    "swapoff",
+    "swapon",
+    "syslog",
+    "umount2",
+    "unshare",
+    "uselib",
+    "userfaultfd",
+    "vhangup",
+];
+const PTRACE: &[&str] = &["process_vm_readv", "process_vm_writev", "ptrace"];
+const IO_URING: &[&str] = &["io_uring_enter", "io_uring_register", "io_uring_setup"];
+
+// System call filter attached to a job right before it is executed. The job
+// is killed by the first system call its filter denies.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seccomp {
+    Profile(SeccompProfile),
+    // deny every system call but these, and the execve(2) of the job
+    Allow(Vec<String>),
+    // deny these system calls, and allow any other
+    Deny(Vec<String>),
+}
+
+// Filters built into the worker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SeccompProfile {
+    // calls that change the kernel, the system or other namespaces, or trace
+    // other processes, and clones into new namespaces
+    DefaultDenyDangerous,
+    // sockets of any domain but unix, and io_uring which can make them too
+    NoNetworkSyscalls,
+    // ptrace(2) and the calls that read or write other processes' memory
+    NoPtrace,
+}
+
+impl SeccompProfile {
+    // The system calls the profile denies, besides clones into new namespaces
+    // for DefaultDenyDangerous and sockets of any domain but unix for
+    // NoNetworkSyscalls.
+    pub fn denied(&self) -> Vec<&'static str> {
+        match self {
+            SeccompProfile::DefaultDenyDangerous => [DANGEROUS, PTRACE].concat(),
+            SeccompProfile::NoNetworkSyscalls => IO_URING.to_vec(),
+            SeccompProfile::NoPtrace => PTRACE.to_vec(),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SeccompProfile::DefaultDenyDangerous => "default-deny-dangerous",
+            SeccompProfile::NoNetworkSyscalls => "no-network-syscalls",
+            SeccompProfile::NoPtrace => "no-ptrace",
+        }
+    }
+}
+
+impl std::str::FromStr for SeccompProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "default-deny-dangerous" => Ok(SeccompProfile::DefaultDenyDangerous),
+            "no-network-syscalls" => Ok(SeccompProfile::NoNetworkSyscalls),
+            "no-ptrace" => Ok(SeccompProfile::NoPtrace),
+            _ => Err(format!(
+                "unknown seccomp profile {:?}, expected default-deny-dangerous, no-network-syscalls or no-ptrace",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SeccompFile {
+    allow: Option<Vec<String>>,
+    deny: Option<Vec<String>>,
+}
+
+impl Seccomp {
+    // Loads a custom filter from a file listing either the system calls to
+    // allow or those to deny, by name:
+    //
+    //   deny = ["mount", "ptrace"]
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let file: SeccompFile = config::Config::builder()
+            .add_source(config::File::with_name(path))
+            .build()
+            .and_then(|cfg| cfg.try_deserialize())
+            .map_err(|e| Error::WorkerErr(format!("failed to load seccomp filter: {}", e)))?;
+        match (file.allow, file.deny) {
+            (Some(allow), None) => Ok(Seccomp::Allow(allow)),
+            (None, Some(deny)) => Ok(Seccomp::Deny(deny)),
+            _ => Err(Error::WorkerErr(format!(
+                "failed to load seccomp filter: {} must list either allow or deny",
+                path
+            ))),
+        }
+    }
+
+    // Whether the filter denies at least every system call other denies, so
+    // that a job may ask for it in place of a filter the worker imposes.
+    pub fn is_as_strict_as(&self, other: &Seccomp) -> bool {
+        match (self, other) {
+            _ if self == other => true,
+            (Seccomp::Allow(allowed), Seccomp::Allow(others)) => {
+                allowed.iter().all(|name| others.contains(name))
+            }
+            (Seccomp::Allow(allowed), _) => other
+                .denied()
+                .iter()
+                .chain(other.restricted())
+                .all(|name| !allowed.iter().any(|allowed| allowed == name)),
+            (_, Seccomp::Allow(_)) => false,
+            _ => {
+                let denied = self.denied();
+                other.denied().iter().all(|name| denied.contains(name))
+                    && other
+                        .restricted()
+                        .iter()
+                        .all(|name| denied.contains(name) || self.restricted().contains(name))
+            }
+        }
+    }
+
+    fn denied(&self) -> Vec<&str> {
+        match self {
+            Seccomp::Profile(profile) => profile.denied(),
+            Seccomp::Deny(names) => names.iter().map(String::as_str).collect(),
+            Seccomp::Allow(_) => vec![],
+        }
+    }
+
+    // Calls the filter denies for some of their arguments, or fails rather
+    // than kills the job for.
+    fn restricted(&self) -> &'static [&'static str] {
+        match self {
+            Seccomp::Profile(SeccompProfile::DefaultDenyDangerous) => &["clone", "clone3"],
+            Seccomp::Profile(SeccompProfile::NoNetworkSyscalls) => &["socket"],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Seccomp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Seccomp::Profile(profile) => write!(f, "{}", profile.as_str()),
+            Seccomp::Allow(_) | Seccomp::Deny(_) => write!(f, "custom"),
+        }
+    }
+}
@@ -1,17 +1,22 @@
-#[derive(Clone, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", content = "detail", rename_all = "snake_case")]
 pub enum Status {
     UnknownState,
     Running,
     Exited(Option<i32>),
     Stopped(StopType),
+    // killed by its seccomp filter for a system call it denies
+    KilledBySeccomp,
+    // killed by SIGXCPU for running out of its CPU time limit
+    KilledByCpuLimit,
 }
 
 impl Status {
     pub fn is_stopped(&self) -> bool {
-        match self {
-            Self::Stopped(_) => true,
-            _ => false,
-        }
+        matches!(self, Self::Stopped(_))
     }
 
     pub fn as_str(&self) -> &str {
@@ -23,15 +28,20 @@ impl Status {
                 StopType::Term => "terminated",
                 StopType::Kill => "Killed",
             },
+            Self::KilledBySeccomp => "Killed by seccomp",
+            Self::KilledByCpuLimit => "Killed by CPU time limit",
         }
     }
+}
 
-    pub fn to_string(&self) -> String {
-        String::from(self.as_str())
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StopType {
     Term,
     Kill,
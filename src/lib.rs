@@ -1,2 +1,5 @@
+pub mod executor;
 pub mod job;
+#[cfg(feature = "server")]
+pub mod server;
 pub mod worker;
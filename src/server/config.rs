@@ -0,0 +1,272 @@
+use crate::{
+    job::{
+        Capabilities, IdMap, Isolation, Network, UserNamespace, CAPABILITY_NAMES, SUBGID_PATH,
+        SUBUID_PATH,
+    },
+    worker::{Config, Error, Policy},
+};
+use nix::unistd::{Gid, Group};
+use serde::Deserialize;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+const ENV_PREFIX: &str = "IRON_EXEC";
+// bounding set of jobs of a server that is not given one, as container
+// runtimes default to
+const DEFAULT_CAPABILITIES: &[&str] = &[
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_SETFCAP",
+    "CAP_SETGID",
+    "CAP_SETPCAP",
+    "CAP_SETUID",
+    "CAP_SYS_CHROOT",
+];
+
+// Settings of the server binary, read from an optional config file and then
+// from IRON_EXEC_* environment variables, which take precedence.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ServerConfig {
+    #[serde(default = "default_listen")]
+    listen: String,
+    #[serde(default = "default_log_dir")]
+    log_dir: String,
+    #[serde(default = "default_thread_count")]
+    thread_count: usize,
+    #[serde(default)]
+    state_dir: Option<String>,
+    #[serde(default)]
+    recover_orphans: bool,
+    // run each job as pid 1 of its own pid namespace
+    #[serde(default)]
+    pid_namespace: bool,
+    // give each job a private /tmp and its requested bind mounts, and make
+    // everything else it sees read-only with read_only_root
+    #[serde(default)]
+    mount_namespace: bool,
+    #[serde(default)]
+    read_only_root: bool,
+    // host paths, e.g. /srv/data,/srv/out, beneath which jobs may ask for
+    // bind mounts; jobs get none without them
+    mount_sources: Option<String>,
+    // network every job gets at least, e.g. loopback to keep them all offline
+    #[serde(default)]
+    network: Network,
+    // give each job a uts namespace with its job id as hostname
+    #[serde(default)]
+    uts_namespace: bool,
+    // run each job in a user namespace, mapping the server's own ids to root
+    // unless uid_map and gid_map, e.g. 0:1000:1,1:100000:65536, say
+    // otherwise; subordinate_ids maps the ranges /etc/subuid and /etc/subgid
+    // give the server's user after its own ids
+    #[serde(default)]
+    user_namespace: bool,
+    #[serde(default)]
+    subordinate_ids: bool,
+    uid_map: Option<String>,
+    gid_map: Option<String>,
+    // the bounding set every job is started with at most, e.g.
+    // CAP_CHOWN,CAP_NET_BIND_SERVICE, so that capabilities the server needs
+    // itself are not handed on; an empty list drops them all, and without
+    // one jobs get DEFAULT_CAPABILITIES
+    capabilities: Option<String>,
+    // keep jobs from gaining privileges through setuid binaries
+    #[serde(default)]
+    no_new_privs: bool,
+    // unix socket served next to, or instead of, the tcp listener, with
+    // callers identified by their uid
+    #[serde(default)]
+    socket: Option<String>,
+    // group, by name or gid, whose members may connect to the socket as
+    // well; without one only the server's own user can
+    socket_group: Option<String>,
+    // PEM files of the server's certificate and key, and of the CA that
+    // client certificates must be signed by; the tcp listener is only served
+    // with all three
+    tls_cert: Option<String>,
+    tls_key: Option<String>,
+    tls_client_ca: Option<String>,
+    // file read with Policy::load, everybody is an operator without one
+    policy: Option<String>,
+}
+
+fn default_listen() -> String {
+    "127.0.0.1:50051".into()
+}
+
+fn default_log_dir() -> String {
+    Config::default().log_dir().into()
+}
+
+fn default_thread_count() -> usize {
+    Config::default().thread_count()
+}
+
+impl ServerConfig {
+    pub fn load(path: Option<&str>) -> Result<Self, Error> {
+        let mut builder = config::Config::builder();
+        if let Some(path) = path {
+            builder = builder.add_source(config::File::with_name(path));
+        }
+        builder
+            .add_source(config::Environment::with_prefix(ENV_PREFIX).try_parsing(true))
+            .build()
+            .and_then(|cfg| cfg.try_deserialize())
+            .map_err(|e| Error::WorkerErr(format!("failed to load server config: {}", e)))
+    }
+
+    pub fn listen(&self) -> &str {
+        &self.listen
+    }
+
+    pub fn socket(&self) -> Option<&str> {
+        self.socket.as_deref()
+    }
+
+    pub fn socket_group(&self) -> Result<Option<Gid>, Error> {
+        let name = match &self.socket_group {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        if let Ok(gid) = name.parse() {
+            return Ok(Some(Gid::from_raw(gid)));
+        }
+        match Group::from_name(name) {
+            Ok(Some(group)) => Ok(Some(group.gid)),
+            Ok(None) => Err(Error::WorkerErr(format!("unknown socket group {:?}", name))),
+            Err(e) => Err(Error::WorkerErr(format!(
+                "failed to look up socket group {:?}: {:?}",
+                name, e
+            ))),
+        }
+    }
+
+    // None if no tls setting is given, in which case only the socket is
+    // served.
+    pub fn tls_config(&self) -> Result<Option<ServerTlsConfig>, Error> {
+        let (cert, key, client_ca) = match (&self.tls_cert, &self.tls_key, &self.tls_client_ca) {
+            (None, None, None) => return Ok(None),
+            (Some(cert), Some(key), Some(client_ca)) => (cert, key, client_ca),
+            _ => {
+                return Err(Error::WorkerErr(
+                    "tls_cert, tls_key and tls_client_ca must all be set".into(),
+                ))
+            }
+        };
+        Ok(Some(
+            ServerTlsConfig::new()
+                .identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?))
+                .client_ca_root(Certificate::from_pem(read_pem(client_ca)?)),
+        ))
+    }
+
+    // The worker config borrows its paths for the life of the process, which
+    // is fine for the one worker a server runs.
+    pub fn worker_config(&self) -> Result<Config, Error> {
+        let mut cfg = Config::new(leak(&self.log_dir), self.thread_count);
+        if let Some(policy) = &self.policy {
+            cfg = cfg.with_policy(Policy::load(policy)?);
+        }
+        if let Some(state_dir) = &self.state_dir {
+            cfg = cfg.with_state_dir(leak(state_dir));
+        }
+        if self.recover_orphans {
+            cfg = cfg.with_orphan_recovery();
+        }
+        if let Some(mount_sources) = &self.mount_sources {
+            cfg = cfg.with_mount_sources(
+                mount_sources
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|path| !path.is_empty())
+                    .map(String::from)
+                    .collect(),
+            );
+        }
+        let mut isolation = Isolation::default().with_network(self.network);
+        if self.pid_namespace {
+            isolation = isolation.with_pid_namespace();
+        }
+        if self.mount_namespace {
+            isolation = isolation.with_mount_namespace();
+        }
+        if self.read_only_root {
+            isolation = isolation.with_read_only_root();
+        }
+        if self.uts_namespace {
+            isolation = isolation.with_uts_namespace();
+        }
+        if self.user_namespace {
+            isolation = isolation.with_user_namespace(self.user_namespace()?);
+        }
+        let bounding: Vec<String> = match &self.capabilities {
+            Some(capabilities) => capabilities
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect(),
+            None => DEFAULT_CAPABILITIES
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        };
+        if let Some(name) = bounding
+            .iter()
+            .find(|name| !CAPABILITY_NAMES.contains(&name.as_str()))
+        {
+            return Err(Error::WorkerErr(format!(
+                "invalid capabilities: unknown capability {:?}",
+                name
+            )));
+        }
+        // an unprivileged server can only drop capabilities inside of a user
+        // namespace, and outside of one its jobs hold none to begin with
+        if self.capabilities.is_some() || self.user_namespace || nix::unistd::geteuid().is_root() {
+            isolation =
+                isolation.with_capabilities(Capabilities::default().with_bounding(bounding));
+        }
+        if self.no_new_privs {
+            isolation = isolation.with_no_new_privs();
+        }
+        Ok(cfg.with_isolation(isolation))
+    }
+
+    fn user_namespace(&self) -> Result<UserNamespace, Error> {
+        let mut user_namespace = UserNamespace::new();
+        if self.subordinate_ids {
+            let uid = nix::unistd::getuid();
+            let user = match nix::unistd::User::from_uid(uid) {
+                Ok(Some(user)) => user.name,
+                _ => uid.to_string(),
+            };
+            user_namespace =
+                user_namespace.with_subordinate_ids(&user, SUBUID_PATH, SUBGID_PATH)?;
+        }
+        if let Some(uid_map) = &self.uid_map {
+            user_namespace = user_namespace.with_uid_map(parse_id_maps("uid_map", uid_map)?);
+        }
+        if let Some(gid_map) = &self.gid_map {
+            user_namespace = user_namespace.with_gid_map(parse_id_maps("gid_map", gid_map)?);
+        }
+        Ok(user_namespace)
+    }
+}
+
+fn parse_id_maps(name: &str, maps: &str) -> Result<Vec<IdMap>, Error> {
+    maps.split(',')
+        .map(|map| map.parse())
+        .collect::<Result<_, _>>()
+        .map_err(|e| Error::WorkerErr(format!("invalid {}: {}", name, e)))
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, Error> {
+    std::fs::read(path).map_err(|e| Error::WorkerErr(format!("failed to read {}: {:?}", path, e)))
+}
+
+fn leak(s: &str) -> &'static str {
+    Box::leak(s.to_string().into_boxed_str())
+}
@@ -0,0 +1,269 @@
+use super::identity::owner_id_from_peer;
+use crate::{
+    executor::Executor,
+    job::{Command, JobInfo},
+    worker::{AsyncWorker, Error, LogStream},
+};
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{rejection::JsonRejection, FromRequestParts, Path, Query, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{convert::Infallible, io};
+use tokio::{io::AsyncReadExt, sync::mpsc};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo, UdsConnectInfo};
+use uuid::Uuid;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+const STREAM_BUFFER: usize = 16;
+const OPENAPI: &str = include_str!("../../api/openapi.json");
+
+// REST front end of an AsyncWorker, served next to the gRPC API and
+// authenticated the same way.
+pub fn router<E: Executor>(worker: AsyncWorker<E>) -> Router {
+    Router::new()
+        .route("/jobs", get(list::<E>).post(start::<E>))
+        .route("/jobs/:id", get(query::<E>).delete(stop::<E>))
+        .route("/jobs/:id/logs", get(logs::<E>))
+        .route("/openapi.json", get(openapi))
+        .with_state(worker)
+}
+
+// The owner of a request, derived from its connection.
+struct Owner(Uuid);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Owner {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, ApiError> {
+        let certs = parts
+            .extensions
+            .get::<TlsConnectInfo<TcpConnectInfo>>()
+            .and_then(|info| info.peer_certs());
+        owner_id_from_peer(parts.extensions.get::<UdsConnectInfo>(), certs)
+            .map(Owner)
+            .map_err(|e| ApiError(StatusCode::UNAUTHORIZED, e))
+    }
+}
+
+struct ApiError(StatusCode, String);
+
+impl From<Error> for ApiError {
+    fn from(e: Error) -> Self {
+        let code = match e {
+            // lookups of another owner's job fail the same way as unknown jobs
+            Error::NotFound(_) => StatusCode::NOT_FOUND,
+            Error::PermissionDenied(_) => StatusCode::FORBIDDEN,
+            // a job that cannot be started is a problem with the request,
+            // while stopping or purging can clash with the job's state
+            Error::JobStartErr(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::JobStopErr(_) | Error::JobSignalErr(_) | Error::JobPurgeErr(_) => {
+                StatusCode::CONFLICT
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(code, e.as_str().into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+fn parse_job_id(job_id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(job_id).map_err(|_| {
+        ApiError(
+            StatusCode::BAD_REQUEST,
+            format!("invalid job id {:?}", job_id),
+        )
+    })
+}
+
+async fn start<E: Executor>(
+    State(worker): State<AsyncWorker<E>>,
+    Owner(owner_id): Owner,
+    command: Result<Json<Command>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(command) = command.map_err(|e| ApiError(e.status(), e.body_text()))?;
+    let job_id = worker.start(command, owner_id).await?;
+    let job_info = worker.query(job_id, owner_id).await?;
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, format!("/jobs/{}", job_id))],
+        Json(job_info),
+    ))
+}
+
+async fn list<E: Executor>(
+    State(worker): State<AsyncWorker<E>>,
+    Owner(owner_id): Owner,
+) -> Result<Json<Vec<JobInfo>>, ApiError> {
+    Ok(Json(worker.list(owner_id).await?))
+}
+
+async fn query<E: Executor>(
+    State(worker): State<AsyncWorker<E>>,
+    Owner(owner_id): Owner,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobInfo>, ApiError> {
+    let job_id = parse_job_id(&job_id)?;
+    Ok(Json(worker.query(job_id, owner_id).await?))
+}
+
+#[derive(Deserialize)]
+struct StopParams {
+    #[serde(default)]
+    force: bool,
+}
+
+async fn stop<E: Executor>(
+    State(worker): State<AsyncWorker<E>>,
+    Owner(owner_id): Owner,
+    Path(job_id): Path<String>,
+    Query(params): Query<StopParams>,
+) -> Result<StatusCode, ApiError> {
+    let job_id = parse_job_id(&job_id)?;
+    worker.stop(job_id, owner_id, !params.force).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+struct LogsParams {
+    #[serde(default = "follow_by_default")]
+    follow: bool,
+}
+
+fn follow_by_default() -> bool {
+    true
+}
+
+// Responds with the job's output as it is written, as server-sent events if
+// the client asks for them and as a plain chunked body otherwise.
+async fn logs<E: Executor>(
+    State(worker): State<AsyncWorker<E>>,
+    Owner(owner_id): Owner,
+    Path(job_id): Path<String>,
+    Query(params): Query<LogsParams>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let job_id = parse_job_id(&job_id)?;
+    let logs = worker
+        .stream(job_id, owner_id)
+        .await?
+        .with_follow(params.follow);
+    let chunks = read_chunks(logs);
+
+    let wants_events = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    if !wants_events {
+        let body = Body::from_stream(ReceiverStream::new(chunks));
+        return Ok(([(header::CONTENT_TYPE, "text/plain")], body).into_response());
+    }
+
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(async move {
+        let mut chunks = ReceiverStream::new(chunks);
+        let mut text = Utf8Chunks::default();
+        while let Some(chunk) = chunks.next().await {
+            let event = match chunk {
+                Ok(chunk) => Event::default().event("log").json_data(text.push(&chunk)),
+                Err(e) => Event::default()
+                    .event("error")
+                    .json_data(format!("failed to read log file: {:?}", e)),
+            };
+            // a send error means the client has gone away
+            let Ok(event) = event else { return };
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+        // output cut off in the middle of a character is sent as it is
+        if let Some(rest) = text.finish() {
+            let Ok(event) = Event::default().event("log").json_data(rest) else {
+                return;
+            };
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+        // a followed stream only ends once the job has, so its final state
+        // closes the stream
+        if params.follow {
+            if let Ok(job_info) = worker.query(job_id, owner_id).await {
+                if let Ok(event) = Event::default().event("exit").json_data(job_info) {
+                    let _ = tx.send(event).await;
+                }
+            }
+        }
+    });
+    let events = ReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+async fn openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+fn read_chunks(mut logs: LogStream) -> mpsc::Receiver<io::Result<Vec<u8>>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(async move {
+        let mut buf = vec![0; CHUNK_SIZE];
+        loop {
+            let chunk = match logs.read(&mut buf).await {
+                Ok(0) => return,
+                Ok(n) => Ok(buf[..n].to_vec()),
+                Err(e) => Err(e),
+            };
+            let failed = chunk.is_err();
+            if tx.send(chunk).await.is_err() || failed {
+                return;
+            }
+        }
+    });
+    rx
+}
+
+// Turns chunks of output into text without splitting characters that
+// straddle two chunks.
+#[derive(Default)]
+struct Utf8Chunks {
+    pending: Vec<u8>,
+}
+
+impl Utf8Chunks {
+    fn push(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        let rest = self.pending.split_off(complete);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+
+    // Whatever is still held back once the stream has ended, lossily.
+    fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.pending);
+        (!rest.is_empty()).then(|| String::from_utf8_lossy(&rest).into_owned())
+    }
+}
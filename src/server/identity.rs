@@ -0,0 +1,83 @@
+use crate::worker::{is_uid_owner_id, owner_id_from_name, owner_id_from_uid, UID_NAME_PREFIX};
+use std::sync::Arc;
+use tonic::transport::{server::UdsConnectInfo, CertificateDer};
+use uuid::Uuid;
+use x509_parser::{certificate::X509Certificate, extensions::GeneralName, prelude::FromDer};
+
+const OWNER_URI_PREFIX: &str = "urn:uuid:";
+
+// Identifies the caller of a request by what its connection vouches for: the
+// kernel's credentials of a unix socket peer, or else a client certificate.
+pub(crate) fn owner_id_from_peer(
+    uds: Option<&UdsConnectInfo>,
+    certs: Option<Arc<Vec<CertificateDer<'static>>>>,
+) -> Result<Uuid, String> {
+    if let Some(uds) = uds {
+        let cred = uds
+            .peer_cred
+            .ok_or_else(|| "missing peer credentials".to_string())?;
+        log::debug!(
+            "unix socket peer uid {} gid {} pid {:?}",
+            cred.uid(),
+            cred.gid(),
+            cred.pid()
+        );
+        return Ok(owner_id_from_uid(cred.uid()));
+    }
+    let certs = certs.ok_or_else(|| "missing client certificate".to_string())?;
+    let cert = certs
+        .first()
+        .ok_or_else(|| "missing client certificate".to_string())?;
+    owner_id_from_cert(cert)
+}
+
+// Maps a DER encoded client certificate to the id of the owner it speaks for.
+// A urn:uuid: URI in the subject alternative names takes precedence, then the
+// subject's common name is used, either as a UUID or hashed into one. Names
+// and ids of local users are refused, as they would speak for that user.
+pub fn owner_id_from_cert(cert: &[u8]) -> Result<Uuid, String> {
+    let owner_id = cert_owner_id(cert)?;
+    if is_uid_owner_id(owner_id) {
+        return Err(format!(
+            "client certificate owner id {} is reserved for local users",
+            owner_id
+        ));
+    }
+    Ok(owner_id)
+}
+
+fn cert_owner_id(cert: &[u8]) -> Result<Uuid, String> {
+    let (_, cert) = X509Certificate::from_der(cert)
+        .map_err(|e| format!("failed to parse client certificate: {}", e))?;
+
+    let san = cert
+        .subject_alternative_name()
+        .map_err(|e| format!("failed to parse client certificate names: {}", e))?;
+    let uris = san
+        .iter()
+        .flat_map(|san| san.value.general_names.iter())
+        .filter_map(|name| match name {
+            GeneralName::URI(uri) => uri.strip_prefix(OWNER_URI_PREFIX),
+            _ => None,
+        });
+    if let Some(owner_id) = uris.into_iter().next() {
+        return Uuid::parse_str(owner_id)
+            .map_err(|_| format!("invalid owner uri {}{}", OWNER_URI_PREFIX, owner_id));
+    }
+
+    let common_name = cert
+        .subject()
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .ok_or_else(|| {
+            "client certificate has neither an owner uri nor a common name".to_string()
+        })?;
+    if common_name.starts_with(UID_NAME_PREFIX) {
+        return Err(format!(
+            "client certificate common name {} is reserved for local users",
+            common_name
+        ));
+    }
+    Ok(owner_id_from_name(common_name))
+}
@@ -0,0 +1,58 @@
+mod config;
+pub mod http;
+mod identity;
+mod service;
+use crate::{
+    executor::Executor,
+    worker::{AsyncWorker, Error},
+};
+pub use config::ServerConfig;
+pub use identity::owner_id_from_cert;
+pub use service::WorkerService;
+use std::future::Future;
+use tokio::net::{TcpListener, UnixListener};
+use tokio_stream::wrappers::{TcpListenerStream, UnixListenerStream};
+use tonic::{service::Routes, transport::ServerTlsConfig};
+
+pub mod proto {
+    tonic::include_proto!("iron_exec.v1");
+}
+
+// Serves the gRPC API, and the REST API next to it, on listener until
+// shutdown resolves. Clients must present a certificate trusted by tls, as it
+// is what identifies their owner.
+pub async fn serve<E: Executor>(
+    worker: AsyncWorker<E>,
+    listener: TcpListener,
+    tls: ServerTlsConfig,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Error> {
+    tonic::transport::Server::builder()
+        // plain HTTP clients may not negotiate HTTP/2
+        .accept_http1(true)
+        .tls_config(tls)
+        .map_err(|e| Error::WorkerErr(format!("invalid tls config: {:?}", e)))?
+        .add_routes(routes(worker))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
+        .await
+        .map_err(|e| Error::WorkerErr(format!("grpc server failed: {:?}", e)))
+}
+
+// Serves both APIs on a unix socket until shutdown resolves. There is no TLS:
+// callers are identified by the uid the kernel reports for them instead.
+pub async fn serve_unix<E: Executor>(
+    worker: AsyncWorker<E>,
+    listener: UnixListener,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), Error> {
+    tonic::transport::Server::builder()
+        .accept_http1(true)
+        .add_routes(routes(worker))
+        .serve_with_incoming_shutdown(UnixListenerStream::new(listener), shutdown)
+        .await
+        .map_err(|e| Error::WorkerErr(format!("unix socket server failed: {:?}", e)))
+}
+
+fn routes<E: Executor>(worker: AsyncWorker<E>) -> Routes {
+    Routes::from(http::router(worker.clone())).add_service(WorkerService::new(worker).into_server())
+}
@@ -0,0 +1,639 @@
+// tonic::Status is large, but it is what every handler returns anyway
+#![allow(clippy::result_large_err)]
+
+use super::{
+    identity::owner_id_from_peer,
+    proto::{
+        self,
+        worker_server::{Worker as WorkerApi, WorkerServer},
+        ListRequest, ListResponse, LogChunk, PurgeRequest, PurgeResponse, QueryRequest,
+        SignalRequest, SignalResponse, StartRequest, StartResponse, StopRequest, StopResponse,
+        StreamRequest, WaitRequest,
+    },
+};
+use crate::{
+    executor::{Executor, NativeExecutor},
+    job::{
+        Capabilities, Command, IdMap, Isolation, JobInfo, Landlock, LandlockFallback, Mount,
+        Network, Rlimits, Rootfs, Seccomp, SeccompProfile, Status as JobStatus, StopType,
+        UserNamespace,
+    },
+    worker::{AsyncWorker, Error},
+};
+use std::time::Duration;
+use tokio::{io::AsyncReadExt, sync::mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::server::UdsConnectInfo, Request, Response, Status};
+use uuid::Uuid;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+const STREAM_BUFFER: usize = 16;
+
+// gRPC front end of an AsyncWorker. The owner of every call is derived from
+// the connection, never from the request itself.
+pub struct WorkerService<E: Executor = NativeExecutor> {
+    worker: AsyncWorker<E>,
+}
+
+impl<E: Executor> WorkerService<E> {
+    pub fn new(worker: AsyncWorker<E>) -> Self {
+        WorkerService { worker }
+    }
+
+    pub fn into_server(self) -> WorkerServer<Self> {
+        WorkerServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl<E: Executor> WorkerApi for WorkerService<E> {
+    type StreamStream = ReceiverStream<Result<LogChunk, Status>>;
+
+    async fn start(
+        &self,
+        request: Request<StartRequest>,
+    ) -> Result<Response<StartResponse>, Status> {
+        let owner_id = owner_id(&request)?;
+        let command = request
+            .into_inner()
+            .command
+            .ok_or_else(|| Status::invalid_argument("missing command"))?;
+        let command = Command::try_from(command).map_err(Status::invalid_argument)?;
+        let job_id = self
+            .worker
+            .start(command, owner_id)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(StartResponse {
+            job_id: job_id.to_string(),
+        }))
+    }
+
+    async fn stop(&self, request: Request<StopRequest>) -> Result<Response<StopResponse>, Status> {
+        let owner_id = owner_id(&request)?;
+        let request = request.into_inner();
+        let job_id = parse_job_id(&request.job_id)?;
+        self.worker
+            .stop(job_id, owner_id, request.gracefully)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(StopResponse {}))
+    }
+
+    async fn signal(
+        &self,
+        request: Request<SignalRequest>,
+    ) -> Result<Response<SignalResponse>, Status> {
+        let owner_id = owner_id(&request)?;
+        let request = request.into_inner();
+        let job_id = parse_job_id(&request.job_id)?;
+        self.worker
+            .signal(job_id, owner_id, request.signal)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(SignalResponse {}))
+    }
+
+    async fn query(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<proto::JobInfo>, Status> {
+        let owner_id = owner_id(&request)?;
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let job_info = self
+            .worker
+            .query(job_id, owner_id)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(job_info.into()))
+    }
+
+    async fn stream(
+        &self,
+        request: Request<StreamRequest>,
+    ) -> Result<Response<Self::StreamStream>, Status> {
+        let owner_id = owner_id(&request)?;
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let mut logs = self
+            .worker
+            .stream(job_id, owner_id)
+            .await
+            .map_err(to_status)?
+            .with_follow(request.get_ref().follow);
+
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            let mut buf = vec![0; CHUNK_SIZE];
+            loop {
+                let chunk = match logs.read(&mut buf).await {
+                    Ok(0) => return,
+                    Ok(n) => Ok(LogChunk {
+                        data: buf[..n].to_vec(),
+                    }),
+                    Err(e) => Err(Status::internal(format!(
+                        "failed to read log file: {:?}",
+                        e
+                    ))),
+                };
+                let failed = chunk.is_err();
+                // a send error means the client has gone away
+                if tx.send(chunk).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn list(&self, request: Request<ListRequest>) -> Result<Response<ListResponse>, Status> {
+        let owner_id = owner_id(&request)?;
+        let jobs = self.worker.list(owner_id).await.map_err(to_status)?;
+        Ok(Response::new(ListResponse {
+            jobs: jobs.into_iter().map(proto::JobInfo::from).collect(),
+        }))
+    }
+
+    async fn wait(
+        &self,
+        request: Request<WaitRequest>,
+    ) -> Result<Response<proto::JobInfo>, Status> {
+        let owner_id = owner_id(&request)?;
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        let timeout = request.get_ref().timeout_ms.map(Duration::from_millis);
+        let job_info = self
+            .worker
+            .wait(job_id, owner_id, timeout)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(job_info.into()))
+    }
+
+    async fn purge(
+        &self,
+        request: Request<PurgeRequest>,
+    ) -> Result<Response<PurgeResponse>, Status> {
+        let owner_id = owner_id(&request)?;
+        let job_id = parse_job_id(&request.get_ref().job_id)?;
+        self.worker
+            .purge(job_id, owner_id)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(PurgeResponse {}))
+    }
+}
+
+fn owner_id<T>(request: &Request<T>) -> Result<Uuid, Status> {
+    owner_id_from_peer(
+        request.extensions().get::<UdsConnectInfo>(),
+        request.peer_certs(),
+    )
+    .map_err(Status::unauthenticated)
+}
+
+fn parse_job_id(job_id: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(job_id)
+        .map_err(|_| Status::invalid_argument(format!("invalid job id {:?}", job_id)))
+}
+
+fn to_status(e: Error) -> Status {
+    match e {
+        // lookups of another owner's job fail the same way as unknown jobs
+        Error::NotFound(e) => Status::not_found(e),
+        Error::PermissionDenied(e) => Status::permission_denied(e),
+        Error::Timeout(e) => Status::deadline_exceeded(e),
+        Error::JobStartErr(e)
+        | Error::JobStopErr(e)
+        | Error::JobSignalErr(e)
+        | Error::JobPurgeErr(e) => Status::failed_precondition(e),
+        e => Status::internal(e.as_str()),
+    }
+}
+
+impl From<Command> for proto::Command {
+    fn from(command: Command) -> Self {
+        proto::Command {
+            name: command.name(),
+            args: command.args(),
+            mounts: command
+                .mounts()
+                .into_iter()
+                .map(|mount| proto::Mount {
+                    source: mount.source().into(),
+                    target: mount.target().into(),
+                    read_only: mount.read_only(),
+                })
+                .collect(),
+            network: proto::Network::from(command.network()) as i32,
+            rootfs: command.rootfs().map(|rootfs| proto::Rootfs {
+                source: Some(match rootfs {
+                    Rootfs::Directory(path) => proto::rootfs::Source::Directory(path),
+                    Rootfs::Tarball(path) => proto::rootfs::Source::Tarball(path),
+                }),
+            }),
+            seccomp: command.seccomp().map(proto::Seccomp::from),
+            capabilities: command.capabilities().map(proto::Capabilities::from),
+            no_new_privs: command.no_new_privs(),
+            landlock: command.landlock().map(proto::Landlock::from),
+            rlimits: command.rlimits().map(proto::Rlimits::from),
+            hostname: command.hostname(),
+            mount_namespace: command.mount_namespace(),
+            read_only_root: command.read_only_root(),
+        }
+    }
+}
+
+impl From<Rlimits> for proto::Rlimits {
+    fn from(rlimits: Rlimits) -> Self {
+        proto::Rlimits {
+            nofile: rlimits.nofile(),
+            core: rlimits.core(),
+            address_space: rlimits.address_space(),
+            cpu: rlimits.cpu(),
+            nproc: rlimits.nproc(),
+        }
+    }
+}
+
+impl From<proto::Rlimits> for Rlimits {
+    fn from(proto_rlimits: proto::Rlimits) -> Self {
+        let mut rlimits = Rlimits::default();
+        if let Some(nofile) = proto_rlimits.nofile {
+            rlimits = rlimits.with_nofile(nofile);
+        }
+        if let Some(core) = proto_rlimits.core {
+            rlimits = rlimits.with_core(core);
+        }
+        if let Some(address_space) = proto_rlimits.address_space {
+            rlimits = rlimits.with_address_space(address_space);
+        }
+        if let Some(cpu) = proto_rlimits.cpu {
+            rlimits = rlimits.with_cpu(cpu);
+        }
+        if let Some(nproc) = proto_rlimits.nproc {
+            rlimits = rlimits.with_nproc(nproc);
+        }
+        rlimits
+    }
+}
+
+impl From<Landlock> for proto::Landlock {
+    fn from(landlock: Landlock) -> Self {
+        proto::Landlock {
+            read_only: landlock.read_only().to_vec(),
+            read_write: landlock.read_write().to_vec(),
+            unsupported: proto::LandlockFallback::from(landlock.unsupported()) as i32,
+        }
+    }
+}
+
+impl From<proto::Landlock> for Landlock {
+    fn from(landlock: proto::Landlock) -> Self {
+        let unsupported = landlock.unsupported().into();
+        Landlock::new(landlock.read_only, landlock.read_write).with_unsupported(unsupported)
+    }
+}
+
+impl From<LandlockFallback> for proto::LandlockFallback {
+    fn from(fallback: LandlockFallback) -> Self {
+        match fallback {
+            LandlockFallback::Fail => proto::LandlockFallback::Fail,
+            LandlockFallback::Warn => proto::LandlockFallback::Warn,
+        }
+    }
+}
+
+impl From<proto::LandlockFallback> for LandlockFallback {
+    fn from(fallback: proto::LandlockFallback) -> Self {
+        match fallback {
+            proto::LandlockFallback::Fail => LandlockFallback::Fail,
+            proto::LandlockFallback::Warn => LandlockFallback::Warn,
+        }
+    }
+}
+
+impl From<Capabilities> for proto::Capabilities {
+    fn from(capabilities: Capabilities) -> Self {
+        let set = |names: Option<&[String]>| {
+            names.map(|names| proto::CapabilitySet {
+                names: names.to_vec(),
+            })
+        };
+        proto::Capabilities {
+            bounding: set(capabilities.bounding()),
+            effective: set(capabilities.effective()),
+            ambient: set(capabilities.ambient()),
+        }
+    }
+}
+
+impl From<proto::Capabilities> for Capabilities {
+    fn from(proto_capabilities: proto::Capabilities) -> Self {
+        let mut capabilities = Capabilities::default();
+        if let Some(bounding) = proto_capabilities.bounding {
+            capabilities = capabilities.with_bounding(bounding.names);
+        }
+        if let Some(effective) = proto_capabilities.effective {
+            capabilities = capabilities.with_effective(effective.names);
+        }
+        if let Some(ambient) = proto_capabilities.ambient {
+            capabilities = capabilities.with_ambient(ambient.names);
+        }
+        capabilities
+    }
+}
+
+impl From<Seccomp> for proto::Seccomp {
+    fn from(seccomp: Seccomp) -> Self {
+        proto::Seccomp {
+            filter: Some(match seccomp {
+                Seccomp::Profile(profile) => {
+                    proto::seccomp::Filter::Profile(proto::SeccompProfile::from(profile) as i32)
+                }
+                Seccomp::Allow(names) => proto::seccomp::Filter::Allow(proto::Syscalls { names }),
+                Seccomp::Deny(names) => proto::seccomp::Filter::Deny(proto::Syscalls { names }),
+            }),
+        }
+    }
+}
+
+// An unknown profile is refused rather than replaced by another one, which
+// could let through syscalls the caller meant to deny.
+impl TryFrom<proto::seccomp::Filter> for Seccomp {
+    type Error = String;
+
+    fn try_from(filter: proto::seccomp::Filter) -> Result<Self, String> {
+        Ok(match filter {
+            proto::seccomp::Filter::Profile(profile) => Seccomp::Profile(
+                proto::SeccompProfile::try_from(profile)
+                    .map_err(|_| format!("unknown seccomp profile {}", profile))?
+                    .into(),
+            ),
+            proto::seccomp::Filter::Allow(syscalls) => Seccomp::Allow(syscalls.names),
+            proto::seccomp::Filter::Deny(syscalls) => Seccomp::Deny(syscalls.names),
+        })
+    }
+}
+
+impl From<SeccompProfile> for proto::SeccompProfile {
+    fn from(profile: SeccompProfile) -> Self {
+        match profile {
+            SeccompProfile::DefaultDenyDangerous => proto::SeccompProfile::DefaultDenyDangerous,
+            SeccompProfile::NoNetworkSyscalls => proto::SeccompProfile::NoNetworkSyscalls,
+            SeccompProfile::NoPtrace => proto::SeccompProfile::NoPtrace,
+        }
+    }
+}
+
+impl From<proto::SeccompProfile> for SeccompProfile {
+    fn from(profile: proto::SeccompProfile) -> Self {
+        match profile {
+            proto::SeccompProfile::DefaultDenyDangerous => SeccompProfile::DefaultDenyDangerous,
+            proto::SeccompProfile::NoNetworkSyscalls => SeccompProfile::NoNetworkSyscalls,
+            proto::SeccompProfile::NoPtrace => SeccompProfile::NoPtrace,
+        }
+    }
+}
+
+impl From<Network> for proto::Network {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Host => proto::Network::Host,
+            Network::Loopback => proto::Network::Loopback,
+        }
+    }
+}
+
+impl From<proto::Network> for Network {
+    fn from(network: proto::Network) -> Self {
+        match network {
+            proto::Network::Host => Network::Host,
+            proto::Network::Loopback => Network::Loopback,
+        }
+    }
+}
+
+impl From<Isolation> for proto::Isolation {
+    fn from(isolation: Isolation) -> Self {
+        proto::Isolation {
+            pid_namespace: isolation.pid_namespace(),
+            mount_namespace: isolation.mount_namespace(),
+            read_only_root: isolation.read_only_root(),
+            network: proto::Network::from(isolation.network()) as i32,
+            user_namespace: isolation.user_namespace().map(|user_namespace| {
+                let id_maps = |maps: &[IdMap]| {
+                    maps.iter()
+                        .map(|map| proto::IdMap {
+                            inside: map.inside(),
+                            outside: map.outside(),
+                            count: map.count(),
+                        })
+                        .collect()
+                };
+                proto::UserNamespace {
+                    uid_map: id_maps(user_namespace.uid_map()),
+                    gid_map: id_maps(user_namespace.gid_map()),
+                }
+            }),
+            seccomp: isolation.seccomp().cloned().map(proto::Seccomp::from),
+            capabilities: Some(isolation.capabilities().clone().into()),
+            no_new_privs: isolation.no_new_privs(),
+            landlock: isolation
+                .landlock()
+                .iter()
+                .cloned()
+                .map(proto::Landlock::from)
+                .collect(),
+            rlimits: isolation.rlimits().cloned().map(proto::Rlimits::from),
+            uts_namespace: isolation.uts_namespace(),
+            hostname: isolation.hostname().map(String::from),
+        }
+    }
+}
+
+impl TryFrom<proto::Isolation> for Isolation {
+    type Error = String;
+
+    fn try_from(proto_isolation: proto::Isolation) -> Result<Self, String> {
+        let mut isolation = Isolation::default().with_network(proto_isolation.network().into());
+        if proto_isolation.pid_namespace {
+            isolation = isolation.with_pid_namespace();
+        }
+        if proto_isolation.mount_namespace {
+            isolation = isolation.with_mount_namespace();
+        }
+        if proto_isolation.read_only_root {
+            isolation = isolation.with_read_only_root();
+        }
+        if let Some(user_namespace) = proto_isolation.user_namespace {
+            let id_maps = |maps: Vec<proto::IdMap>| {
+                maps.into_iter()
+                    .map(|map| IdMap::new(map.inside, map.outside, map.count))
+                    .collect()
+            };
+            isolation = isolation.with_user_namespace(
+                UserNamespace::new()
+                    .with_uid_map(id_maps(user_namespace.uid_map))
+                    .with_gid_map(id_maps(user_namespace.gid_map)),
+            );
+        }
+        if let Some(filter) = proto_isolation.seccomp.and_then(|seccomp| seccomp.filter) {
+            isolation = isolation.with_seccomp(filter.try_into()?);
+        }
+        if let Some(capabilities) = proto_isolation.capabilities {
+            isolation = isolation.with_capabilities(capabilities.into());
+        }
+        if proto_isolation.no_new_privs {
+            isolation = isolation.with_no_new_privs();
+        }
+        for landlock in proto_isolation.landlock {
+            isolation = isolation.with_landlock(landlock.into());
+        }
+        if let Some(rlimits) = proto_isolation.rlimits {
+            isolation = isolation.with_rlimits(rlimits.into());
+        }
+        if proto_isolation.uts_namespace {
+            isolation = isolation.with_uts_namespace();
+        }
+        if let Some(hostname) = proto_isolation.hostname {
+            isolation = isolation.with_hostname(hostname);
+        }
+        Ok(isolation)
+    }
+}
+
+impl From<JobInfo> for proto::JobInfo {
+    fn from(job_info: JobInfo) -> Self {
+        proto::JobInfo {
+            version: job_info.version(),
+            job_id: job_info.id().to_string(),
+            status: job_info.status().to_string(),
+            pid: job_info.pid(),
+            exit_code: job_info.exit_code(),
+            command: Some(job_info.command().into()),
+            isolation: Some(job_info.isolation().into()),
+            state: proto::JobState::from(job_info.status()) as i32,
+        }
+    }
+}
+
+impl From<JobStatus> for proto::JobState {
+    fn from(status: JobStatus) -> Self {
+        match status {
+            JobStatus::UnknownState => proto::JobState::Unknown,
+            JobStatus::Running => proto::JobState::Running,
+            JobStatus::Exited(_) => proto::JobState::Exited,
+            JobStatus::Stopped(StopType::Term) => proto::JobState::Terminated,
+            JobStatus::Stopped(StopType::Kill) => proto::JobState::Killed,
+            JobStatus::KilledBySeccomp => proto::JobState::KilledBySeccomp,
+            JobStatus::KilledByCpuLimit => proto::JobState::KilledByCpuLimit,
+        }
+    }
+}
+
+impl TryFrom<proto::Command> for Command {
+    type Error = String;
+
+    fn try_from(command: proto::Command) -> Result<Self, String> {
+        let network = command.network().into();
+        let seccomp = command
+            .seccomp
+            .and_then(|seccomp| seccomp.filter)
+            .map(Seccomp::try_from)
+            .transpose()?;
+        let capabilities = command.capabilities.map(Capabilities::from);
+        let no_new_privs = command.no_new_privs;
+        let landlock = command.landlock.map(Landlock::from);
+        let rlimits = command.rlimits.map(Rlimits::from);
+        let hostname = command.hostname;
+        let (mount_namespace, read_only_root) = (command.mount_namespace, command.read_only_root);
+        let rootfs = command
+            .rootfs
+            .and_then(|rootfs| rootfs.source)
+            .map(|source| match source {
+                proto::rootfs::Source::Directory(path) => Rootfs::Directory(path),
+                proto::rootfs::Source::Tarball(path) => Rootfs::Tarball(path),
+            });
+        let mounts = command
+            .mounts
+            .into_iter()
+            .map(|mount| {
+                let bind = Mount::new(mount.source, mount.target);
+                match mount.read_only {
+                    true => bind.with_read_only(),
+                    false => bind,
+                }
+            })
+            .collect();
+        let command = Command::new(command.name, command.args)
+            .with_mounts(mounts)
+            .with_network(network);
+        let command = match mount_namespace {
+            true => command.with_mount_namespace(),
+            false => command,
+        };
+        let command = match read_only_root {
+            true => command.with_read_only_root(),
+            false => command,
+        };
+        let command = match rootfs {
+            Some(rootfs) => command.with_rootfs(rootfs),
+            None => command,
+        };
+        let command = match seccomp {
+            Some(seccomp) => command.with_seccomp(seccomp),
+            None => command,
+        };
+        let command = match capabilities {
+            Some(capabilities) => command.with_capabilities(capabilities),
+            None => command,
+        };
+        let command = match no_new_privs {
+            true => command.with_no_new_privs(),
+            false => command,
+        };
+        let command = match landlock {
+            Some(landlock) => command.with_landlock(landlock),
+            None => command,
+        };
+        let command = match rlimits {
+            Some(rlimits) => command.with_rlimits(rlimits),
+            None => command,
+        };
+        Ok(match hostname {
+            Some(hostname) => command.with_hostname(hostname),
+            None => command,
+        })
+    }
+}
+
+impl TryFrom<proto::JobInfo> for JobInfo {
+    type Error = Error;
+
+    fn try_from(job_info: proto::JobInfo) -> Result<Self, Error> {
+        let id = Uuid::parse_str(&job_info.job_id)
+            .map_err(|_| Error::WorkerErr(format!("invalid job id {:?}", job_info.job_id)))?;
+        let status = match job_info.state() {
+            proto::JobState::Unknown => JobStatus::UnknownState,
+            proto::JobState::Running => JobStatus::Running,
+            proto::JobState::Exited => JobStatus::Exited(job_info.exit_code),
+            proto::JobState::Terminated => JobStatus::Stopped(StopType::Term),
+            proto::JobState::Killed => JobStatus::Stopped(StopType::Kill),
+            proto::JobState::KilledBySeccomp => JobStatus::KilledBySeccomp,
+            proto::JobState::KilledByCpuLimit => JobStatus::KilledByCpuLimit,
+        };
+        let command = job_info
+            .command
+            .ok_or_else(|| Error::WorkerErr(format!("job {} has no command", id)))?
+            .try_into()
+            .map_err(Error::WorkerErr)?;
+        let isolation = job_info
+            .isolation
+            .unwrap_or_default()
+            .try_into()
+            .map_err(Error::WorkerErr)?;
+        Ok(
+            JobInfo::new(id, status, job_info.pid, job_info.exit_code, command)
+                .with_isolation(isolation),
+        )
+    }
+}
@@ -0,0 +1,177 @@
+use super::{Config, Error, Event, EventFilter, Operation, Worker};
+use crate::{
+    executor::{Executor, NativeExecutor},
+    job::{Command, Job, JobInfo},
+};
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, ReadBuf},
+    sync::mpsc::Receiver,
+    time::Sleep,
+};
+use uuid::Uuid;
+
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+// Async facade over Worker. Calls that fork, signal or touch the journal run
+// on tokio's blocking pool, everything else is answered in place.
+pub struct AsyncWorker<E: Executor = NativeExecutor> {
+    worker: Arc<Worker<E>>,
+}
+
+impl<E: Executor> Clone for AsyncWorker<E> {
+    fn clone(&self) -> Self {
+        AsyncWorker {
+            worker: self.worker.clone(),
+        }
+    }
+}
+
+impl AsyncWorker<NativeExecutor> {
+    pub fn new(cfg: Config) -> Result<Self, Error> {
+        Ok(AsyncWorker {
+            worker: Arc::new(Worker::new(cfg)?),
+        })
+    }
+}
+
+impl<E: Executor> AsyncWorker<E> {
+    pub fn with_executor(cfg: Config, executor: E) -> Result<Self, Error> {
+        Ok(AsyncWorker {
+            worker: Arc::new(Worker::with_executor(cfg, executor)?),
+        })
+    }
+
+    pub fn worker(&self) -> &Worker<E> {
+        &self.worker
+    }
+
+    pub async fn start(&self, command: Command, owner_id: Uuid) -> Result<Uuid, Error> {
+        let worker = self.worker.clone();
+        tokio::task::spawn_blocking(move || worker.start(command, owner_id))
+            .await
+            .map_err(|e| Error::JobStartErr(format!("start task failed: {:?}", e)))?
+    }
+
+    pub async fn stop(&self, job_id: Uuid, owner_id: Uuid, gracefully: bool) -> Result<(), Error> {
+        let worker = self.worker.clone();
+        tokio::task::spawn_blocking(move || worker.stop(job_id, owner_id, gracefully))
+            .await
+            .map_err(|e| Error::JobStopErr(format!("stop task failed: {:?}", e)))?
+    }
+
+    pub async fn signal(&self, job_id: Uuid, owner_id: Uuid, signal: i32) -> Result<(), Error> {
+        self.worker.signal(job_id, owner_id, signal)
+    }
+
+    pub async fn query(&self, job_id: Uuid, owner_id: Uuid) -> Result<JobInfo, Error> {
+        self.worker.query(job_id, owner_id)
+    }
+
+    pub async fn purge(&self, job_id: Uuid, owner_id: Uuid) -> Result<(), Error> {
+        let worker = self.worker.clone();
+        tokio::task::spawn_blocking(move || worker.purge(job_id, owner_id))
+            .await
+            .map_err(|e| Error::JobPurgeErr(format!("purge task failed: {:?}", e)))?
+    }
+
+    pub async fn list(&self, owner_id: Uuid) -> Result<Vec<JobInfo>, Error> {
+        self.worker.list(owner_id)
+    }
+
+    pub async fn wait(
+        &self,
+        job_id: Uuid,
+        owner_id: Uuid,
+        timeout: Option<Duration>,
+    ) -> Result<JobInfo, Error> {
+        let job = self
+            .worker
+            .authorized_job(job_id, owner_id, Operation::Query)?
+            .ok_or_else(|| Error::NotFound(format!("no job with id {} found for user", job_id)))?;
+        // subscribe before checking so an exit between the two is not missed
+        let mut events = self.worker.events.subscribe_async(EventFilter::job(job_id));
+        let finished = async {
+            while !job.is_finished() {
+                if events.recv().await.is_none() {
+                    break;
+                }
+            }
+        };
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, finished)
+                .await
+                .map_err(|_| Error::Timeout(format!("timed out waiting for job {}", job_id)))?,
+            None => finished.await,
+        }
+        job.query()
+    }
+
+    pub async fn stream(&self, job_id: Uuid, owner_id: Uuid) -> Result<LogStream, Error> {
+        let job = self
+            .worker
+            .authorized_job(job_id, owner_id, Operation::Stream)?
+            .ok_or_else(|| Error::NotFound(format!("no job with id {} found for user", job_id)))?;
+        let file = tokio::fs::File::open(job.log_path(self.worker.cfg.log_dir()))
+            .await
+            .map_err(|e| Error::JobStreamErr(format!("failed to open log file: {:?}", e)))?;
+        Ok(LogStream {
+            file,
+            job,
+            follow: true,
+            delay: None,
+        })
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> Receiver<Event> {
+        self.worker.events.subscribe_async(filter)
+    }
+}
+
+// Reads a job's log file, following it as it grows until the job finishes.
+pub struct LogStream {
+    file: tokio::fs::File,
+    job: Box<Job>,
+    follow: bool,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl LogStream {
+    // Without following, the stream ends at the current end of the log.
+    pub fn with_follow(mut self, follow: bool) -> Self {
+        self.follow = follow;
+        self
+    }
+}
+
+impl AsyncRead for LogStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(delay) = this.delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                this.delay = None;
+            }
+            // checked before reading so output written just before the job
+            // finished is still returned
+            let finished = this.job.is_finished();
+            let filled = buf.filled().len();
+            ready!(Pin::new(&mut this.file).poll_read(cx, buf))?;
+            if buf.filled().len() > filled || finished || !this.follow {
+                return Poll::Ready(Ok(()));
+            }
+            this.delay = Some(Box::pin(tokio::time::sleep(FOLLOW_INTERVAL)));
+        }
+    }
+}
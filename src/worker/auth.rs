@@ -0,0 +1,240 @@
+use super::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Namespace of the owner ids derived from names, such as certificate common
+// names, that are not UUIDs themselves.
+pub const OWNER_NAMESPACE: Uuid = Uuid::from_u128(0x6d1c_9a4e_52b3_4f0e_9b7a_1e2f_3c4d_5e6f);
+
+pub fn owner_id_from_name(name: &str) -> Uuid {
+    if let Some(uid) = name
+        .strip_prefix(UID_NAME_PREFIX)
+        .and_then(|uid| uid.parse().ok())
+    {
+        return owner_id_from_uid(uid);
+    }
+    Uuid::parse_str(name).unwrap_or_else(|_| Uuid::new_v5(&OWNER_NAMESPACE, name.as_bytes()))
+}
+
+// Prefix of the names of local users, which no other name may start with.
+pub const UID_NAME_PREFIX: &str = "uid:";
+
+// Owner ids of local users are their uid behind this prefix, a v8 layout that
+// no id hashed from a name shares, so ids claimed by anybody else can be told
+// apart from them.
+const UID_OWNER_PREFIX: u128 = 0x7569_6400_0000_8000_8000_0000_0000_0000;
+
+// Owner id of a local user, named uid:<uid> in policies.
+pub fn owner_id_from_uid(uid: u32) -> Uuid {
+    Uuid::from_u128(UID_OWNER_PREFIX | uid as u128)
+}
+
+pub fn is_uid_owner_id(owner_id: Uuid) -> bool {
+    owner_id.as_u128() >> 32 == UID_OWNER_PREFIX >> 32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Operator,
+    ReadOnly,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Admin => "admin",
+            Self::Operator => "operator",
+            Self::ReadOnly => "read_only",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Start,
+    Stop,
+    // sending signals other than the stop signals
+    Signal,
+    Query,
+    Stream,
+    Purge,
+}
+
+impl Operation {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Signal => "signal",
+            Self::Query => "query",
+            Self::Stream => "stream",
+            Self::Purge => "purge",
+        }
+    }
+}
+
+// Whose jobs a role's operations apply to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Own,
+    All,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Permissions {
+    operations: Vec<Operation>,
+    scope: Scope,
+}
+
+impl Permissions {
+    pub fn new(operations: Vec<Operation>, scope: Scope) -> Self {
+        Permissions { operations, scope }
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    pub fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    fn defaults(role: Role) -> Self {
+        use Operation::*;
+        match role {
+            Role::Admin => {
+                Permissions::new(vec![Start, Stop, Signal, Query, Stream, Purge], Scope::All)
+            }
+            Role::Operator => {
+                Permissions::new(vec![Start, Stop, Signal, Query, Stream, Purge], Scope::Own)
+            }
+            Role::ReadOnly => Permissions::new(vec![Query, Stream], Scope::All),
+        }
+    }
+}
+
+// Maps callers to roles and roles to the operations they may perform. Out of
+// the box everybody is an operator, who may do anything to their own jobs and
+// nothing to anybody else's.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Policy {
+    default_role: Role,
+    members: HashMap<Uuid, Role>,
+    permissions: HashMap<Role, Permissions>,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            default_role: Role::Operator,
+            members: HashMap::new(),
+            permissions: [Role::Admin, Role::Operator, Role::ReadOnly]
+                .into_iter()
+                .map(|role| (role, Permissions::defaults(role)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PolicyFile {
+    default_role: Option<Role>,
+    #[serde(default)]
+    roles: HashMap<Role, RoleFile>,
+}
+
+#[derive(Deserialize)]
+struct RoleFile {
+    // owner ids, or names mapped to them with owner_id_from_name, including
+    // local users named uid:<uid>
+    #[serde(default)]
+    members: Vec<String>,
+    operations: Option<Vec<Operation>>,
+    scope: Option<Scope>,
+}
+
+impl Policy {
+    // Reads a policy file in any format the config crate understands, e.g.
+    //
+    //   default_role = "read_only"
+    //   [roles.admin]
+    //   members = ["alice"]
+    //   [roles.operator]
+    //   members = ["bob"]
+    //   operations = ["start", "stop", "query", "stream"]
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let file: PolicyFile = config::Config::builder()
+            .add_source(config::File::with_name(path))
+            .build()
+            .and_then(|cfg| cfg.try_deserialize())
+            .map_err(|e| Error::WorkerErr(format!("failed to load policy: {}", e)))?;
+
+        let mut policy = Policy::default();
+        if let Some(default_role) = file.default_role {
+            policy = policy.with_default_role(default_role);
+        }
+        for (role, role_file) in file.roles {
+            for member in role_file.members {
+                policy = policy.with_member(owner_id_from_name(&member), role);
+            }
+            let defaults = Permissions::defaults(role);
+            policy = policy.with_permissions(
+                role,
+                Permissions::new(
+                    role_file.operations.unwrap_or(defaults.operations),
+                    role_file.scope.unwrap_or(defaults.scope),
+                ),
+            );
+        }
+        Ok(policy)
+    }
+
+    pub fn with_default_role(mut self, role: Role) -> Self {
+        self.default_role = role;
+        self
+    }
+
+    pub fn with_member(mut self, owner_id: Uuid, role: Role) -> Self {
+        self.members.insert(owner_id, role);
+        self
+    }
+
+    pub fn with_permissions(mut self, role: Role, permissions: Permissions) -> Self {
+        self.permissions.insert(role, permissions);
+        self
+    }
+
+    pub fn role(&self, owner_id: Uuid) -> Role {
+        self.members
+            .get(&owner_id)
+            .copied()
+            .unwrap_or(self.default_role)
+    }
+
+    pub fn permissions(&self, role: Role) -> Permissions {
+        self.permissions
+            .get(&role)
+            .cloned()
+            .unwrap_or_else(|| Permissions::defaults(role))
+    }
+
+    // Returns whose jobs owner_id may perform operation on. Denials do not
+    // depend on any job, so they reveal nothing about which jobs exist.
+    pub fn authorize(&self, owner_id: Uuid, operation: Operation) -> Result<Scope, Error> {
+        let role = self.role(owner_id);
+        let permissions = self.permissions(role);
+        if !permissions.operations.contains(&operation) {
+            return Err(Error::PermissionDenied(format!(
+                "{} role may not {} jobs",
+                role.as_str(),
+                operation.as_str()
+            )));
+        }
+        Ok(permissions.scope)
+    }
+}
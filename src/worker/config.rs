@@ -1,7 +1,17 @@
+use super::{Policy, DEFAULT_NOTIFY_CAPACITY};
+use crate::job::Isolation;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     log_dir: &'static str,
     thread_count: usize,
+    state_dir: Option<&'static str>,
+    recover_orphans: bool,
+    notify_capacity: usize,
+    policy: Policy,
+    isolation: Isolation,
+    // host paths beneath which jobs may ask for bind mounts, none by default
+    mount_sources: Vec<String>,
 }
 
 impl Config {
@@ -9,14 +19,43 @@ impl Config {
         Config {
             log_dir,
             thread_count,
+            state_dir: None,
+            recover_orphans: false,
+            notify_capacity: DEFAULT_NOTIFY_CAPACITY,
+            policy: Policy::default(),
+            isolation: Isolation::default(),
+            mount_sources: Vec::new(),
         }
     }
 
-    pub fn default() -> Self {
-        Config {
-            log_dir: "/tmp",
-            thread_count: 4,
-        }
+    pub fn with_state_dir(mut self, state_dir: &'static str) -> Self {
+        self.state_dir = Some(state_dir);
+        self
+    }
+
+    pub fn with_orphan_recovery(mut self) -> Self {
+        self.recover_orphans = true;
+        self
+    }
+
+    pub fn with_notify_capacity(mut self, notify_capacity: usize) -> Self {
+        self.notify_capacity = notify_capacity;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_isolation(mut self, isolation: Isolation) -> Self {
+        self.isolation = isolation;
+        self
+    }
+
+    pub fn with_mount_sources(mut self, mount_sources: Vec<String>) -> Self {
+        self.mount_sources = mount_sources;
+        self
     }
 
     pub fn log_dir(&self) -> &'static str {
@@ -26,4 +65,43 @@ impl Config {
     pub fn thread_count(&self) -> usize {
         self.thread_count
     }
+
+    pub fn state_dir(&self) -> Option<&'static str> {
+        self.state_dir
+    }
+
+    pub fn recover_orphans(&self) -> bool {
+        self.recover_orphans
+    }
+
+    pub fn notify_capacity(&self) -> usize {
+        self.notify_capacity
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    pub fn isolation(&self) -> &Isolation {
+        &self.isolation
+    }
+
+    pub fn mount_sources(&self) -> &[String] {
+        &self.mount_sources
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            log_dir: "/tmp",
+            thread_count: 4,
+            state_dir: None,
+            recover_orphans: false,
+            notify_capacity: DEFAULT_NOTIFY_CAPACITY,
+            policy: Policy::default(),
+            isolation: Isolation::default(),
+            mount_sources: Vec::new(),
+        }
+    }
 }
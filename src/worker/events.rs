@@ -0,0 +1,156 @@
+use crate::job::StopType;
+use crossbeam::channel::{Receiver, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    job_id: Uuid,
+    owner_id: Uuid,
+    kind: EventKind,
+}
+
+impl Event {
+    pub fn new(job_id: Uuid, owner_id: Uuid, kind: EventKind) -> Self {
+        Event {
+            job_id,
+            owner_id,
+            kind,
+        }
+    }
+
+    pub fn job_id(&self) -> Uuid {
+        self.job_id
+    }
+
+    pub fn owner_id(&self) -> Uuid {
+        self.owner_id
+    }
+
+    pub fn kind(&self) -> EventKind {
+        self.kind.clone()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventKind {
+    Started { pid: u32 },
+    Stopped { stop_type: StopType },
+    Exited { exit_code: Option<i32> },
+    KilledBySeccomp,
+    KilledByCpuLimit,
+    Failed { error: String },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EventFilter {
+    owner_id: Option<Uuid>,
+    job_id: Option<Uuid>,
+}
+
+impl EventFilter {
+    pub fn all() -> Self {
+        EventFilter::default()
+    }
+
+    pub fn owner(owner_id: Uuid) -> Self {
+        EventFilter {
+            owner_id: Some(owner_id),
+            job_id: None,
+        }
+    }
+
+    pub fn job(job_id: Uuid) -> Self {
+        EventFilter {
+            owner_id: None,
+            job_id: Some(job_id),
+        }
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        self.owner_id.is_none_or(|id| id == event.owner_id)
+            && self.job_id.is_none_or(|id| id == event.job_id)
+    }
+}
+
+struct Subscriber {
+    filter: EventFilter,
+    sink: Sink,
+}
+
+enum Sink {
+    Blocking(Sender<Event>),
+    #[cfg(feature = "async")]
+    Async(tokio::sync::mpsc::Sender<Event>),
+}
+
+impl Sink {
+    // Returns false once the receiving end has gone away.
+    fn send(&self, event: Event) -> bool {
+        match self {
+            Sink::Blocking(tx) => match tx.try_send(event) {
+                Ok(_) => true,
+                Err(TrySendError::Full(event)) => {
+                    log::warn!("subscriber is lagging, dropping event {:?}", event);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+            #[cfg(feature = "async")]
+            Sink::Async(tx) => match tx.try_send(event) {
+                Ok(_) => true,
+                Err(tokio::sync::mpsc::error::TrySendError::Full(event)) => {
+                    log::warn!("subscriber is lagging, dropping event {:?}", event);
+                    true
+                }
+                Err(tokio::sync::mpsc::error::TrySendError::Closed(_)) => false,
+            },
+        }
+    }
+}
+
+// Fans every job lifecycle event out to each subscriber whose filter matches.
+// Publishing never blocks: a subscriber that has fallen a full buffer behind
+// misses events until it catches up, and dropped receivers are forgotten.
+#[derive(Clone)]
+pub struct EventBus {
+    capacity: usize,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        EventBus {
+            capacity: capacity.max(1),
+            subscribers: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> Receiver<Event> {
+        let (tx, rx) = crossbeam::channel::bounded(self.capacity);
+        self.subscribers.lock().unwrap().push(Subscriber {
+            filter,
+            sink: Sink::Blocking(tx),
+        });
+        rx
+    }
+
+    #[cfg(feature = "async")]
+    pub fn subscribe_async(&self, filter: EventFilter) -> tokio::sync::mpsc::Receiver<Event> {
+        let (tx, rx) = tokio::sync::mpsc::channel(self.capacity);
+        self.subscribers.lock().unwrap().push(Subscriber {
+            filter,
+            sink: Sink::Async(tx),
+        });
+        rx
+    }
+
+    pub fn publish(&self, event: Event) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|subscriber| {
+            !subscriber.filter.matches(&event) || subscriber.sink.send(event.clone())
+        });
+    }
+}
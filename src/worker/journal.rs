@@ -0,0 +1,174 @@
+use super::Error;
+use crate::job::{Command, Isolation, Job, Status, StopType};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+pub const JOURNAL_FILENAME: &str = "journal.jsonl";
+
+// entries only live between being read or written a line at a time, so the
+// size of Started is not worth boxing it for
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Entry {
+    Started {
+        job_id: Uuid,
+        owner_id: Uuid,
+        command: Command,
+        pid: u32,
+        #[serde(default)]
+        start_time: Option<u64>,
+        #[serde(default)]
+        isolation: Isolation,
+    },
+    Stopped {
+        job_id: Uuid,
+        stop_type: StopType,
+    },
+    Exited {
+        job_id: Uuid,
+        exit_code: Option<i32>,
+    },
+    KilledBySeccomp {
+        job_id: Uuid,
+    },
+    KilledByCpuLimit {
+        job_id: Uuid,
+    },
+    Purged {
+        job_id: Uuid,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct Journal {
+    file: Arc<Mutex<File>>,
+}
+
+impl Journal {
+    pub fn open(state_dir: &str) -> Result<Self, Error> {
+        std::fs::create_dir_all(state_dir)
+            .map_err(|e| Error::WorkerErr(format!("failed to create state directory: {:?}", e)))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(Path::new(state_dir).join(JOURNAL_FILENAME))
+            .map_err(|e| Error::WorkerErr(format!("failed to open job journal: {:?}", e)))?;
+        Ok(Journal {
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    pub fn record(&self, entry: Entry) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(&entry)
+            .map_err(|e| Error::WorkerErr(format!("failed to serialize journal entry: {:?}", e)))?;
+        line.push(b'\n');
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|e| Error::WorkerErr(format!("failed to lock job journal: {:?}", e)))?;
+        file.write_all(&line)
+            .and_then(|_| file.sync_data())
+            .map_err(|e| Error::WorkerErr(format!("failed to write journal entry: {:?}", e)))
+    }
+
+    pub fn entries(&self) -> Result<Vec<Entry>, Error> {
+        let file = self
+            .file
+            .lock()
+            .map_err(|e| Error::WorkerErr(format!("failed to lock job journal: {:?}", e)))?;
+        let reader = BufReader::new(
+            file.try_clone()
+                .map_err(|e| Error::WorkerErr(format!("failed to read job journal: {:?}", e)))?,
+        );
+        let mut entries = vec![];
+        for line in reader.lines() {
+            let line =
+                line.map_err(|e| Error::WorkerErr(format!("failed to read job journal: {:?}", e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => log::warn!("skipping unreadable journal entry {:?}: {:?}", line, e),
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn replay(&self) -> Result<Vec<Box<Job>>, Error> {
+        let mut jobs: Vec<Box<Job>> = vec![];
+        for entry in self.entries()? {
+            match entry {
+                Entry::Started {
+                    job_id,
+                    owner_id,
+                    command,
+                    pid,
+                    start_time,
+                    isolation,
+                } => {
+                    let mut job = Job::new(
+                        job_id,
+                        command,
+                        pid,
+                        Arc::new(Mutex::new(Status::Running)),
+                        owner_id,
+                    );
+                    job.set_start_time(start_time);
+                    job.set_isolation(isolation);
+                    jobs.push(Box::new(job))
+                }
+                Entry::Stopped { job_id, stop_type } => {
+                    if let Some(job) = jobs.iter().find(|job| job.id() == job_id) {
+                        *job.status().lock().unwrap() = Status::Stopped(stop_type);
+                    }
+                }
+                Entry::Exited { job_id, exit_code } => {
+                    if let Some(job) = jobs.iter().find(|job| job.id() == job_id) {
+                        let status = job.status();
+                        let mut status = status.lock().unwrap();
+                        if !status.is_stopped() {
+                            *status = Status::Exited(exit_code);
+                        }
+                    }
+                }
+                Entry::KilledBySeccomp { job_id } => {
+                    if let Some(job) = jobs.iter().find(|job| job.id() == job_id) {
+                        let status = job.status();
+                        let mut status = status.lock().unwrap();
+                        if !status.is_stopped() {
+                            *status = Status::KilledBySeccomp;
+                        }
+                    }
+                }
+                Entry::KilledByCpuLimit { job_id } => {
+                    if let Some(job) = jobs.iter().find(|job| job.id() == job_id) {
+                        let status = job.status();
+                        let mut status = status.lock().unwrap();
+                        if !status.is_stopped() {
+                            *status = Status::KilledByCpuLimit;
+                        }
+                    }
+                }
+                Entry::Purged { job_id } => jobs.retain(|job| job.id() != job_id),
+            }
+        }
+        for job in jobs.iter() {
+            job.set_finished(true);
+            let status = job.status();
+            let mut status = status.lock().unwrap();
+            if *status == Status::Running {
+                *status = Status::UnknownState;
+            }
+        }
+        Ok(jobs)
+    }
+}
@@ -1,15 +1,33 @@
+#[cfg(feature = "async")]
+mod async_worker;
+mod auth;
 mod config;
-use crate::job::{Command, Job, JobInfo};
+mod events;
+mod journal;
+mod notify;
+mod reaper;
+use crate::{
+    executor::{Executor, ExitCallback, NativeExecutor},
+    job::{log_path, Command, Job, JobInfo, Status},
+};
+#[cfg(feature = "async")]
+pub use async_worker::{AsyncWorker, LogStream};
+pub use auth::{
+    is_uid_owner_id, owner_id_from_name, owner_id_from_uid, Operation, Permissions, Policy, Role,
+    Scope, OWNER_NAMESPACE, UID_NAME_PREFIX,
+};
 pub use config::Config;
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::Receiver;
+pub use events::{Event, EventBus, EventFilter, EventKind};
+pub use journal::{Entry, Journal};
+pub use notify::{Notifier, DEFAULT_NOTIFY_CAPACITY};
+pub use reaper::{ExitHandler, Reaper};
 use std::{
     fmt::Display,
     fs::File,
-    os::fd::AsRawFd,
-    path::Path,
     sync::{Arc, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use threadpool::ThreadPool;
 use uuid::Uuid;
@@ -20,8 +38,15 @@ pub enum Error {
     JobErr(Uuid, String),
     JobStartErr(String),
     JobStopErr(String),
+    JobSignalErr(String),
     JobQueryErr(String),
     JobStreamErr(String),
+    JobWaitErr(String),
+    JobPurgeErr(String),
+    PermissionDenied(String),
+    // the job is unknown, or belongs to another owner
+    NotFound(String),
+    Timeout(String),
 }
 
 impl Error {
@@ -31,8 +56,14 @@ impl Error {
             Self::JobErr(_, e) => e,
             Self::JobStartErr(e) => e,
             Self::JobStopErr(e) => e,
+            Self::JobSignalErr(e) => e,
             Self::JobQueryErr(e) => e,
             Self::JobStreamErr(e) => e,
+            Self::JobWaitErr(e) => e,
+            Self::JobPurgeErr(e) => e,
+            Self::PermissionDenied(e) => e,
+            Self::NotFound(e) => e,
+            Self::Timeout(e) => e,
         }
     }
 }
@@ -45,63 +76,137 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
-#[derive(Clone)]
-pub struct Worker {
+pub type JobResult = Result<(Uuid, bool), Error>;
+
+pub struct Worker<E: Executor = NativeExecutor> {
     cfg: Config,
     thread_pool: ThreadPool,
-    notify_chan: (
-        Sender<Result<(Uuid, bool), Error>>,
-        Receiver<Result<(Uuid, bool), Error>>,
-    ),
+    executor: Arc<E>,
+    notifier: Notifier,
+    events: EventBus,
+    journal: Option<Journal>,
     pub jobs: Arc<Mutex<Vec<Box<Job>>>>,
 }
 
-impl Worker {
+impl<E: Executor> Clone for Worker<E> {
+    fn clone(&self) -> Self {
+        Worker {
+            cfg: self.cfg.clone(),
+            thread_pool: self.thread_pool.clone(),
+            executor: self.executor.clone(),
+            notifier: self.notifier.clone(),
+            events: self.events.clone(),
+            journal: self.journal.clone(),
+            jobs: self.jobs.clone(),
+        }
+    }
+}
+
+impl Worker<NativeExecutor> {
     pub fn new(cfg: Config) -> Result<Self, Error> {
-        let (tx, rx) = crossbeam::channel::bounded(cfg.thread_count());
+        let executor = NativeExecutor::new()?
+            .with_isolation(cfg.isolation().clone())
+            .with_mount_sources(cfg.mount_sources().to_vec());
+        Worker::with_executor(cfg, executor)
+    }
+}
+
+impl<E: Executor> Worker<E> {
+    pub fn with_executor(cfg: Config, executor: E) -> Result<Self, Error> {
         let thread_pool = ThreadPool::new(cfg.thread_count());
+        let notifier = Notifier::new(cfg.notify_capacity());
+        let events = EventBus::new(cfg.notify_capacity());
+        let (journal, jobs) = match cfg.state_dir() {
+            Some(state_dir) => {
+                let journal = Journal::open(state_dir)?;
+                let jobs = journal.replay()?;
+                (Some(journal), jobs)
+            }
+            None => (None, vec![]),
+        };
         let worker = Worker {
             cfg,
             thread_pool,
-            notify_chan: (tx, rx),
-            jobs: Arc::new(Mutex::new(vec![])),
+            executor: Arc::new(executor),
+            notifier,
+            events,
+            journal,
+            jobs: Arc::new(Mutex::new(jobs)),
         };
         worker
             .create_log_dir()
             .map_err(|e| Error::WorkerErr(format!("failed to create log directory: {:?}", e)))?;
+        if worker.cfg.recover_orphans() {
+            worker.adopt_orphans();
+        }
         Ok(worker)
     }
 
     pub fn start(&self, command: Command, owner_id: Uuid) -> Result<Uuid, Error> {
+        self.cfg.policy().authorize(owner_id, Operation::Start)?;
         let job_id = Uuid::new_v4();
 
-        let log_filepath =
-            Path::new(self.cfg.log_dir()).join(format!("{}_{}.log", command.name(), job_id));
-        let log_file = File::create(&log_filepath)
+        let log_file = File::create(log_path(self.cfg.log_dir(), &command, job_id))
             .map_err(|e| Error::JobStartErr(format!("failed to create log file: {:?}", e)))?;
 
-        let (job, child_proc) = match Job::start(job_id, command, owner_id, log_file.as_raw_fd()) {
+        let (job, process) = match Job::start(job_id, command, owner_id, &log_file, &*self.executor)
+        {
             Ok((job, proc)) => (Box::new(job), proc),
-            Err(e) => return Err(e),
+            Err(e) => {
+                self.events.publish(Event::new(
+                    job_id,
+                    owner_id,
+                    EventKind::Failed {
+                        error: e.as_str().to_string(),
+                    },
+                ));
+                return Err(e);
+            }
         };
+        self.record(Entry::Started {
+            job_id,
+            owner_id,
+            command: job.command(),
+            pid: job.pid(),
+            start_time: job.start_time(),
+            isolation: job.isolation(),
+        });
         let mut jobs = self.jobs.lock().unwrap();
         jobs.push(job.clone());
+        self.events.publish(Event::new(
+            job_id,
+            owner_id,
+            EventKind::Started { pid: job.pid() },
+        ));
 
-        let sender = self.notify_chan.0.clone();
-        self.thread_pool.execute(move || {
-            if let Err(e) = sender.send(job.wait(child_proc)) {
-                panic!("failed to send job result from execution thread: {:?}", e);
-            };
-        });
+        let on_exit = self.on_exit(job);
+        self.executor.wait(job_id, process, on_exit);
 
         Ok(job_id)
     }
 
     pub fn stop(&self, job_id: Uuid, owner_id: Uuid, gracefully: bool) -> Result<(), Error> {
-        let sender = self.notify_chan.0.clone();
-        match self.find_job(job_id, owner_id) {
-            Some(job) => job.stop(gracefully, sender),
-            None => Err(Error::JobStopErr(format!(
+        match self.authorized_job(job_id, owner_id, Operation::Stop)? {
+            Some(job) => {
+                job.stop(gracefully, &*self.executor)?;
+                if let Status::Stopped(stop_type) = *job.status().lock().unwrap() {
+                    self.record(Entry::Stopped { job_id, stop_type });
+                }
+                Ok(())
+            }
+            None => Err(Error::NotFound(format!(
+                "no job with id {} found for user",
+                job_id
+            ))),
+        }
+    }
+
+    // Sends any signal but the stop signals to a running job, which stop is
+    // for so that the job's status says how it ended.
+    pub fn signal(&self, job_id: Uuid, owner_id: Uuid, signal: i32) -> Result<(), Error> {
+        match self.authorized_job(job_id, owner_id, Operation::Signal)? {
+            Some(job) => job.signal(signal, &*self.executor),
+            None => Err(Error::NotFound(format!(
                 "no job with id {} found for user",
                 job_id
             ))),
@@ -109,35 +214,190 @@ impl Worker {
     }
 
     pub fn query(&self, job_id: Uuid, owner_id: Uuid) -> Result<JobInfo, Error> {
-        match self.find_job(job_id, owner_id) {
+        match self.authorized_job(job_id, owner_id, Operation::Query)? {
             Some(job) => job.query(),
-            None => Err(Error::JobQueryErr(format!(
+            None => Err(Error::NotFound(format!(
                 "no job with id {} found for user",
                 job_id
             ))),
         }
     }
 
+    pub fn list(&self, owner_id: Uuid) -> Result<Vec<JobInfo>, Error> {
+        let scope = self.cfg.policy().authorize(owner_id, Operation::Query)?;
+        let jobs = self.jobs.lock().unwrap();
+        jobs.iter()
+            .filter(|job| scope == Scope::All || job.owner_id() == owner_id)
+            .map(|job| job.query())
+            .collect()
+    }
+
     pub fn stream(&self, job_id: Uuid, owner_id: Uuid) -> Result<std::io::BufReader<File>, Error> {
-        match self.find_job(job_id, owner_id) {
+        match self.authorized_job(job_id, owner_id, Operation::Stream)? {
             Some(job) => job.stream(self.cfg.log_dir()),
-            None => Err(Error::JobStreamErr(format!(
+            None => Err(Error::NotFound(format!(
                 "no job with id {} found for user",
                 job_id,
             ))),
         }
     }
 
-    pub fn notify_receiver(&self) -> Receiver<Result<(Uuid, bool), Error>> {
-        self.notify_chan.1.clone()
+    pub fn wait(
+        &self,
+        job_id: Uuid,
+        owner_id: Uuid,
+        timeout: Option<Duration>,
+    ) -> Result<JobInfo, Error> {
+        let job = self
+            .authorized_job(job_id, owner_id, Operation::Query)?
+            .ok_or_else(|| Error::NotFound(format!("no job with id {} found for user", job_id)))?;
+        // subscribe before checking so an exit between the two is not missed
+        let events = self.events.subscribe(EventFilter::job(job_id));
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        while !job.is_finished() {
+            let res = match deadline {
+                Some(deadline) => events.recv_deadline(deadline).map_err(|e| e.is_timeout()),
+                None => events.recv().map_err(|_| false),
+            };
+            match res {
+                Ok(_) => continue,
+                Err(true) => {
+                    return Err(Error::Timeout(format!(
+                        "timed out waiting for job {}",
+                        job_id
+                    )))
+                }
+                Err(false) => break,
+            }
+        }
+        job.query()
+    }
+
+    // Forgets a finished job and removes its log.
+    pub fn purge(&self, job_id: Uuid, owner_id: Uuid) -> Result<(), Error> {
+        let job = self
+            .authorized_job(job_id, owner_id, Operation::Purge)?
+            .ok_or_else(|| Error::NotFound(format!("no job with id {} found for user", job_id)))?;
+        if !job.is_finished() {
+            return Err(Error::JobPurgeErr(format!(
+                "job {} has not finished yet",
+                job_id
+            )));
+        }
+        self.jobs.lock().unwrap().retain(|job| job.id() != job_id);
+        self.record(Entry::Purged { job_id });
+        match std::fs::remove_file(job.log_path(self.cfg.log_dir())) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                log::warn!("failed to remove log of purged job {}: {:?}", job_id, e)
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub fn notify_receiver(&self) -> Receiver<JobResult> {
+        self.notifier.receiver()
+    }
+
+    pub fn subscribe(&self, filter: EventFilter) -> Receiver<Event> {
+        self.events.subscribe(filter)
     }
 
-    fn find_job(&self, job_id: Uuid, owner_id: Uuid) -> Option<Box<Job>> {
+    // Looks up a job that owner_id may perform operation on. Jobs outside the
+    // caller's scope are reported as missing, so their existence is not given
+    // away.
+    fn authorized_job(
+        &self,
+        job_id: Uuid,
+        owner_id: Uuid,
+        operation: Operation,
+    ) -> Result<Option<Box<Job>>, Error> {
+        let scope = self.cfg.policy().authorize(owner_id, operation)?;
         let jobs = self.jobs.lock().unwrap();
-        jobs.clone()
+        Ok(jobs
             .iter()
-            .find(|job| job.id() == job_id && job.owner_id() == owner_id)
-            .map(|job| job.clone())
+            .find(|job| job.id() == job_id && (scope == Scope::All || job.owner_id() == owner_id))
+            .cloned())
+    }
+
+    fn adopt_orphans(&self) {
+        let jobs = self.jobs.lock().unwrap();
+        for job in jobs.iter() {
+            let status = job.status();
+            let mut status = status.lock().unwrap();
+            if *status != Status::UnknownState {
+                continue;
+            }
+            // marked running before adopting, as the exit may be handled as
+            // soon as the executor takes over
+            *status = Status::Running;
+            job.set_finished(false);
+
+            let on_exit = self.on_exit(job.clone());
+            if !self
+                .executor
+                .adopt(job.id(), job.pid(), job.start_time(), on_exit)
+            {
+                *status = Status::UnknownState;
+                job.set_finished(true);
+            }
+        }
+    }
+
+    // Builds the callback the executor runs once a job's process has exited.
+    // The exit is recorded on a pool thread, then journaled and announced on
+    // the notify channel.
+    fn on_exit(&self, job: Box<Job>) -> ExitCallback {
+        let thread_pool = self.thread_pool.clone();
+        let notifier = self.notifier.clone();
+        let events = self.events.clone();
+        let journal = self.journal.clone();
+        Box::new(move |exit_status| {
+            thread_pool.execute(move || {
+                let res = match exit_status {
+                    Ok(exit_status) => job.exited(exit_status),
+                    Err(e) => Err(Error::JobErr(job.id(), e.as_str().to_string())),
+                };
+                let kind = match &res {
+                    Ok(_) => match *job.status().lock().unwrap() {
+                        Status::Stopped(stop_type) => EventKind::Stopped { stop_type },
+                        Status::Exited(exit_code) => EventKind::Exited { exit_code },
+                        Status::KilledBySeccomp => EventKind::KilledBySeccomp,
+                        Status::KilledByCpuLimit => EventKind::KilledByCpuLimit,
+                        _ => EventKind::Exited { exit_code: None },
+                    },
+                    Err(e) => EventKind::Failed {
+                        error: e.as_str().to_string(),
+                    },
+                };
+                if let (Ok(_), Some(journal)) = (&res, &journal) {
+                    let job_id = job.id();
+                    let entry = match kind {
+                        EventKind::Exited { exit_code } => Entry::Exited { job_id, exit_code },
+                        EventKind::KilledBySeccomp => Entry::KilledBySeccomp { job_id },
+                        EventKind::KilledByCpuLimit => Entry::KilledByCpuLimit { job_id },
+                        _ => Entry::Exited {
+                            job_id,
+                            exit_code: None,
+                        },
+                    };
+                    if let Err(e) = journal.record(entry) {
+                        log::error!("failed to record job event: {}", e);
+                    }
+                }
+                job.set_finished(true);
+                events.publish(Event::new(job.id(), job.owner_id(), kind));
+                notifier.send(res);
+            })
+        })
+    }
+
+    fn record(&self, entry: Entry) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.record(entry) {
+                log::error!("failed to record job event: {}", e);
+            }
+        }
     }
 
     fn create_log_dir(&self) -> Result<(), Error> {
@@ -151,7 +411,7 @@ impl Worker {
     }
 }
 
-impl Drop for Worker {
+impl<E: Executor> Drop for Worker<E> {
     fn drop(&mut self) {
         let jobs = self.jobs.clone();
         thread::spawn(move || {
@@ -160,6 +420,7 @@ impl Drop for Worker {
             let pids = jobs.iter().map(|job| job.pid());
             println!("hanging processes are preventing graceful shutdown of the worker, the following pids are responsible: {:?}", pids);
         });
+        self.executor.join();
         self.thread_pool.join();
     }
 }
@@ -0,0 +1,41 @@
+use super::JobResult;
+use crossbeam::channel::{Receiver, Sender, TrySendError};
+
+pub const DEFAULT_NOTIFY_CAPACITY: usize = 1024;
+
+// Bounded channel of job results that never blocks the sender. When nobody
+// drains the receiver and the buffer fills up, the oldest result is dropped
+// to make room for the newest.
+#[derive(Clone, Debug)]
+pub struct Notifier {
+    tx: Sender<JobResult>,
+    rx: Receiver<JobResult>,
+}
+
+impl Notifier {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, rx) = crossbeam::channel::bounded(capacity.max(1));
+        Notifier { tx, rx }
+    }
+
+    pub fn send(&self, res: JobResult) {
+        let mut res = res;
+        loop {
+            match self.tx.try_send(res) {
+                Ok(_) => return,
+                Err(TrySendError::Full(unsent)) => {
+                    if let Ok(dropped) = self.rx.try_recv() {
+                        log::warn!("notify channel is full, dropping result {:?}", dropped);
+                    }
+                    res = unsent;
+                }
+                // the notifier holds a receiver itself, so this cannot happen
+                Err(TrySendError::Disconnected(_)) => return,
+            }
+        }
+    }
+
+    pub fn receiver(&self) -> Receiver<JobResult> {
+        self.rx.clone()
+    }
+}
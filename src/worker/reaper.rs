@@ -0,0 +1,154 @@
+use super::Error;
+use crate::job::PidFd;
+use nix::sys::{
+    epoll::{Epoll, EpollCreateFlags, EpollEvent, EpollFlags},
+    eventfd::{eventfd, EfdFlags},
+};
+use std::{
+    collections::HashMap,
+    os::fd::{AsRawFd, OwnedFd},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+const WAKE_TOKEN: u64 = 0;
+const EVENT_BATCH: usize = 64;
+
+pub type ExitHandler = Box<dyn FnOnce() + Send>;
+
+// A single thread waiting on the pidfds of every watched process, so the
+// number of jobs that can be reaped at once is not bound to a thread count.
+#[derive(Clone)]
+pub struct Reaper {
+    inner: Arc<Inner>,
+    _shutdown: Arc<Shutdown>,
+}
+
+struct Inner {
+    epoll: Epoll,
+    wake: OwnedFd,
+    shutdown: AtomicBool,
+    next_token: AtomicU64,
+    watched: Mutex<HashMap<u64, (Arc<PidFd>, ExitHandler)>>,
+    idle: Condvar,
+}
+
+struct Shutdown(Arc<Inner>);
+
+impl Reaper {
+    pub fn new() -> Result<Self, Error> {
+        let epoll = Epoll::new(EpollCreateFlags::EPOLL_CLOEXEC)
+            .map_err(|e| Error::WorkerErr(format!("failed to create reaper epoll: {:?}", e)))?;
+        let wake = eventfd(0, EfdFlags::EFD_CLOEXEC | EfdFlags::EFD_NONBLOCK)
+            .map_err(|e| Error::WorkerErr(format!("failed to create reaper eventfd: {:?}", e)))?;
+        epoll
+            .add(&wake, EpollEvent::new(EpollFlags::EPOLLIN, WAKE_TOKEN))
+            .map_err(|e| Error::WorkerErr(format!("failed to register reaper eventfd: {:?}", e)))?;
+
+        let inner = Arc::new(Inner {
+            epoll,
+            wake,
+            shutdown: AtomicBool::new(false),
+            next_token: AtomicU64::new(WAKE_TOKEN + 1),
+            watched: Mutex::new(HashMap::new()),
+            idle: Condvar::new(),
+        });
+        let reaper_inner = inner.clone();
+        thread::Builder::new()
+            .name("iron-exec-reaper".into())
+            .spawn(move || reaper_inner.run())
+            .map_err(|e| Error::WorkerErr(format!("failed to spawn reaper thread: {:?}", e)))?;
+
+        Ok(Reaper {
+            inner: inner.clone(),
+            _shutdown: Arc::new(Shutdown(inner)),
+        })
+    }
+
+    // Calls on_exit from the reaper thread once the process behind pidfd has
+    // exited, so it must hand any blocking work off to another thread. The
+    // handler is given back if the pidfd could not be watched.
+    pub fn watch(&self, pidfd: Arc<PidFd>, on_exit: ExitHandler) -> Result<(), ExitHandler> {
+        let token = self.inner.next_token.fetch_add(1, Ordering::Relaxed);
+        let mut watched = self.inner.watched.lock().unwrap();
+        if let Err(e) = self
+            .inner
+            .epoll
+            .add(&pidfd, EpollEvent::new(EpollFlags::EPOLLIN, token))
+        {
+            log::warn!("failed to watch pidfd: {:?}", e);
+            return Err(on_exit);
+        }
+        watched.insert(token, (pidfd, on_exit));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.watched.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Blocks until every watched process has exited and been handled.
+    pub fn join(&self) {
+        let mut watched = self.inner.watched.lock().unwrap();
+        while !watched.is_empty() {
+            watched = self.inner.idle.wait(watched).unwrap();
+        }
+    }
+}
+
+impl Inner {
+    fn run(&self) {
+        let mut events = [EpollEvent::empty(); EVENT_BATCH];
+        loop {
+            let ready = match self.epoll.wait(&mut events, -1) {
+                Ok(ready) => ready,
+                Err(nix::errno::Errno::EINTR) => continue,
+                Err(e) => {
+                    log::error!("reaper failed to wait for process exits: {:?}", e);
+                    return;
+                }
+            };
+            for event in &events[..ready] {
+                if event.data() == WAKE_TOKEN {
+                    let mut buf = [0u8; 8];
+                    let _ = nix::unistd::read(self.wake.as_raw_fd(), &mut buf);
+                    continue;
+                }
+                self.reap(event.data());
+            }
+            if self.shutdown.load(Ordering::Acquire) {
+                return;
+            }
+        }
+    }
+
+    fn reap(&self, token: u64) {
+        // the handler runs under the lock so join cannot return before it has
+        // handed its work off
+        let mut watched = self.watched.lock().unwrap();
+        if let Some((pidfd, on_exit)) = watched.remove(&token) {
+            if let Err(e) = self.epoll.delete(&pidfd) {
+                log::warn!("failed to unwatch pidfd: {:?}", e);
+            }
+            drop(pidfd);
+            on_exit();
+        }
+        if watched.is_empty() {
+            self.idle.notify_all();
+        }
+    }
+}
+
+impl Drop for Shutdown {
+    fn drop(&mut self) {
+        self.0.shutdown.store(true, Ordering::Release);
+        let _ = nix::unistd::write(self.0.wake.as_raw_fd(), &1u64.to_ne_bytes());
+    }
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "async")]
+mod utils;
+
+use std::time::Duration;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, Status, StopType},
+    worker::{AsyncWorker, Config, Error, EventFilter, EventKind},
+};
+use tokio::io::AsyncReadExt;
+use utils::logs::{TestLog, LOG_DIR};
+use uuid::Uuid;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_async_start_wait_query() {
+    let worker = AsyncWorker::new(Config::new(LOG_DIR, 4)).unwrap();
+    let log_handler = TestLog::new();
+    let owner_id = Uuid::new_v4();
+
+    let test_cases = [
+        (
+            Command::new("echo".into(), vec!["hello".into(), "world".into()]),
+            Status::Exited(Some(0)),
+            "job exited without error",
+        ),
+        (
+            Command::new(
+                "sh".into(),
+                vec!["./tests/scripts/echo_and_error.sh".into()],
+            ),
+            Status::Exited(Some(127)),
+            "job exited with status 127",
+        ),
+    ];
+
+    for (command, expected_status, error_case) in test_cases {
+        let job_id = assert_ok!(worker.start(command.clone(), owner_id).await);
+        let job_info = assert_ok!(
+            tokio::time::timeout(TIMEOUT, worker.wait(job_id, owner_id, None)).await,
+            "wait did not complete when {}",
+            error_case,
+        );
+        let job_info = assert_ok!(job_info);
+        assert_eq!(
+            expected_status,
+            job_info.status(),
+            "job was not in expected state when {}",
+            error_case,
+        );
+
+        let queried = assert_ok!(worker.query(job_id, owner_id).await);
+        assert_eq!(job_info, queried);
+
+        // waiting on a job that has already finished returns straight away
+        let job_info =
+            assert_ok!(tokio::time::timeout(TIMEOUT, worker.wait(job_id, owner_id, None)).await);
+        assert_eq!(expected_status, assert_ok!(job_info).status());
+
+        log_handler.consume(format!("{}_{}.log", command.name(), job_id));
+    }
+
+    let e = assert_err!(worker.wait(Uuid::new_v4(), owner_id, None).await);
+    assert!(matches!(e, Error::NotFound(_)));
+
+    let command = Command::new("sh".into(), vec!["./tests/scripts/long_runtime.sh".into()]);
+    let job_id = assert_ok!(worker.start(command.clone(), owner_id).await);
+    let e = assert_err!(
+        worker
+            .wait(job_id, owner_id, Some(Duration::from_millis(100)))
+            .await
+    );
+    assert!(matches!(e, Error::Timeout(_)));
+    assert_eq!(format!("timed out waiting for job {}", job_id), e.as_str());
+    assert_ok!(worker.stop(job_id, owner_id, false).await);
+    assert_ok!(worker.wait(job_id, owner_id, Some(TIMEOUT)).await);
+    log_handler.consume(format!("{}_{}.log", command.name(), job_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_async_stop_and_subscribe() {
+    let worker = AsyncWorker::new(Config::new(LOG_DIR, 4)).unwrap();
+    let log_handler = TestLog::new();
+    let owner_id = Uuid::new_v4();
+    let mut events = worker.subscribe(EventFilter::owner(owner_id));
+
+    let command = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let job_id = assert_ok!(worker.start(command.clone(), owner_id).await);
+    let started = assert_ok!(tokio::time::timeout(TIMEOUT, events.recv()).await).unwrap();
+    assert!(matches!(started.kind(), EventKind::Started { .. }));
+
+    assert_ok!(worker.stop(job_id, owner_id, false).await);
+    let stopped = assert_ok!(tokio::time::timeout(TIMEOUT, events.recv()).await).unwrap();
+    assert_eq!(
+        EventKind::Stopped {
+            stop_type: StopType::Kill
+        },
+        stopped.kind(),
+    );
+
+    let job_info = assert_ok!(worker.wait(job_id, owner_id, Some(TIMEOUT)).await);
+    assert_eq!(Status::Stopped(StopType::Kill), job_info.status());
+    log_handler.consume(format!("{}_{}.log", command.name(), job_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_async_stream_follows_job() {
+    let worker = AsyncWorker::new(Config::new(LOG_DIR, 4)).unwrap();
+    let log_handler = TestLog::new();
+    let owner_id = Uuid::new_v4();
+
+    let command = Command::new(
+        "sh".into(),
+        vec!["-c".into(), "echo first; sleep 1; echo second".into()],
+    );
+    let job_id = assert_ok!(worker.start(command.clone(), owner_id).await);
+    let mut stream = assert_ok!(worker.stream(job_id, owner_id).await);
+
+    let mut output = String::new();
+    let read = tokio::time::timeout(TIMEOUT, stream.read_to_string(&mut output)).await;
+    assert_ok!(assert_ok!(read, "log stream did not end with the job"));
+    assert_eq!("first\nsecond\n", output);
+
+    log_handler.consume(format!("{}_{}.log", command.name(), job_id));
+}
@@ -0,0 +1,219 @@
+#![cfg(feature = "cli")]
+mod utils;
+
+use claim::assert_ok;
+use iron_exec::job::{Status, StopType};
+use std::{
+    fs,
+    process::{Command, Output},
+};
+use utils::{logs::TestLog, server::TestServer};
+use uuid::Uuid;
+
+// Runs the iron-exec binary against its own local state directory, removed on
+// drop, or against a TestServer.
+struct TestCli {
+    args: Vec<String>,
+    state_dir: Option<String>,
+}
+
+impl TestCli {
+    fn local() -> TestCli {
+        let state_dir = format!("./tests/.state/cli_{}", Uuid::new_v4());
+        TestCli {
+            args: vec!["--state-dir".into(), state_dir.clone()],
+            state_dir: Some(state_dir),
+        }
+    }
+
+    fn remote(server: &TestServer, common_name: &str) -> TestCli {
+        let client = server.pki.client(common_name);
+        let cert = server.pki.path(&format!("{}.pem", common_name));
+        let key = server.pki.path(&format!("{}.key", common_name));
+        fs::write(&cert, &client.cert_pem).unwrap();
+        fs::write(&key, &client.key_pem).unwrap();
+        let port = server.addr().rsplit(':').next().unwrap();
+        TestCli {
+            args: vec![
+                "--server".into(),
+                format!("https://localhost:{}", port),
+                "--ca".into(),
+                server.pki.path("ca.pem"),
+                "--cert".into(),
+                cert,
+                "--key".into(),
+                key,
+            ],
+            state_dir: None,
+        }
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        assert_ok!(Command::new(env!("CARGO_BIN_EXE_iron-exec"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .args(&self.args)
+            .args(args)
+            .output())
+    }
+
+    fn start(&self, args: &[&str]) -> (Uuid, Output) {
+        let output = self.run(&[&["start", "--"], args].concat());
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let job_id = stdout.lines().next().unwrap_or_default();
+        let job_id = Uuid::parse_str(job_id)
+            .unwrap_or_else(|_| panic!("start did not print a job id: {:?}", output));
+        (job_id, output)
+    }
+}
+
+impl Drop for TestCli {
+    fn drop(&mut self) {
+        if let Some(state_dir) = &self.state_dir {
+            let _ = fs::remove_dir_all(state_dir);
+        }
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+pub fn test_cli_local_exit_codes() {
+    let cli = TestCli::local();
+
+    let test_cases = [
+        (vec!["echo", "hello"], 0, "job exited without error"),
+        (vec!["sh", "-c", "exit 3"], 3, "job exited with status 3"),
+        (
+            vec!["sh", "-c", "kill -9 $$"],
+            1,
+            "job was killed by a signal it was not stopped with",
+        ),
+        (vec!["not-a-command"], 125, "job could not be started"),
+    ];
+
+    for (command, expected_code, error_case) in test_cases {
+        let output = cli.run(&[&["start", "--"], command.as_slice()].concat());
+        assert_eq!(
+            Some(expected_code),
+            output.status.code(),
+            "start did not exit with expected code when {}: {:?}",
+            error_case,
+            output
+        );
+    }
+}
+
+#[test]
+pub fn test_cli_local_job() {
+    let cli = TestCli::local();
+
+    let (job_id, output) = cli.start(&["echo", "hello", "world"]);
+    assert_eq!(Some(0), output.status.code());
+    let job_id = job_id.to_string();
+
+    let output = cli.run(&["logs", &job_id]);
+    assert_eq!(Some(0), output.status.code());
+    assert_eq!("hello world\n", stdout(&output));
+
+    let output = cli.run(&["-o", "json", "status", &job_id]);
+    assert_eq!(Some(0), output.status.code());
+    let value: serde_json::Value = assert_ok!(serde_json::from_str(&stdout(&output)));
+    assert_eq!(job_id, value["id"]);
+    assert_eq!(serde_json::json!(Status::Exited(Some(0))), value["status"]);
+    assert_eq!(0, value["exit_code"]);
+
+    let output = cli.run(&["list"]);
+    assert_eq!(Some(0), output.status.code());
+    let listed = stdout(&output);
+    assert!(listed.starts_with("JOB"), "list had no header: {}", listed);
+    assert!(
+        listed.contains(&format!("{}  Exited", job_id)),
+        "list did not show the job: {}",
+        listed
+    );
+
+    let unknown_id = Uuid::new_v4().to_string();
+    let test_cases = [
+        (vec!["wait", &job_id], 0, "waiting on a finished job"),
+        (vec!["stop", &job_id], 125, "stopping a finished job"),
+        (vec!["status", &unknown_id], 125, "querying an unknown job"),
+    ];
+
+    for (args, expected_code, error_case) in test_cases {
+        let output = cli.run(&args);
+        assert_eq!(
+            Some(expected_code),
+            output.status.code(),
+            "command did not exit with expected code when {}: {:?}",
+            error_case,
+            output
+        );
+    }
+}
+
+#[test]
+pub fn test_cli_remote_job() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let cli = TestCli::remote(&server, "alice");
+
+    // a remote start returns as soon as the job is running
+    let (looping_id, output) = cli.start(&["sh", "./tests/scripts/infinite_loop.sh"]);
+    assert_eq!(Some(0), output.status.code());
+    let job_id = looping_id.to_string();
+
+    let output = cli.run(&["wait", "--timeout", "1", &job_id]);
+    assert_eq!(
+        Some(124),
+        output.status.code(),
+        "wait did not time out on a running job: {:?}",
+        output
+    );
+
+    let other = TestCli::remote(&server, "bob");
+    let output = other.run(&["status", &job_id]);
+    assert_eq!(
+        Some(125),
+        output.status.code(),
+        "another owner could query the job: {:?}",
+        output
+    );
+
+    let output = cli.run(&["stop", "--force", &job_id]);
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    let output = cli.run(&["-o", "json", "wait", &job_id]);
+    assert_eq!(Some(137), output.status.code(), "{:?}", output);
+    let value: serde_json::Value = assert_ok!(serde_json::from_str(&stdout(&output)));
+    assert_eq!(
+        serde_json::json!(Status::Stopped(StopType::Kill)),
+        value["status"]
+    );
+
+    let (echo_id, output) = cli.start(&["echo", "hello", "world"]);
+    assert_eq!(Some(0), output.status.code());
+    let output = cli.run(&["logs", "--follow", &echo_id.to_string()]);
+    assert_eq!("hello world\n", stdout(&output));
+    let output = cli.run(&["wait", &echo_id.to_string()]);
+    assert_eq!(Some(0), output.status.code());
+
+    log_handler.consume(format!("sh_{}.log", looping_id));
+    log_handler.consume(format!("echo_{}.log", echo_id));
+}
+
+#[test]
+pub fn test_cli_remote_default_capabilities() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let cli = TestCli::remote(&server, "alice");
+
+    // a server without a bounding set of its own starts jobs with the
+    // container runtimes' default one
+    let (job_id, output) = cli.start(&["grep", "^CapBnd:", "/proc/self/status"]);
+    assert_eq!(Some(0), output.status.code());
+    let output = cli.run(&["logs", "--follow", &job_id.to_string()]);
+    assert_eq!("CapBnd:\t00000000800405fb\n", stdout(&output));
+
+    log_handler.consume(format!("grep_{}.log", job_id));
+}
@@ -0,0 +1,111 @@
+mod utils;
+
+use claim::assert_ok;
+use iron_exec::job::{Command, JobInfo, Status, StopType, SCHEMA_VERSION};
+use serde_json::json;
+use utils::app::TestApp;
+use uuid::Uuid;
+
+#[test]
+pub fn test_command_serde() {
+    let command = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+
+    let value = assert_ok!(serde_json::to_value(&command));
+    assert_eq!(
+        json!({ "name": "echo", "args": ["hello", "world"] }),
+        value,
+        "command did not serialize to expected json",
+    );
+
+    let round_trip: Command = assert_ok!(serde_json::from_value(value));
+    assert_eq!(
+        command, round_trip,
+        "command did not survive json round trip"
+    );
+}
+
+#[test]
+pub fn test_status_serde() {
+    let test_cases = [
+        (Status::UnknownState, json!({ "state": "unknown_state" })),
+        (Status::Running, json!({ "state": "running" })),
+        (
+            Status::Exited(Some(127)),
+            json!({ "state": "exited", "detail": 127 }),
+        ),
+        (
+            Status::Exited(None),
+            json!({ "state": "exited", "detail": null }),
+        ),
+        (
+            Status::Stopped(StopType::Term),
+            json!({ "state": "stopped", "detail": "term" }),
+        ),
+        (
+            Status::Stopped(StopType::Kill),
+            json!({ "state": "stopped", "detail": "kill" }),
+        ),
+        (
+            Status::KilledBySeccomp,
+            json!({ "state": "killed_by_seccomp" }),
+        ),
+        (
+            Status::KilledByCpuLimit,
+            json!({ "state": "killed_by_cpu_limit" }),
+        ),
+    ];
+
+    for (status, expected) in test_cases {
+        let value = assert_ok!(serde_json::to_value(&status));
+        assert_eq!(
+            expected, value,
+            "{} did not serialize to expected json",
+            status
+        );
+
+        let round_trip: Status = assert_ok!(serde_json::from_value(value));
+        assert_eq!(
+            status, round_trip,
+            "{} did not survive json round trip",
+            status
+        );
+    }
+}
+
+#[test]
+pub fn test_job_info_serde() {
+    let app = TestApp::new();
+
+    let owner_id = Uuid::new_v4();
+    let command = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let job_id = app.worker.start(command.clone(), owner_id).unwrap();
+    assert_ok!(app.wait());
+
+    let job_info = assert_ok!(app.worker.query(job_id, owner_id));
+    let value = assert_ok!(serde_json::to_value(&job_info));
+    assert_eq!(json!(SCHEMA_VERSION), value["version"]);
+    assert_eq!(json!(job_id), value["id"]);
+    assert_eq!(json!({ "state": "exited", "detail": 0 }), value["status"]);
+    assert_eq!(json!(0), value["exit_code"]);
+    assert_eq!(
+        json!({ "name": "echo", "args": ["hello", "world"] }),
+        value["command"]
+    );
+
+    let round_trip: JobInfo = assert_ok!(serde_json::from_value(value.clone()));
+    assert_eq!(
+        job_info, round_trip,
+        "job info did not survive json round trip"
+    );
+
+    let mut unversioned = value;
+    unversioned.as_object_mut().unwrap().remove("version");
+    let job_info: JobInfo = assert_ok!(serde_json::from_value(unversioned));
+    assert_eq!(
+        SCHEMA_VERSION,
+        job_info.version(),
+        "unversioned job info was not read as the current schema version",
+    );
+
+    app.log_handler.consume(format!("echo_{}.log", job_id));
+}
@@ -0,0 +1,3 @@
+trap 'echo reloaded; exit 3' USR1
+echo ready
+while true; do sleep 0.1; done
@@ -0,0 +1,152 @@
+#![cfg(feature = "server")]
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Status, StopType},
+    server::proto::{
+        seccomp, Command, ListRequest, QueryRequest, Seccomp, StartRequest, StopRequest,
+        StreamRequest,
+    },
+};
+use tonic::Code;
+use utils::{logs::TestLog, server::TestServer};
+use uuid::Uuid;
+
+fn command(name: &str, args: &[&str]) -> Option<Command> {
+    Some(Command {
+        name: name.into(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        ..Default::default()
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_grpc_job_lifecycle() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let mut client = server.connect("alice").await;
+
+    let start = StartRequest {
+        command: command("echo", &["hello", "world"]),
+    };
+    let job_id = assert_ok!(client.start(start).await).into_inner().job_id;
+
+    // the stream only ends once the job has finished, so the query after it
+    // sees the exit
+    let stream = StreamRequest {
+        job_id: job_id.clone(),
+        follow: true,
+    };
+    let mut stream = assert_ok!(client.stream(stream).await).into_inner();
+    let mut output = vec![];
+    while let Some(chunk) = assert_ok!(stream.message().await) {
+        output.extend(chunk.data);
+    }
+    assert_eq!(b"hello world\n".to_vec(), output);
+
+    let query = QueryRequest {
+        job_id: job_id.clone(),
+    };
+    let job_info = assert_ok!(client.query(query).await).into_inner();
+    assert_eq!(job_id, job_info.job_id);
+    assert_eq!(Status::Exited(Some(0)).to_string(), job_info.status);
+    assert_eq!(Some(0), job_info.exit_code);
+    assert_eq!(command("echo", &["hello", "world"]), job_info.command);
+
+    let start = StartRequest {
+        command: command("sh", &["./tests/scripts/infinite_loop.sh"]),
+    };
+    let looping_id = assert_ok!(client.start(start).await).into_inner().job_id;
+    let stop = StopRequest {
+        job_id: looping_id.clone(),
+        gracefully: true,
+    };
+    assert_ok!(client.stop(stop).await);
+    let query = QueryRequest {
+        job_id: looping_id.clone(),
+    };
+    let job_info = assert_ok!(client.query(query).await).into_inner();
+    assert_eq!(Status::Stopped(StopType::Term).to_string(), job_info.status);
+
+    let jobs = assert_ok!(client.list(ListRequest {}).await)
+        .into_inner()
+        .jobs;
+    let ids: Vec<String> = jobs.into_iter().map(|job_info| job_info.job_id).collect();
+    assert_eq!(vec![job_id.clone(), looping_id.clone()], ids);
+
+    // drain the stopped job's stream so its exit is handled before shutdown
+    let stream = StreamRequest {
+        job_id: looping_id.clone(),
+        follow: true,
+    };
+    let mut stream = assert_ok!(client.stream(stream).await).into_inner();
+    while assert_ok!(stream.message().await).is_some() {}
+
+    log_handler.consume(format!("echo_{}.log", job_id));
+    log_handler.consume(format!("sh_{}.log", looping_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_grpc_errors() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let mut client = server.connect("alice").await;
+
+    let start = StartRequest {
+        command: command("echo", &["hello"]),
+    };
+    let job_id = assert_ok!(client.start(start).await).into_inner().job_id;
+
+    let test_cases = [
+        (
+            Uuid::new_v4().to_string(),
+            Code::NotFound,
+            "querying a non-existent job",
+        ),
+        (
+            "not-a-uuid".to_string(),
+            Code::InvalidArgument,
+            "querying an invalid job id",
+        ),
+    ];
+
+    for (job_id, expected_code, error_case) in test_cases {
+        let status = assert_err!(
+            client.query(QueryRequest { job_id }).await,
+            "query did not fail when {}",
+            error_case
+        );
+        assert_eq!(
+            expected_code,
+            status.code(),
+            "unexpected status code when {}",
+            error_case
+        );
+    }
+
+    let status = assert_err!(client.start(StartRequest { command: None }).await);
+    assert_eq!(Code::InvalidArgument, status.code());
+    assert_eq!("missing command", status.message());
+
+    // a profile from a newer client is refused, not swapped for another
+    let start = StartRequest {
+        command: command("echo", &["hello"]).map(|command| Command {
+            seccomp: Some(Seccomp {
+                filter: Some(seccomp::Filter::Profile(99)),
+            }),
+            ..command
+        }),
+    };
+    let status = assert_err!(client.start(start).await);
+    assert_eq!(Code::InvalidArgument, status.code());
+    assert_eq!("unknown seccomp profile 99", status.message());
+
+    let stream = StreamRequest {
+        job_id: job_id.clone(),
+        follow: true,
+    };
+    let mut stream = assert_ok!(client.stream(stream).await).into_inner();
+    while assert_ok!(stream.message().await).is_some() {}
+    log_handler.consume(format!("echo_{}.log", job_id));
+}
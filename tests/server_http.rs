@@ -0,0 +1,246 @@
+#![cfg(feature = "server")]
+mod utils;
+
+use claim::assert_ok;
+use iron_exec::job::{Status, StopType};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use utils::{logs::TestLog, server::TestServer};
+use uuid::Uuid;
+
+// Splits a server-sent event stream into (event, data) pairs.
+fn events(body: &str) -> Vec<(String, String)> {
+    body.split("\n\n")
+        .filter_map(|event| {
+            let mut name = String::new();
+            let mut data = vec![];
+            for line in event.lines() {
+                if let Some(value) = line.strip_prefix("event:") {
+                    name = value.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.trim_start());
+                }
+            }
+            (!name.is_empty()).then(|| (name, data.join("\n")))
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_http_job_lifecycle() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let client = server.http_client("alice");
+
+    let command = json!({ "name": "echo", "args": ["hello", "world"] });
+    let response = assert_ok!(client.post(server.url("/jobs")).json(&command).send().await);
+    assert_eq!(StatusCode::CREATED, response.status());
+    let job_info: Value = assert_ok!(response.json().await);
+    let job_id = job_info["id"].as_str().unwrap().to_string();
+    assert_eq!(command, job_info["command"]);
+
+    // a followed log only ends once the job has finished, so the query after
+    // it sees the exit
+    let response = assert_ok!(
+        client
+            .get(server.url(&format!("/jobs/{}/logs", job_id)))
+            .send()
+            .await
+    );
+    assert_eq!(StatusCode::OK, response.status());
+    assert_eq!("hello world\n", assert_ok!(response.text().await));
+
+    let response = assert_ok!(
+        client
+            .get(server.url(&format!("/jobs/{}", job_id)))
+            .send()
+            .await
+    );
+    assert_eq!(StatusCode::OK, response.status());
+    let job_info: Value = assert_ok!(response.json().await);
+    assert_eq!(json!(Status::Exited(Some(0))), job_info["status"]);
+    assert_eq!(0, job_info["exit_code"]);
+    assert_eq!("host", job_info["isolation"]["network"]);
+
+    let response = assert_ok!(client.get(server.url("/jobs")).send().await);
+    let jobs: Vec<Value> = assert_ok!(response.json().await);
+    assert_eq!(
+        vec![job_id.clone()],
+        jobs.iter()
+            .map(|job| job["id"].as_str().unwrap())
+            .collect::<Vec<_>>()
+    );
+
+    let command = json!({ "name": "sh", "args": ["./tests/scripts/infinite_loop.sh"] });
+    let response = assert_ok!(client.post(server.url("/jobs")).json(&command).send().await);
+    let job_info: Value = assert_ok!(response.json().await);
+    let looping_id = job_info["id"].as_str().unwrap().to_string();
+
+    let response = assert_ok!(
+        client
+            .delete(server.url(&format!("/jobs/{}?force=true", looping_id)))
+            .send()
+            .await
+    );
+    assert_eq!(StatusCode::NO_CONTENT, response.status());
+
+    let response = assert_ok!(
+        client
+            .get(server.url(&format!("/jobs/{}/logs", looping_id)))
+            .header("accept", "text/event-stream")
+            .send()
+            .await
+    );
+    assert_eq!(
+        Some("text/event-stream"),
+        response
+            .headers()
+            .get("content-type")
+            .and_then(|value| value.to_str().ok())
+    );
+    let events = events(&assert_ok!(response.text().await));
+    let (name, data) = events.last().expect("log stream had no events");
+    assert_eq!("exit", name);
+    let job_info: Value = assert_ok!(serde_json::from_str(data));
+    assert_eq!(json!(Status::Stopped(StopType::Kill)), job_info["status"]);
+    for (name, data) in &events[..events.len() - 1] {
+        assert_eq!("log", name);
+        let output: String = assert_ok!(serde_json::from_str(data));
+        assert!(output.contains("looping"), "unexpected output {:?}", output);
+    }
+
+    log_handler.consume(format!("echo_{}.log", job_id));
+    log_handler.consume(format!("sh_{}.log", looping_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_http_log_events_end_mid_character() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let client = server.http_client("alice");
+
+    // the output ends with the first byte of a two byte character
+    let command = json!({ "name": "printf", "args": ["caf\\303"] });
+    let response = assert_ok!(client.post(server.url("/jobs")).json(&command).send().await);
+    let job_info: Value = assert_ok!(response.json().await);
+    let job_id = job_info["id"].as_str().unwrap().to_string();
+
+    let response = assert_ok!(
+        client
+            .get(server.url(&format!("/jobs/{}/logs", job_id)))
+            .header("accept", "text/event-stream")
+            .send()
+            .await
+    );
+    let events = events(&assert_ok!(response.text().await));
+    let (name, _) = events.last().expect("log stream had no events");
+    assert_eq!("exit", name);
+    let output: String = events[..events.len() - 1]
+        .iter()
+        .map(|(name, data)| {
+            assert_eq!("log", name);
+            assert_ok!(serde_json::from_str::<String>(data))
+        })
+        .collect();
+    assert_eq!("caf\u{FFFD}", output);
+
+    log_handler.consume(format!("printf_{}.log", job_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_http_errors() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let client = server.http_client("alice");
+
+    let response = assert_ok!(
+        client
+            .post(server.url("/jobs"))
+            .json(&json!({ "name": "echo", "args": ["hello"] }))
+            .send()
+            .await
+    );
+    let job_info: Value = assert_ok!(response.json().await);
+    let job_id = job_info["id"].as_str().unwrap().to_string();
+    assert_ok!(
+        client
+            .get(server.url(&format!("/jobs/{}/logs", job_id)))
+            .send()
+            .await
+    );
+
+    let other_client = server.http_client("bob");
+    let unknown_id = Uuid::new_v4().to_string();
+    let test_cases = [
+        (
+            client.get(server.url("/jobs/not-a-uuid")),
+            StatusCode::BAD_REQUEST,
+            "querying an invalid job id",
+        ),
+        (
+            client.get(server.url(&format!("/jobs/{}", unknown_id))),
+            StatusCode::NOT_FOUND,
+            "querying an unknown job",
+        ),
+        (
+            other_client.get(server.url(&format!("/jobs/{}", job_id))),
+            StatusCode::NOT_FOUND,
+            "querying another owner's job",
+        ),
+        (
+            client.delete(server.url(&format!("/jobs/{}", job_id))),
+            StatusCode::CONFLICT,
+            "stopping a finished job",
+        ),
+        (
+            client
+                .post(server.url("/jobs"))
+                .json(&json!({ "name": "echo" })),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "starting a job without args",
+        ),
+        (
+            client
+                .post(server.url("/jobs"))
+                .json(&json!({ "name": "whatever-madeup-command", "args": [] })),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "starting a job that cannot be spawned",
+        ),
+    ];
+
+    for (request, expected_status, error_case) in test_cases {
+        let response = assert_ok!(request.send().await);
+        assert_eq!(
+            expected_status,
+            response.status(),
+            "response did not have expected status when {}",
+            error_case
+        );
+        let body: Value = assert_ok!(response.json().await);
+        assert!(
+            body["error"].is_string(),
+            "response had no error message when {}",
+            error_case
+        );
+    }
+
+    log_handler.consume(format!("echo_{}.log", job_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_http_openapi() {
+    let server = TestServer::spawn();
+    let client = server.http_client("alice");
+
+    let response = assert_ok!(client.get(server.url("/openapi.json")).send().await);
+    assert_eq!(StatusCode::OK, response.status());
+    let document: Value = assert_ok!(response.json().await);
+    assert_eq!("3.0.3", document["openapi"]);
+    for path in ["/jobs", "/jobs/{id}", "/jobs/{id}/logs"] {
+        assert!(
+            document["paths"][path].is_object(),
+            "openapi document did not describe {}",
+            path
+        );
+    }
+}
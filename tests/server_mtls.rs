@@ -0,0 +1,156 @@
+#![cfg(feature = "server")]
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    server::{
+        owner_id_from_cert,
+        proto::{Command, ListRequest, QueryRequest, StartRequest, StreamRequest},
+    },
+    worker::{owner_id_from_uid, OWNER_NAMESPACE},
+};
+use rcgen::SanType;
+use tonic::Code;
+use utils::{
+    logs::TestLog,
+    server::TestServer,
+    tls::{TestCert, TestPki},
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_owner_id_from_cert() {
+    let pki = TestPki::new();
+    let owner_id = Uuid::new_v4();
+    let owner_uri = SanType::URI(format!("urn:uuid:{}", owner_id).try_into().unwrap());
+    let root_id = owner_id_from_uid(0);
+    let root_uri = SanType::URI(format!("urn:uuid:{}", root_id).try_into().unwrap());
+    let reserved = format!(
+        "client certificate owner id {} is reserved for local users",
+        root_id
+    );
+
+    let test_cases = [
+        (
+            pki.client(&owner_id.to_string()),
+            Ok(owner_id),
+            "common name is a uuid",
+        ),
+        (
+            pki.client("alice"),
+            Ok(Uuid::new_v5(&OWNER_NAMESPACE, b"alice")),
+            "common name is a plain name",
+        ),
+        (
+            pki.client("uid:0"),
+            Err("client certificate common name uid:0 is reserved for local users".to_string()),
+            "common name names a local user",
+        ),
+        (
+            pki.client(&root_id.to_string()),
+            Err(reserved.clone()),
+            "common name is a local user's owner id",
+        ),
+        (
+            pki.sign(Some("alice"), vec![root_uri]),
+            Err(reserved),
+            "owner uri is a local user's owner id",
+        ),
+        (
+            pki.sign(Some("alice"), vec![owner_uri.clone()]),
+            Ok(owner_id),
+            "owner uri is given alongside a common name",
+        ),
+        (
+            pki.sign(None, vec![owner_uri]),
+            Ok(owner_id),
+            "owner uri is given without a common name",
+        ),
+        (
+            pki.sign(
+                Some("alice"),
+                vec![SanType::URI("urn:uuid:nope".try_into().unwrap())],
+            ),
+            Err("invalid owner uri urn:uuid:nope".to_string()),
+            "owner uri is not a uuid",
+        ),
+        (
+            pki.sign(None, vec![]),
+            Err("client certificate has neither an owner uri nor a common name".to_string()),
+            "certificate names no owner",
+        ),
+    ];
+
+    for (cert, expected, error_case) in test_cases {
+        assert_eq!(
+            expected,
+            owner_id_from_cert(&cert.der),
+            "unexpected owner id when {}",
+            error_case
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_untrusted_clients_are_rejected() {
+    let server = TestServer::spawn();
+    let other_pki = TestPki::new();
+
+    let test_cases: [(Option<TestCert>, &str); 2] = [
+        (None, "client presents no certificate"),
+        (
+            Some(other_pki.client("alice")),
+            "client certificate is signed by another ca",
+        ),
+    ];
+
+    for (client, error_case) in test_cases {
+        // the handshake may only fail once the first request is sent
+        let res = match server.connect_with(client.as_ref()).await {
+            Ok(mut client) => client
+                .list(ListRequest {})
+                .await
+                .map(|_| ())
+                .map_err(|_| ()),
+            Err(_) => Err(()),
+        };
+        assert_err!(res, "request was served when {}", error_case);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_owner_is_taken_from_certificate() {
+    let server = TestServer::spawn();
+    let log_handler = TestLog::new();
+    let mut alice = server.connect("alice").await;
+    let mut bob = server.connect("bob").await;
+
+    let start = StartRequest {
+        command: Some(Command {
+            name: "echo".into(),
+            args: vec!["hello".into()],
+            ..Default::default()
+        }),
+    };
+    let job_id = assert_ok!(alice.start(start).await).into_inner().job_id;
+
+    let query = QueryRequest {
+        job_id: job_id.clone(),
+    };
+    let status = assert_err!(bob.query(query.clone()).await);
+    assert_eq!(Code::NotFound, status.code());
+    let jobs = assert_ok!(bob.list(ListRequest {}).await).into_inner().jobs;
+    assert!(jobs.is_empty(), "another owner's jobs were listed");
+
+    // a new certificate for the same name speaks for the same owner
+    let mut alice_again = server.connect("alice").await;
+    assert_ok!(alice_again.query(query).await);
+
+    let stream = StreamRequest {
+        job_id: job_id.clone(),
+        follow: true,
+    };
+    let mut stream = assert_ok!(alice.stream(stream).await).into_inner();
+    while assert_ok!(stream.message().await).is_some() {}
+    log_handler.consume(format!("echo_{}.log", job_id));
+}
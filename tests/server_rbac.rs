@@ -0,0 +1,87 @@
+#![cfg(feature = "server")]
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Status, StopType},
+    server::proto::{
+        Command, ListRequest, PurgeRequest, QueryRequest, StartRequest, StopRequest, StreamRequest,
+    },
+};
+use std::fs;
+use tonic::Code;
+use utils::{logs::TestLog, server::TestServer};
+use uuid::Uuid;
+
+const POLICY_DIR: &str = "./tests/.state/policy";
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_grpc_roles() {
+    fs::create_dir_all(POLICY_DIR).unwrap();
+    let policy = format!("{}/{}.toml", POLICY_DIR, Uuid::new_v4());
+    fs::write(
+        &policy,
+        "[roles.admin]\nmembers = [\"root\"]\n\n[roles.read_only]\nmembers = [\"auditor\"]\n",
+    )
+    .unwrap();
+    let server = TestServer::spawn_with_env(&[("IRON_EXEC_POLICY", &policy)]);
+    fs::remove_file(&policy).unwrap();
+    let log_handler = TestLog::new();
+
+    let mut alice = server.connect("alice").await;
+    let mut bob = server.connect("bob").await;
+    let mut auditor = server.connect("auditor").await;
+    let mut root = server.connect("root").await;
+
+    let start = StartRequest {
+        command: Some(Command {
+            name: "sh".into(),
+            args: vec!["./tests/scripts/infinite_loop.sh".into()],
+            ..Default::default()
+        }),
+    };
+    let job_id = assert_ok!(alice.start(start.clone()).await)
+        .into_inner()
+        .job_id;
+    let query = QueryRequest {
+        job_id: job_id.clone(),
+    };
+    let stop = StopRequest {
+        job_id: job_id.clone(),
+        gracefully: false,
+    };
+
+    let status = assert_err!(auditor.start(start).await);
+    assert_eq!(Code::PermissionDenied, status.code());
+    assert_eq!("read_only role may not start jobs", status.message());
+    let status = assert_err!(auditor.stop(stop.clone()).await);
+    assert_eq!(Code::PermissionDenied, status.code());
+    assert_ok!(auditor.query(query.clone()).await);
+    let jobs = assert_ok!(auditor.list(ListRequest {}).await)
+        .into_inner()
+        .jobs;
+    assert_eq!(1, jobs.len());
+
+    let status = assert_err!(bob.stop(stop.clone()).await);
+    assert_eq!(Code::NotFound, status.code());
+
+    assert_ok!(root.stop(stop).await);
+    let stream = StreamRequest {
+        job_id: job_id.clone(),
+        follow: true,
+    };
+    let mut stream = assert_ok!(alice.stream(stream).await).into_inner();
+    while assert_ok!(stream.message().await).is_some() {}
+    let job_info = assert_ok!(alice.query(query.clone()).await).into_inner();
+    assert_eq!(Status::Stopped(StopType::Kill).to_string(), job_info.status);
+
+    let purge = PurgeRequest {
+        job_id: job_id.clone(),
+    };
+    let status = assert_err!(auditor.purge(purge.clone()).await);
+    assert_eq!(Code::PermissionDenied, status.code());
+    log_handler.consume(format!("sh_{}.log", job_id));
+    assert_ok!(alice.purge(purge).await);
+    let status = assert_err!(alice.query(query).await);
+    assert_eq!(Code::NotFound, status.code());
+}
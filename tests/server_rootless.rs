@@ -0,0 +1,102 @@
+#![cfg(feature = "cli")]
+
+use claim::assert_ok;
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+use serde_json::Value;
+use std::{
+    io::{BufRead, BufReader},
+    os::unix::fs::PermissionsExt,
+    process::{Command, Output, Stdio},
+};
+use uuid::Uuid;
+
+const NOBODY: &str = "65534";
+
+fn cli(socket: &str, args: &[&str]) -> Output {
+    assert_ok!(Command::new(env!("CARGO_BIN_EXE_iron-exec"))
+        .args(["--socket", socket])
+        .args(args)
+        .output())
+}
+
+#[test]
+pub fn test_rootless_server_isolates_jobs() {
+    // nobody can neither reach the build's binaries nor the test's
+    // directories, so the server gets a copy of its own outside of them
+    let dir = format!("/tmp/iron_exec_rootless_{}", Uuid::new_v4());
+    assert_ok!(std::fs::create_dir(&dir));
+    assert_ok!(std::fs::set_permissions(
+        &dir,
+        std::fs::Permissions::from_mode(0o777)
+    ));
+    let server = format!("{}/iron-exec-server", dir);
+    assert_ok!(std::fs::copy(
+        env!("CARGO_BIN_EXE_iron-exec-server"),
+        &server
+    ));
+    let socket = format!("{}/sock", dir);
+
+    let mut child = assert_ok!(Command::new("setpriv")
+        .args([
+            "--reuid",
+            NOBODY,
+            "--regid",
+            NOBODY,
+            "--clear-groups",
+            &server
+        ])
+        .current_dir(&dir)
+        .env("IRON_EXEC_SOCKET", &socket)
+        .env("IRON_EXEC_LOG_DIR", format!("{}/logs", dir))
+        .env("IRON_EXEC_USER_NAMESPACE", "true")
+        .env("IRON_EXEC_PID_NAMESPACE", "true")
+        .env("IRON_EXEC_MOUNT_NAMESPACE", "true")
+        .env("IRON_EXEC_NETWORK", "loopback")
+        .env("IRON_EXEC_UTS_NAMESPACE", "true")
+        .stdout(Stdio::piped())
+        .spawn());
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    assert_ok!(stdout.read_line(&mut line));
+    assert_eq!(format!("listening on unix:{}\n", socket), line);
+
+    let output = cli(
+        &socket,
+        &[
+            "start",
+            "--wait",
+            "--",
+            "sh",
+            "-c",
+            "echo $$; id -u; ls -A /tmp; cut -d: -f1 /proc/net/dev | tail -n +3 | tr -d ' '; \
+             uname -n",
+        ],
+    );
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    let job_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let output = cli(&socket, &["logs", &job_id]);
+    assert_eq!(
+        format!("2\n0\nlo\n{}\n", job_id),
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let output = cli(&socket, &["-o", "json", "status", &job_id]);
+    let job_info: Value = assert_ok!(serde_json::from_slice(&output.stdout));
+    let isolation = &job_info["isolation"];
+    assert_eq!(true, isolation["pid_namespace"]);
+    assert_eq!(true, isolation["mount_namespace"]);
+    assert_eq!("loopback", isolation["network"]);
+    assert_eq!(job_id, isolation["hostname"]);
+    assert_eq!(
+        65534, isolation["user_namespace"]["uid_map"][0]["outside"],
+        "job's root was not mapped to the server's uid"
+    );
+
+    let _ = kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM);
+    let _ = child.wait();
+    assert_ok!(std::fs::remove_dir_all(&dir));
+}
@@ -0,0 +1,138 @@
+#![cfg(feature = "cli")]
+mod utils;
+
+use claim::assert_ok;
+use iron_exec::server::proto::QueryRequest;
+use serde_json::Value;
+use std::{
+    fs,
+    io::{Read, Write},
+    os::unix::{
+        fs::{MetadataExt, PermissionsExt},
+        net::UnixStream,
+    },
+    process::{Command, Output},
+};
+use tonic::Code;
+use utils::{logs::TestLog, server::TestServer};
+use uuid::Uuid;
+
+fn cli(socket: &str, args: &[&str]) -> Output {
+    assert_ok!(Command::new(env!("CARGO_BIN_EXE_iron-exec"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args(["--socket", socket])
+        .args(args)
+        .output())
+}
+
+// Sends a bare HTTP/1.1 request over the socket and returns the response body.
+fn http_get(socket: &str, path: &str) -> String {
+    let mut stream = assert_ok!(UnixStream::connect(socket));
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    );
+    assert_ok!(stream.write_all(request.as_bytes()));
+    let mut response = String::new();
+    assert_ok!(stream.read_to_string(&mut response));
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .unwrap_or_else(|| panic!("malformed response {:?}", response));
+    assert!(head.starts_with("HTTP/1.1 200"), "request failed: {}", head);
+    body.to_string()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_unix_socket_owner_from_peer_credentials() {
+    let socket = format!("./tests/.state/sock_{}", Uuid::new_v4());
+    let server = TestServer::spawn_with_env(&[("IRON_EXEC_SOCKET", &socket)]);
+    let log_handler = TestLog::new();
+
+    let output = cli(&socket, &["start", "--wait", "--", "echo", "hello"]);
+    assert_eq!(Some(0), output.status.code(), "{:?}", output);
+    let job_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let output = cli(&socket, &["logs", &job_id]);
+    assert_eq!("hello\n", String::from_utf8_lossy(&output.stdout));
+
+    let jobs: Vec<Value> = assert_ok!(serde_json::from_str(&http_get(&socket, "/jobs")));
+    assert_eq!(1, jobs.len());
+    assert_eq!(job_id, jobs[0]["id"]);
+
+    // only the server's own user may connect without a socket group
+    let metadata = assert_ok!(fs::metadata(&socket));
+    assert_eq!(0o600, metadata.permissions().mode() & 0o777);
+
+    // socket callers are named by their uid, which no certificate may claim
+    let uid = nix::unistd::getuid();
+    let test_cases = [
+        (
+            format!("uid:{}", uid),
+            Code::Unauthenticated,
+            "a certificate claims the same uid",
+        ),
+        (
+            "alice".to_string(),
+            Code::NotFound,
+            "querying as another owner",
+        ),
+    ];
+
+    for (common_name, expected_code, error_case) in test_cases {
+        let mut client = server.connect(&common_name).await;
+        let query = QueryRequest {
+            job_id: job_id.clone(),
+        };
+        let code = match client.query(query).await {
+            Ok(_) => Code::Ok,
+            Err(status) => status.code(),
+        };
+        assert_eq!(
+            expected_code, code,
+            "query did not return expected code when {}",
+            error_case
+        );
+    }
+
+    drop(server);
+    assert!(
+        !std::path::Path::new(&socket).exists(),
+        "socket was not removed on shutdown"
+    );
+    log_handler.consume(format!("echo_{}.log", job_id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn test_unix_socket_group() {
+    let socket = format!("./tests/.state/sock_{}", Uuid::new_v4());
+    let group = assert_ok!(nix::unistd::Group::from_gid(nix::unistd::Gid::from_raw(1)))
+        .expect("no group with gid 1");
+    let test_cases = [
+        (group.name.clone(), "the group is named"),
+        ("1".to_string(), "the group is given by gid"),
+    ];
+
+    for (socket_group, error_case) in test_cases {
+        let _server = TestServer::spawn_with_env(&[
+            ("IRON_EXEC_SOCKET", &socket),
+            ("IRON_EXEC_SOCKET_GROUP", &socket_group),
+        ]);
+        let output = cli(&socket, &["list"]);
+        assert_eq!(Some(0), output.status.code(), "{:?}", output);
+
+        // members of the group may connect as well
+        let metadata = assert_ok!(fs::metadata(&socket));
+        assert_eq!(
+            0o660,
+            metadata.permissions().mode() & 0o777,
+            "socket did not have expected mode when {}",
+            error_case
+        );
+        assert_eq!(
+            1,
+            metadata.gid(),
+            "socket did not have expected group when {}",
+            error_case
+        );
+    }
+}
@@ -3,12 +3,14 @@ use uuid::Uuid;
 
 use super::logs::{TestLog, LOG_DIR};
 
+#[allow(dead_code)]
 pub struct TestApp {
     pub worker: Worker,
     pub log_handler: TestLog,
 }
 
 impl TestApp {
+    #[allow(dead_code)]
     pub fn new() -> TestApp {
         let cfg = Config::new(LOG_DIR, 4);
         TestApp {
@@ -17,6 +19,20 @@ impl TestApp {
         }
     }
 
+    #[allow(dead_code)]
+    pub fn with_state_dir(state_dir: &'static str) -> TestApp {
+        TestApp::with_config(Config::new(LOG_DIR, 4).with_state_dir(state_dir))
+    }
+
+    #[allow(dead_code)]
+    pub fn with_config(cfg: Config) -> TestApp {
+        TestApp {
+            worker: Worker::new(cfg).unwrap(),
+            log_handler: TestLog::new(),
+        }
+    }
+
+    #[allow(dead_code)]
     pub fn wait(&self) -> Result<(Uuid, bool), Error> {
         let receiver = self.worker.notify_receiver();
         match receiver.recv() {
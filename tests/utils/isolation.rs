@@ -0,0 +1,89 @@
+use super::{app::TestApp, logs::LOG_DIR};
+use claim::assert_ok;
+use iron_exec::{
+    job::{Command, Isolation, JobInfo, Mount},
+    worker::Config,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+#[allow(dead_code)]
+pub fn pid_namespace_app() -> TestApp {
+    TestApp::with_config(
+        Config::new(LOG_DIR, 4).with_isolation(Isolation::default().with_pid_namespace()),
+    )
+}
+
+#[allow(dead_code)]
+pub fn sh(script: &str) -> Command {
+    Command::new("sh".into(), vec!["-c".into(), script.into()])
+}
+
+// Runs command to completion, returning its job info and output.
+#[allow(dead_code)]
+pub fn run(app: &TestApp, command: Command) -> (JobInfo, String) {
+    let owner_id = Uuid::new_v4();
+    let job_id = assert_ok!(app.worker.start(command.clone(), owner_id));
+    let job_info = assert_ok!(app
+        .worker
+        .wait(job_id, owner_id, Some(Duration::from_secs(5))));
+    let output = app
+        .log_handler
+        .consume(format!("{}_{}.log", command.name(), job_id));
+    (job_info, String::from_utf8_lossy(&output).into_owned())
+}
+
+// A root filesystem holding a marker file, with the host's binaries and
+// libraries to be bind mounted in read-only.
+#[allow(dead_code)]
+pub fn rootfs() -> (String, Vec<Mount>) {
+    let dir = format!(
+        "{}/tests/.state/rootfs_{}",
+        env!("CARGO_MANIFEST_DIR"),
+        Uuid::new_v4()
+    );
+    assert_ok!(std::fs::create_dir_all(format!("{}/usr", dir)));
+    assert_ok!(std::fs::write(format!("{}/marker", dir), "rootfs\n"));
+    let mut mounts = vec![Mount::new("/usr".into(), "/usr".into()).with_read_only()];
+    for path in ["bin", "lib", "lib64", "sbin"] {
+        let host_path = format!("/{}", path);
+        match std::fs::read_link(&host_path) {
+            Ok(link) => assert_ok!(std::os::unix::fs::symlink(
+                link,
+                format!("{}/{}", dir, path)
+            )),
+            Err(_) if std::path::Path::new(&host_path).is_dir() => {
+                assert_ok!(std::fs::create_dir(format!("{}/{}", dir, path)));
+                mounts.push(Mount::new(host_path.clone(), host_path).with_read_only());
+            }
+            Err(_) => {}
+        }
+    }
+    (dir, mounts)
+}
+
+// A worker allowed to bind mount the test's own directories and the host's
+// binaries and libraries.
+#[allow(dead_code)]
+pub fn mount_app(isolation: Isolation) -> TestApp {
+    let mut mount_sources = vec![format!("{}/tests/.state", env!("CARGO_MANIFEST_DIR"))];
+    mount_sources.extend(["/usr", "/bin", "/lib", "/lib64", "/sbin"].map(String::from));
+    TestApp::with_config(
+        Config::new(LOG_DIR, 4)
+            .with_isolation(isolation)
+            .with_mount_sources(mount_sources),
+    )
+}
+
+// A directory holding a single file, to bind mount into jobs.
+#[allow(dead_code)]
+pub fn mount_source() -> String {
+    let dir = format!(
+        "{}/tests/.state/mount_{}",
+        env!("CARGO_MANIFEST_DIR"),
+        Uuid::new_v4()
+    );
+    assert_ok!(std::fs::create_dir_all(&dir));
+    assert_ok!(std::fs::write(format!("{}/file", dir), "mounted\n"));
+    dir
+}
@@ -1,6 +1,6 @@
 use std::{env::current_dir, fs};
 
-pub const LOG_DIR: &'static str = "./tests/.logs";
+pub const LOG_DIR: &str = "./tests/.logs";
 
 pub struct TestLog {}
 
@@ -15,13 +15,12 @@ impl TestLog {
             .expect("failed to determine the current directory")
             .join(LOG_DIR)
             .join(log_filename);
-        let content = fs::read(log_file_path.clone()).expect(
-            format!(
+        let content = fs::read(log_file_path.clone()).unwrap_or_else(|_| {
+            panic!(
                 "expected log file {} does not exist",
                 log_file_path.to_str().unwrap()
             )
-            .as_str(),
-        );
+        });
         fs::remove_file(log_file_path).unwrap();
         content
     }
@@ -1,2 +1,7 @@
 pub mod app;
+pub mod isolation;
 pub mod logs;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "server")]
+pub mod tls;
@@ -0,0 +1,125 @@
+use iron_exec::server::proto::worker_client::WorkerClient;
+use nix::{
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, ChildStdout, Command, Stdio},
+};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+
+use super::{
+    logs::LOG_DIR,
+    tls::{TestCert, TestPki},
+};
+
+// Runs the iron-exec-server binary on a free local port for the lifetime of
+// the value, shutting it down with SIGTERM on drop.
+pub struct TestServer {
+    child: Child,
+    // kept open so the server can keep printing
+    _stdout: BufReader<ChildStdout>,
+    addr: String,
+    pub pki: TestPki,
+}
+
+impl TestServer {
+    #[allow(dead_code)]
+    pub fn spawn() -> TestServer {
+        TestServer::spawn_with_env(&[])
+    }
+
+    #[allow(dead_code)]
+    pub fn spawn_with_env(env: &[(&str, &str)]) -> TestServer {
+        let pki = TestPki::new();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_iron-exec-server"))
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .env("IRON_EXEC_LISTEN", "127.0.0.1:0")
+            .env("IRON_EXEC_LOG_DIR", LOG_DIR)
+            .env("IRON_EXEC_TLS_CERT", pki.path("server.pem"))
+            .env("IRON_EXEC_TLS_KEY", pki.path("server.key"))
+            .env("IRON_EXEC_TLS_CLIENT_CA", pki.path("ca.pem"))
+            .envs(env.iter().copied())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn iron-exec-server");
+
+        let mut line = String::new();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        stdout
+            .read_line(&mut line)
+            .expect("failed to read iron-exec-server address");
+        let addr = line
+            .trim()
+            .strip_prefix("listening on ")
+            .unwrap_or_else(|| panic!("unexpected iron-exec-server output {:?}", line))
+            .to_string();
+        TestServer {
+            child,
+            _stdout: stdout,
+            addr,
+            pki,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    // Connects with a fresh client certificate for common_name.
+    #[allow(dead_code)]
+    pub async fn connect(&self, common_name: &str) -> WorkerClient<Channel> {
+        self.connect_with(Some(&self.pki.client(common_name)))
+            .await
+            .expect("failed to connect to iron-exec-server")
+    }
+
+    #[allow(dead_code)]
+    pub async fn connect_with(
+        &self,
+        client: Option<&TestCert>,
+    ) -> Result<WorkerClient<Channel>, tonic::transport::Error> {
+        let mut tls = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(self.pki.ca_pem()))
+            .domain_name("localhost");
+        if let Some(client) = client {
+            tls = tls.identity(Identity::from_pem(&client.cert_pem, &client.key_pem));
+        }
+        let channel = Channel::from_shared(format!("https://{}", self.addr))
+            .unwrap()
+            .tls_config(tls)?
+            .connect()
+            .await?;
+        Ok(WorkerClient::new(channel))
+    }
+
+    // An HTTP client for the REST API with a fresh client certificate for
+    // common_name.
+    #[allow(dead_code)]
+    pub fn http_client(&self, common_name: &str) -> reqwest::Client {
+        let client = self.pki.client(common_name);
+        let identity = format!("{}{}", client.cert_pem, client.key_pem);
+        reqwest::Client::builder()
+            .add_root_certificate(
+                reqwest::Certificate::from_pem(self.pki.ca_pem().as_bytes()).unwrap(),
+            )
+            .identity(reqwest::Identity::from_pem(identity.as_bytes()).unwrap())
+            .build()
+            .expect("failed to build http client")
+    }
+
+    #[allow(dead_code)]
+    pub fn url(&self, path: &str) -> String {
+        let port = self.addr.rsplit(':').next().unwrap();
+        format!("https://localhost:{}{}", port, path)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = kill(Pid::from_raw(self.child.id() as i32), Signal::SIGTERM);
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,89 @@
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType, IsCa, KeyPair,
+    SanType,
+};
+use std::{fs, path::PathBuf};
+use uuid::Uuid;
+
+const STATE_DIR: &str = "./tests/.state";
+
+#[allow(dead_code)]
+pub struct TestCert {
+    pub cert_pem: String,
+    pub key_pem: String,
+    pub der: Vec<u8>,
+}
+
+// A throwaway CA that signs the server certificate and client certificates,
+// with its PEM files written to a directory removed on drop.
+pub struct TestPki {
+    ca: Certificate,
+    ca_key: KeyPair,
+    dir: PathBuf,
+}
+
+impl TestPki {
+    #[allow(dead_code)]
+    pub fn new() -> TestPki {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "iron-exec test ca");
+        let ca = params.self_signed(&ca_key).unwrap();
+
+        let dir = PathBuf::from(STATE_DIR).join(format!("tls_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("ca.pem"), ca.pem()).unwrap();
+        let pki = TestPki { ca, ca_key, dir };
+
+        let server = pki.sign(
+            Some("iron-exec-server"),
+            vec![SanType::DnsName("localhost".try_into().unwrap())],
+        );
+        fs::write(pki.dir.join("server.pem"), &server.cert_pem).unwrap();
+        fs::write(pki.dir.join("server.key"), &server.key_pem).unwrap();
+        pki
+    }
+
+    #[allow(dead_code)]
+    pub fn ca_pem(&self) -> String {
+        self.ca.pem()
+    }
+
+    #[allow(dead_code)]
+    pub fn path(&self, name: &str) -> String {
+        self.dir.join(name).to_str().unwrap().to_string()
+    }
+
+    #[allow(dead_code)]
+    pub fn client(&self, common_name: &str) -> TestCert {
+        self.sign(Some(common_name), vec![])
+    }
+
+    #[allow(dead_code)]
+    pub fn sign(&self, common_name: Option<&str>, sans: Vec<SanType>) -> TestCert {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.distinguished_name = DistinguishedName::new();
+        if let Some(common_name) = common_name {
+            params
+                .distinguished_name
+                .push(DnType::CommonName, common_name);
+        }
+        params.subject_alt_names = sans;
+        let cert = params.signed_by(&key, &self.ca, &self.ca_key).unwrap();
+        TestCert {
+            cert_pem: cert.pem(),
+            key_pem: key.serialize_pem(),
+            der: cert.der().to_vec(),
+        }
+    }
+}
+
+impl Drop for TestPki {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
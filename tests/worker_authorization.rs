@@ -0,0 +1,207 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, Status, StopType},
+    worker::{
+        owner_id_from_name, owner_id_from_uid, Config, Error, Operation, Permissions, Policy, Role,
+        Scope,
+    },
+};
+use std::fs;
+use utils::{app::TestApp, logs::LOG_DIR};
+use uuid::Uuid;
+
+const POLICY_DIR: &str = "./tests/.state/policy";
+
+#[test]
+pub fn test_role_permissions() {
+    let admin = Uuid::new_v4();
+    let auditor = Uuid::new_v4();
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+    let policy = Policy::default()
+        .with_member(admin, Role::Admin)
+        .with_member(auditor, Role::ReadOnly);
+    let app = TestApp::with_config(Config::new(LOG_DIR, 4).with_policy(policy));
+
+    let command = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let job_id = app.worker.start(command.clone(), alice).unwrap();
+    let not_found = format!("no job with id {} found for user", job_id);
+    let unknown_id = Uuid::new_v4();
+
+    let test_cases: [(Result<(), Error>, Option<&str>, &str); 11] = [
+        (
+            app.worker.start(command.clone(), auditor).map(|_| ()),
+            Some("read_only role may not start jobs"),
+            "read only caller starts a job",
+        ),
+        (
+            app.worker.query(job_id, auditor).map(|_| ()),
+            None,
+            "read only caller queries another owner's job",
+        ),
+        (
+            app.worker.stream(job_id, auditor).map(|_| ()),
+            None,
+            "read only caller streams another owner's job",
+        ),
+        (
+            app.worker.stop(job_id, auditor, false),
+            Some("read_only role may not stop jobs"),
+            "read only caller stops another owner's job",
+        ),
+        (
+            app.worker.stop(unknown_id, auditor, false),
+            Some("read_only role may not stop jobs"),
+            "read only caller stops a non-existent job",
+        ),
+        (
+            app.worker.signal(job_id, auditor, 1),
+            Some("read_only role may not signal jobs"),
+            "read only caller signals another owner's job",
+        ),
+        (
+            app.worker.purge(job_id, auditor),
+            Some("read_only role may not purge jobs"),
+            "read only caller purges another owner's job",
+        ),
+        (
+            app.worker.query(job_id, bob).map(|_| ()),
+            Some(&not_found),
+            "operator queries another owner's job",
+        ),
+        (
+            app.worker.stop(job_id, bob, false),
+            Some(&not_found),
+            "operator stops another owner's job",
+        ),
+        (
+            app.worker.signal(job_id, bob, 1),
+            Some(&not_found),
+            "operator signals another owner's job",
+        ),
+        (
+            app.worker.query(job_id, admin).map(|_| ()),
+            None,
+            "admin queries another owner's job",
+        ),
+    ];
+
+    for (res, expected_error, error_case) in test_cases {
+        match expected_error {
+            Some(expected_error) => {
+                let e = assert_err!(res, "call did not fail when {}", error_case);
+                assert_eq!(
+                    expected_error,
+                    e.as_str(),
+                    "error message did not match expected message when {}",
+                    error_case
+                );
+            }
+            None => {
+                assert_ok!(res, "call failed when {}", error_case);
+            }
+        }
+    }
+
+    let e = assert_err!(app.worker.start(command.clone(), auditor));
+    assert!(matches!(e, Error::PermissionDenied(_)));
+
+    let listed = |owner_id| -> Vec<Uuid> {
+        let jobs = assert_ok!(app.worker.list(owner_id));
+        jobs.iter().map(|job_info| job_info.id()).collect()
+    };
+    assert_eq!(vec![job_id], listed(admin));
+    assert_eq!(vec![job_id], listed(auditor));
+    assert_eq!(vec![job_id], listed(alice));
+    assert_eq!(Vec::<Uuid>::new(), listed(bob));
+
+    // an admin can stop somebody else's runaway job
+    assert_ok!(app.worker.stop(job_id, admin, false));
+    assert_ok!(app.wait());
+    let job_info = assert_ok!(app.worker.query(job_id, alice));
+    assert_eq!(Status::Stopped(StopType::Kill), job_info.status());
+
+    app.log_handler
+        .consume(format!("{}_{}.log", command.name(), job_id));
+}
+
+#[test]
+pub fn test_policy_load() {
+    fs::create_dir_all(POLICY_DIR).unwrap();
+    let admin = Uuid::new_v4();
+
+    let path = format!("{}/{}.toml", POLICY_DIR, Uuid::new_v4());
+    fs::write(
+        &path,
+        format!(
+            r#"
+default_role = "read_only"
+
+[roles.admin]
+members = ["root", "{}", "uid:1000"]
+
+[roles.operator]
+members = ["Alice"]
+operations = ["start", "query"]
+"#,
+            admin
+        ),
+    )
+    .unwrap();
+    let policy = assert_ok!(Policy::load(&path));
+    fs::remove_file(&path).unwrap();
+
+    let test_cases = [
+        (owner_id_from_name("root"), Role::Admin, "member is named"),
+        (admin, Role::Admin, "member is given by owner id"),
+        (
+            owner_id_from_uid(1000),
+            Role::Admin,
+            "member is a local user",
+        ),
+        (
+            owner_id_from_name("Alice"),
+            Role::Operator,
+            "member name is not lower case",
+        ),
+        (
+            owner_id_from_name("alice"),
+            Role::ReadOnly,
+            "caller only differs from a member by case",
+        ),
+        (Uuid::new_v4(), Role::ReadOnly, "caller is not a member"),
+    ];
+    for (owner_id, expected_role, error_case) in test_cases {
+        assert_eq!(
+            expected_role,
+            policy.role(owner_id),
+            "unexpected role when {}",
+            error_case
+        );
+    }
+
+    assert_eq!(
+        Permissions::new(vec![Operation::Start, Operation::Query], Scope::Own),
+        policy.permissions(Role::Operator),
+        "operator permissions were not overridden",
+    );
+    assert_eq!(
+        Policy::default().permissions(Role::Admin),
+        policy.permissions(Role::Admin),
+        "admin permissions did not keep their defaults",
+    );
+    let e = assert_err!(policy.authorize(owner_id_from_name("Alice"), Operation::Stop));
+    assert_eq!("operator role may not stop jobs", e.as_str());
+
+    let path = format!("{}/{}.toml", POLICY_DIR, Uuid::new_v4());
+    fs::write(&path, "[roles.superuser]\nmembers = [\"root\"]\n").unwrap();
+    let e = assert_err!(Policy::load(&path));
+    fs::remove_file(&path).unwrap();
+    assert!(
+        e.as_str().starts_with("failed to load policy"),
+        "unexpected error {}",
+        e.as_str()
+    );
+}
@@ -0,0 +1,100 @@
+mod utils;
+
+use claim::assert_err;
+use iron_exec::{
+    job::{Capabilities, Isolation, Status},
+    worker::Config,
+};
+use utils::{
+    app::TestApp,
+    isolation::{run, sh},
+    logs::LOG_DIR,
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_capabilities() {
+    let app = TestApp::new();
+    let bounded = Capabilities::default()
+        .with_bounding(vec!["CAP_CHOWN".into(), "CAP_NET_BIND_SERVICE".into()]);
+    let bounded_app = TestApp::with_config(
+        Config::new(LOG_DIR, 4).with_isolation(Isolation::default().with_capabilities(bounded)),
+    );
+    let status = |fields: &str| sh(&format!("grep -E '^({}):' /proc/self/status", fields));
+
+    let test_cases = [
+        (
+            &bounded_app,
+            status("CapBnd|CapEff"),
+            "CapEff:\t0000000000000401\nCapBnd:\t0000000000000401\n",
+            Isolation::default().with_capabilities(
+                Capabilities::default()
+                    .with_bounding(vec!["CAP_CHOWN".into(), "CAP_NET_BIND_SERVICE".into()]),
+            ),
+            "job runs within the worker's bounding set",
+        ),
+        (
+            &bounded_app,
+            status("CapBnd|CapEff").with_capabilities(
+                Capabilities::default()
+                    .with_bounding(vec!["CAP_NET_BIND_SERVICE".into(), "CAP_SYS_ADMIN".into()]),
+            ),
+            "CapEff:\t0000000000000400\nCapBnd:\t0000000000000400\n",
+            Isolation::default().with_capabilities(
+                Capabilities::default().with_bounding(vec!["CAP_NET_BIND_SERVICE".into()]),
+            ),
+            "job narrows the worker's bounding set",
+        ),
+        (
+            &app,
+            status("CapAmb")
+                .with_capabilities(Capabilities::default().with_ambient(vec!["CAP_CHOWN".into()])),
+            "CapAmb:\t0000000000000001\n",
+            Isolation::default()
+                .with_capabilities(Capabilities::default().with_ambient(vec!["CAP_CHOWN".into()])),
+            "job raises an ambient capability",
+        ),
+        (
+            &bounded_app,
+            status("CapAmb").with_capabilities(
+                Capabilities::default()
+                    .with_ambient(vec!["CAP_CHOWN".into(), "CAP_SYS_ADMIN".into()]),
+            ),
+            "CapAmb:\t0000000000000001\n",
+            Isolation::default().with_capabilities(
+                Capabilities::default()
+                    .with_bounding(vec!["CAP_CHOWN".into(), "CAP_NET_BIND_SERVICE".into()])
+                    .with_ambient(vec!["CAP_CHOWN".into()]),
+            ),
+            "job raises an ambient capability outside the worker's bounding set",
+        ),
+        (
+            &app,
+            status("NoNewPrivs").with_no_new_privs(),
+            "NoNewPrivs:\t1\n",
+            Isolation::default().with_no_new_privs(),
+            "job sets no_new_privs",
+        ),
+        (
+            &app,
+            status("NoNewPrivs"),
+            "NoNewPrivs:\t0\n",
+            Isolation::default(),
+            "job keeps the worker's privileges",
+        ),
+    ];
+
+    for (app, command, output, isolation, error_case) in test_cases {
+        let (job_info, job_output) = run(app, command);
+        assert_eq!(Status::Exited(Some(0)), job_info.status(), "{}", error_case);
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(isolation, job_info.isolation(), "{}", error_case);
+    }
+
+    let res = app.worker.start(
+        sh("true")
+            .with_capabilities(Capabilities::default().with_bounding(vec!["CAP_NOPE".into()])),
+        Uuid::new_v4(),
+    );
+    assert_eq!("unknown capability \"CAP_NOPE\"", assert_err!(res).as_str());
+}
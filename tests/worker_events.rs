@@ -0,0 +1,108 @@
+mod utils;
+
+use std::time::Duration;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, StopType},
+    worker::{Event, EventFilter, EventKind},
+};
+use utils::app::TestApp;
+use uuid::Uuid;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+pub fn test_subscribers_see_every_event() {
+    let app = TestApp::new();
+    let owner_id = Uuid::new_v4();
+
+    let first = app.worker.subscribe(EventFilter::all());
+    let second = app.worker.subscribe(EventFilter::owner(owner_id));
+
+    let command = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let job_id = app.worker.start(command.clone(), owner_id).unwrap();
+    let pid = app.worker.query(job_id, owner_id).unwrap().pid();
+
+    for (receiver, subscriber) in [(&first, "unfiltered"), (&second, "owner")] {
+        let expected = [
+            Event::new(job_id, owner_id, EventKind::Started { pid }),
+            Event::new(job_id, owner_id, EventKind::Exited { exit_code: Some(0) }),
+        ];
+        for expected_event in expected {
+            let event = assert_ok!(
+                receiver.recv_timeout(TIMEOUT),
+                "{} subscriber did not receive event",
+                subscriber,
+            );
+            assert_eq!(
+                expected_event, event,
+                "{} subscriber received unexpected event",
+                subscriber,
+            );
+        }
+    }
+
+    assert_ok!(app.wait());
+    app.log_handler
+        .consume(format!("{}_{}.log", command.name(), job_id));
+}
+
+#[test]
+pub fn test_subscription_filters() {
+    let app = TestApp::new();
+    let owner_id = Uuid::new_v4();
+    let other_owner_id = Uuid::new_v4();
+
+    let infinite_loop = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let watched_id = app.worker.start(infinite_loop.clone(), owner_id).unwrap();
+
+    let by_job = app.worker.subscribe(EventFilter::job(watched_id));
+    let by_owner = app.worker.subscribe(EventFilter::owner(owner_id));
+
+    let echo = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let other_id = app.worker.start(echo.clone(), other_owner_id).unwrap();
+    assert_ok!(app.wait());
+
+    let madeup = Command::new("whatever-madeup-command".into(), vec![]);
+    assert_err!(app.worker.start(madeup.clone(), owner_id));
+    let failed = assert_ok!(by_owner.recv_timeout(TIMEOUT));
+    assert_eq!(owner_id, failed.owner_id());
+    assert!(
+        matches!(failed.kind(), EventKind::Failed { .. }),
+        "failed start was not published",
+    );
+
+    app.worker.stop(watched_id, owner_id, true).unwrap();
+    assert_ok!(app.wait());
+
+    for (receiver, subscriber) in [(&by_job, "job"), (&by_owner, "owner")] {
+        let event = assert_ok!(
+            receiver.recv_timeout(TIMEOUT),
+            "{} subscriber did not receive stop",
+            subscriber,
+        );
+        assert_eq!(
+            Event::new(
+                watched_id,
+                owner_id,
+                EventKind::Stopped {
+                    stop_type: StopType::Term,
+                },
+            ),
+            event,
+            "{} subscriber received unexpected event",
+            subscriber,
+        );
+        assert_err!(
+            receiver.try_recv(),
+            "{} subscriber received events outside its filter",
+            subscriber,
+        );
+    }
+
+    app.log_handler
+        .consume(format!("{}_{}.log", echo.name(), other_id));
+    app.log_handler
+        .consume(format!("{}_{}.log", infinite_loop.name(), watched_id));
+}
@@ -0,0 +1,102 @@
+mod utils;
+
+use claim::assert_ok;
+use iron_exec::{
+    job::{Command, Status, StopType},
+    worker::{Entry, Journal},
+};
+use utils::app::TestApp;
+use uuid::Uuid;
+
+const STATE_DIR: &str = "./tests/.state/journal";
+
+#[test]
+pub fn test_journal_rebuilds_jobs() {
+    let _ = std::fs::remove_dir_all(STATE_DIR);
+    let app = TestApp::with_state_dir(STATE_DIR);
+    let owner_id = Uuid::new_v4();
+
+    let echo = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let exited_id = app.worker.start(echo.clone(), owner_id).unwrap();
+    assert_ok!(app.wait());
+
+    let infinite_loop = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let stopped_id = app.worker.start(infinite_loop.clone(), owner_id).unwrap();
+    app.worker.stop(stopped_id, owner_id, true).unwrap();
+    assert_ok!(app.wait());
+
+    let long_runtime = Command::new("sh".into(), vec!["./tests/scripts/long_runtime.sh".into()]);
+    let running_id = app.worker.start(long_runtime.clone(), owner_id).unwrap();
+
+    let entries = assert_ok!(assert_ok!(Journal::open(STATE_DIR)).entries());
+    assert_eq!(6, entries.len(), "journal did not record every job event");
+    assert!(
+        entries.contains(&Entry::Exited {
+            job_id: exited_id,
+            exit_code: Some(0),
+        }),
+        "journal did not record exit of finished job",
+    );
+    assert!(
+        entries.contains(&Entry::Stopped {
+            job_id: stopped_id,
+            stop_type: StopType::Term,
+        }),
+        "journal did not record stop of terminated job",
+    );
+
+    let restarted = TestApp::with_state_dir(STATE_DIR);
+    let test_cases = [
+        (exited_id, &echo, Status::Exited(Some(0)), "job had exited"),
+        (
+            stopped_id,
+            &infinite_loop,
+            Status::Stopped(StopType::Term),
+            "job had been terminated",
+        ),
+        (
+            running_id,
+            &long_runtime,
+            Status::UnknownState,
+            "job was still running",
+        ),
+    ];
+
+    for (job_id, command, expected_status, error_case) in test_cases {
+        let job_info = assert_ok!(
+            restarted.worker.query(job_id, owner_id),
+            "restarted worker did not know job when {}",
+            error_case,
+        );
+        assert_eq!(
+            expected_status,
+            job_info.status(),
+            "restarted worker did not restore status when {}",
+            error_case,
+        );
+        assert_eq!(
+            *command,
+            job_info.command(),
+            "restarted worker did not restore command when {}",
+            error_case,
+        );
+        assert_ok!(
+            restarted.worker.stream(job_id, owner_id),
+            "restarted worker could not stream logs when {}",
+            error_case,
+        );
+    }
+
+    app.worker.stop(running_id, owner_id, false).unwrap();
+    assert_ok!(app.wait());
+
+    for (job_id, command) in [
+        (exited_id, echo),
+        (stopped_id, infinite_loop),
+        (running_id, long_runtime),
+    ] {
+        app.log_handler
+            .consume(format!("{}_{}.log", command.name(), job_id));
+    }
+    std::fs::remove_dir_all(STATE_DIR).unwrap();
+}
@@ -0,0 +1,168 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Isolation, Landlock, Status},
+    worker::Config,
+};
+use utils::{
+    app::TestApp,
+    isolation::{run, sh},
+    logs::LOG_DIR,
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_landlock() {
+    let app = TestApp::new();
+    let dir = format!(
+        "{}/tests/.state/landlock_{}",
+        env!("CARGO_MANIFEST_DIR"),
+        Uuid::new_v4()
+    );
+    assert_ok!(std::fs::create_dir_all(format!("{}/ro", dir)));
+    assert_ok!(std::fs::create_dir_all(format!("{}/rw", dir)));
+    assert_ok!(std::fs::create_dir_all(format!("{}/hidden", dir)));
+    assert_ok!(std::fs::write(format!("{}/ro/file", dir), "read-only\n"));
+    assert_ok!(std::fs::write(format!("{}/hidden/file", dir), "hidden\n"));
+    // what sh needs to run at all
+    let system: Vec<String> = ["/usr", "/bin", "/lib", "/lib64", "/etc"]
+        .into_iter()
+        .filter(|path| std::path::Path::new(path).exists())
+        .map(String::from)
+        .collect();
+    let landlock = Landlock::new(
+        [system.clone(), vec![format!("{}/ro", dir)]].concat(),
+        vec![format!("{}/rw", dir)],
+    );
+
+    let test_cases = [
+        (
+            format!("cat {}/ro/file", dir),
+            Status::Exited(Some(0)),
+            "read-only\n".to_string(),
+            "job reads a read-only path",
+        ),
+        (
+            format!("echo written > {0}/rw/file && cat {0}/rw/file", dir),
+            Status::Exited(Some(0)),
+            "written\n".to_string(),
+            "job writes a read-write path",
+        ),
+        (
+            format!("echo written > {}/ro/file", dir),
+            Status::Exited(Some(2)),
+            format!("sh: 1: cannot create {}/ro/file: Permission denied\n", dir),
+            "job writes a read-only path",
+        ),
+        (
+            format!("cat {}/hidden/file", dir),
+            Status::Exited(Some(1)),
+            format!("cat: {}/hidden/file: Permission denied\n", dir),
+            "job reads a path it is not given",
+        ),
+    ];
+
+    for (script, status, output, error_case) in test_cases {
+        let (job_info, job_output) = run(&app, sh(&script).with_landlock(landlock.clone()));
+        assert_eq!(status, job_info.status(), "{}", error_case);
+        assert_eq!(
+            status,
+            Status::Exited(job_info.exit_code()),
+            "{}",
+            error_case
+        );
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(
+            vec![landlock.clone()],
+            job_info.isolation().landlock(),
+            "{}",
+            error_case
+        );
+    }
+    assert_eq!(
+        "read-only\n",
+        assert_ok!(std::fs::read_to_string(format!("{}/ro/file", dir)))
+    );
+
+    let res = app.worker.start(
+        sh("true").with_landlock(Landlock::new(
+            [system, vec![format!("{}/missing", dir)]].concat(),
+            vec![],
+        )),
+        Uuid::new_v4(),
+    );
+    assert_eq!(
+        "failed to apply the job's landlock ruleset: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }",
+        assert_err!(res).as_str()
+    );
+}
+
+#[test]
+pub fn test_landlock_worker_ruleset() {
+    let dir = format!(
+        "{}/tests/.state/landlock_{}",
+        env!("CARGO_MANIFEST_DIR"),
+        Uuid::new_v4()
+    );
+    assert_ok!(std::fs::create_dir_all(format!("{}/rw", dir)));
+    assert_ok!(std::fs::create_dir_all(format!("{}/hidden", dir)));
+    assert_ok!(std::fs::write(format!("{}/hidden/file", dir), "hidden\n"));
+    let system: Vec<String> = ["/usr", "/bin", "/lib", "/lib64", "/etc"]
+        .into_iter()
+        .filter(|path| std::path::Path::new(path).exists())
+        .map(String::from)
+        .collect();
+    let worker = Landlock::new(system.clone(), vec![format!("{}/rw", dir)]);
+    let app = TestApp::with_config(
+        Config::new(LOG_DIR, 4).with_isolation(Isolation::default().with_landlock(worker.clone())),
+    );
+    let command = Landlock::new(
+        [
+            system,
+            vec![format!("{}/rw", dir), format!("{}/hidden", dir)],
+        ]
+        .concat(),
+        vec![],
+    );
+
+    let test_cases = [
+        (
+            sh(&format!(
+                "echo written > {0}/rw/file && cat {0}/rw/file",
+                dir
+            )),
+            Status::Exited(Some(0)),
+            "written\n".to_string(),
+            vec![worker.clone()],
+            "job without a ruleset of its own writes a path the worker allows",
+        ),
+        (
+            sh(&format!("cat {}/hidden/file", dir)).with_landlock(command.clone()),
+            Status::Exited(Some(1)),
+            format!("cat: {}/hidden/file: Permission denied\n", dir),
+            vec![worker.clone(), command.clone()],
+            "job reads a path only its own ruleset allows",
+        ),
+        (
+            sh(&format!("echo again > {}/rw/file", dir)).with_landlock(command.clone()),
+            Status::Exited(Some(2)),
+            format!("sh: 1: cannot create {}/rw/file: Permission denied\n", dir),
+            vec![worker.clone(), command.clone()],
+            "job writes a path its own ruleset makes read-only",
+        ),
+    ];
+
+    for (command, status, output, landlock, error_case) in test_cases {
+        let (job_info, job_output) = run(&app, command);
+        assert_eq!(status, job_info.status(), "{}", error_case);
+        assert_eq!(
+            status,
+            Status::Exited(job_info.exit_code()),
+            "{}",
+            error_case
+        );
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(landlock, job_info.isolation().landlock(), "{}", error_case);
+    }
+}
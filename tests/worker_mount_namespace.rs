@@ -0,0 +1,270 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::job::{Isolation, Mount};
+use utils::{
+    app::TestApp,
+    isolation::{mount_app, mount_source, run, sh},
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_mount_namespace() {
+    let app = mount_app(Isolation::default().with_mount_namespace());
+    let source = mount_source();
+    let target = mount_source();
+    let host_file = format!("/tmp/iron_exec_{}", Uuid::new_v4());
+
+    let test_cases = [
+        (
+            sh(&format!("echo job > {} && ls -A /tmp", host_file)),
+            0,
+            format!("{}\n", host_file.trim_start_matches("/tmp/")),
+            "job writes to its private /tmp",
+        ),
+        (
+            sh(&format!("cat {0}/file && touch {0}/new", target))
+                .with_mounts(vec![Mount::new(source.clone(), target.clone())]),
+            0,
+            "mounted\n".to_string(),
+            "job writes to a bind mount",
+        ),
+        (
+            sh("cat /tmp/data/file; touch /tmp/data/other 2>/dev/null").with_mounts(vec![
+                Mount::new(source.clone(), "/tmp/data".into()).with_read_only(),
+            ]),
+            1,
+            "mounted\n".to_string(),
+            "job writes to a read-only bind mount",
+        ),
+        (
+            sh(&format!("touch {}/root 2>/dev/null", source)),
+            0,
+            String::new(),
+            "job writes outside of /tmp without a read-only root",
+        ),
+    ];
+
+    for (command, expected_code, expected_output, error_case) in test_cases {
+        let (job_info, output) = run(&app, command);
+        assert_eq!(
+            Some(expected_code),
+            job_info.exit_code(),
+            "job did not exit with expected exit code when {}: {:?}",
+            error_case,
+            output
+        );
+        assert_eq!(
+            expected_output, output,
+            "job did not print expected output when {}",
+            error_case
+        );
+    }
+
+    assert!(
+        !std::path::Path::new(&host_file).exists(),
+        "job's /tmp was shared with the host"
+    );
+    assert!(std::path::Path::new(&format!("{}/new", source)).exists());
+    assert!(!std::path::Path::new(&format!("{}/new", target)).exists());
+    assert!(!std::path::Path::new(&format!("{}/other", source)).exists());
+}
+
+#[test]
+pub fn test_mount_namespace_read_only_root() {
+    let app = mount_app(
+        Isolation::default()
+            .with_pid_namespace()
+            .with_read_only_root(),
+    );
+    let source = mount_source();
+    let worker_pid = std::process::id();
+
+    let test_cases = [
+        (
+            sh(&format!("touch {}/root 2>/dev/null", source)),
+            1,
+            String::new(),
+            "job writes to the read-only root",
+        ),
+        (
+            sh("touch /tmp/file && ls /tmp"),
+            0,
+            "file\n".to_string(),
+            "job writes to its private /tmp",
+        ),
+        (
+            sh(&format!(
+                "cat {0}/file && touch {0}/new 2>/dev/null",
+                source
+            ))
+            .with_mounts(vec![Mount::new(source.clone(), source.clone())]),
+            1,
+            "mounted\n".to_string(),
+            "job writes to a bind mount under the read-only root",
+        ),
+        (
+            sh(&format!(
+                "test -d /proc/1 && test ! -d /proc/{}",
+                worker_pid
+            )),
+            0,
+            String::new(),
+            "job lists the processes of its pid namespace",
+        ),
+    ];
+
+    for (command, expected_code, expected_output, error_case) in test_cases {
+        let (job_info, output) = run(&app, command);
+        assert_eq!(
+            Some(expected_code),
+            job_info.exit_code(),
+            "job did not exit with expected exit code when {}: {:?}",
+            error_case,
+            output
+        );
+        assert!(
+            output.ends_with(&expected_output),
+            "job did not print expected output when {}: {:?}",
+            error_case,
+            output
+        );
+    }
+    assert!(!std::path::Path::new(&format!("{}/root", source)).exists());
+    assert!(!std::path::Path::new(&format!("{}/new", source)).exists());
+}
+
+#[test]
+pub fn test_mount_namespace_per_job() {
+    let app = mount_app(Isolation::default());
+    let source = mount_source();
+    let host_file = format!("/tmp/iron_exec_{}", Uuid::new_v4());
+
+    let test_cases = [
+        (
+            sh(&format!("echo job > {} && ls -A /tmp", host_file)).with_mount_namespace(),
+            0,
+            format!("{}\n", host_file.trim_start_matches("/tmp/")),
+            (true, false),
+            "job asks for a private /tmp",
+        ),
+        (
+            sh(&format!("touch {}/root 2>/dev/null", source)).with_read_only_root(),
+            1,
+            String::new(),
+            (true, true),
+            "job asks for a read-only root",
+        ),
+        (
+            sh("cat /tmp/data/file")
+                .with_mount_namespace()
+                .with_mounts(vec![Mount::new(source.clone(), "/tmp/data".into())]),
+            0,
+            "mounted\n".to_string(),
+            (true, false),
+            "job asks for a bind mount in its own mount namespace",
+        ),
+        (
+            sh("true"),
+            0,
+            String::new(),
+            (false, false),
+            "job asks for neither",
+        ),
+    ];
+
+    for (command, expected_code, expected_output, expected_isolation, error_case) in test_cases {
+        let (job_info, output) = run(&app, command);
+        assert_eq!(
+            Some(expected_code),
+            job_info.exit_code(),
+            "job did not exit with expected exit code when {}: {:?}",
+            error_case,
+            output
+        );
+        assert_eq!(
+            expected_output, output,
+            "job did not print expected output when {}",
+            error_case
+        );
+        let isolation = job_info.isolation();
+        assert_eq!(
+            expected_isolation,
+            (isolation.mount_namespace(), isolation.read_only_root()),
+            "job info did not report the expected isolation when {}",
+            error_case
+        );
+    }
+
+    assert!(
+        !std::path::Path::new(&host_file).exists(),
+        "job's /tmp was shared with the host"
+    );
+    assert!(!std::path::Path::new(&format!("{}/root", source)).exists());
+}
+
+#[test]
+pub fn test_mount_errors() {
+    let source = mount_source();
+    let mount_app = mount_app(Isolation::default().with_mount_namespace());
+    let app = TestApp::new();
+
+    let missing_source = format!("{}/missing", source);
+    let link = format!("{}/link", source);
+    assert_ok!(std::os::unix::fs::symlink("/etc", &link));
+    let test_cases = [
+        (
+            &app,
+            Mount::new(source.clone(), "/data".into()),
+            "bind mounts need the job to have a mount namespace".to_string(),
+            "worker has no mount namespace",
+        ),
+        (
+            &mount_app,
+            Mount::new(missing_source.clone(), "/data".into()),
+            format!(
+                "failed to read mount source {}: Os {{ code: 2, kind: NotFound, message: \"No such file or directory\" }}",
+                missing_source
+            ),
+            "mount source does not exist",
+        ),
+        (
+            &mount_app,
+            Mount::new("/etc".into(), "/data".into()),
+            "mount source /etc is outside of the worker's allowed mount sources".to_string(),
+            "mount source is not allowed",
+        ),
+        (
+            &mount_app,
+            Mount::new(link.clone(), "/data".into()),
+            format!(
+                "mount source {} is outside of the worker's allowed mount sources",
+                link
+            ),
+            "mount source links to a path that is not allowed",
+        ),
+        (
+            &mount_app,
+            Mount::new(source.clone(), "data".into()),
+            "mount target data is not an absolute path".to_string(),
+            "mount target is relative",
+        ),
+        (
+            &mount_app,
+            Mount::new(source.clone(), "/no/such/dir".into()),
+            "failed to set up the job's mounts: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }".to_string(),
+            "mount target outside of /tmp does not exist",
+        ),
+    ];
+
+    for (app, mount, expected_error, error_case) in test_cases {
+        let command = sh("true").with_mounts(vec![mount]);
+        let error = assert_err!(app.worker.start(command, Uuid::new_v4()));
+        assert_eq!(
+            expected_error,
+            error.as_str(),
+            "start did not fail as expected when {}",
+            error_case
+        );
+    }
+}
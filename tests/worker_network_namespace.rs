@@ -0,0 +1,77 @@
+mod utils;
+
+use claim::assert_ok;
+use iron_exec::{
+    job::{Command, Isolation, Network},
+    worker::Config,
+};
+use std::net::TcpListener;
+use utils::{app::TestApp, isolation::run, logs::LOG_DIR};
+
+#[test]
+pub fn test_network_namespace() {
+    let app = TestApp::new();
+    let offline_app = TestApp::with_config(
+        Config::new(LOG_DIR, 4)
+            .with_isolation(Isolation::default().with_network(Network::Loopback)),
+    );
+    let listener = assert_ok!(TcpListener::bind("127.0.0.1:0"));
+    let connect = format!(
+        "echo > /dev/tcp/127.0.0.1/{}",
+        assert_ok!(listener.local_addr()).port()
+    );
+    let bash = |script: &str| Command::new("bash".into(), vec!["-c".into(), script.into()]);
+    let loopback = Isolation::default().with_network(Network::Loopback);
+
+    let test_cases = [
+        (
+            &app,
+            bash(&connect),
+            0,
+            Isolation::default(),
+            "job connects to the host on the host network",
+        ),
+        (
+            &app,
+            bash(&format!("{} 2>/dev/null", connect)).with_network(Network::Loopback),
+            1,
+            loopback.clone(),
+            "job connects to the host when asking for loopback only",
+        ),
+        (
+            &app,
+            bash("cut -d: -f1 /proc/net/dev | tail -n +3 | tr -d ' ' && : > /dev/udp/127.0.0.1/9")
+                .with_network(Network::Loopback),
+            0,
+            loopback.clone(),
+            "job uses loopback when asking for loopback only",
+        ),
+        (
+            &offline_app,
+            bash(&format!("{} 2>/dev/null", connect)).with_network(Network::Host),
+            1,
+            loopback.clone(),
+            "job asks for the host network from an offline worker",
+        ),
+    ];
+
+    for (app, command, expected_code, expected_isolation, error_case) in test_cases {
+        let (job_info, output) = run(app, command);
+        assert_eq!(
+            Some(expected_code),
+            job_info.exit_code(),
+            "job did not exit with expected exit code when {}: {:?}",
+            error_case,
+            output
+        );
+        assert_eq!(
+            expected_isolation,
+            job_info.isolation(),
+            "job info did not report expected isolation when {}",
+            error_case
+        );
+        if expected_code == 0 && !job_info.isolation().network().is_host() {
+            assert_eq!("lo\n", output, "job saw interfaces other than loopback");
+        }
+    }
+}
@@ -0,0 +1,57 @@
+mod utils;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use claim::assert_ok;
+use iron_exec::{
+    job::{Command, Status},
+    worker::Config,
+};
+use utils::{app::TestApp, logs::LOG_DIR};
+use uuid::Uuid;
+
+#[test]
+pub fn test_undrained_notify_channel_does_not_block() {
+    let app = TestApp::with_config(Config::new(LOG_DIR, 1).with_notify_capacity(2));
+    let owner_id = Uuid::new_v4();
+    let command = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+
+    let job_ids: Vec<Uuid> = (0..6)
+        .map(|_| app.worker.start(command.clone(), owner_id).unwrap())
+        .collect();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    for job_id in job_ids.iter() {
+        loop {
+            let job_info = assert_ok!(app.worker.query(*job_id, owner_id));
+            if job_info.status() == Status::Exited(Some(0)) {
+                break;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "job exits were blocked by an undrained notify channel",
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // give the pool thread time to publish the final result
+    thread::sleep(Duration::from_millis(100));
+    let receiver = app.worker.notify_receiver();
+    assert_eq!(
+        2,
+        receiver.len(),
+        "notify channel did not hold only the newest results",
+    );
+    while let Ok(res) = receiver.try_recv() {
+        assert!(job_ids.contains(&assert_ok!(res).0));
+    }
+
+    for job_id in job_ids {
+        app.log_handler
+            .consume(format!("{}_{}.log", command.name(), job_id));
+    }
+}
@@ -0,0 +1,131 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::job::{Command, Status, StopType};
+use std::time::{Duration, Instant};
+use utils::isolation::{pid_namespace_app, sh};
+use uuid::Uuid;
+
+#[test]
+pub fn test_pid_namespace_exits() {
+    let app = pid_namespace_app();
+
+    let worker_pid = std::process::id();
+    let test_cases = [
+        (
+            sh("echo $$"),
+            Status::Exited(Some(0)),
+            "2\n".to_string(),
+            "job runs as the child of the namespace's init",
+        ),
+        (
+            sh("exit 3"),
+            Status::Exited(Some(3)),
+            String::new(),
+            "job exits with status 3",
+        ),
+        (
+            sh("kill -9 $$"),
+            Status::Exited(None),
+            String::new(),
+            "job is killed by a signal of its own",
+        ),
+        (
+            sh(&format!("kill -0 {} || echo unreachable", worker_pid)),
+            Status::Exited(Some(0)),
+            "unreachable\n".to_string(),
+            "job signals the worker by its host pid",
+        ),
+    ];
+
+    for (command, expected_status, expected_output, error_case) in test_cases {
+        let owner_id = Uuid::new_v4();
+        let job_id = assert_ok!(app.worker.start(command.clone(), owner_id));
+        assert_ok!(app.wait());
+        let job_info = assert_ok!(app.worker.query(job_id, owner_id));
+        assert_eq!(
+            expected_status,
+            job_info.status(),
+            "job was not in expected state when {}",
+            error_case
+        );
+        assert_eq!(
+            expected_status,
+            match job_info.exit_code() {
+                Some(code) => Status::Exited(Some(code)),
+                None => Status::Exited(None),
+            },
+            "job did not exit with expected exit code when {}",
+            error_case
+        );
+        let output = app
+            .log_handler
+            .consume(format!("{}_{}.log", command.name(), job_id));
+        assert!(
+            String::from_utf8_lossy(&output).ends_with(&expected_output),
+            "job did not print expected output when {}: {:?}",
+            error_case,
+            String::from_utf8_lossy(&output)
+        );
+    }
+}
+
+#[test]
+pub fn test_pid_namespace_stop() {
+    let app = pid_namespace_app();
+
+    let test_cases = [
+        (true, StopType::Term, "job is stopped gracefully"),
+        (false, StopType::Kill, "job is killed"),
+    ];
+
+    for (gracefully, stop_type, error_case) in test_cases {
+        let owner_id = Uuid::new_v4();
+        let command = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+        let job_id = assert_ok!(app.worker.start(command, owner_id));
+        assert_ok!(app.worker.stop(job_id, owner_id, gracefully));
+        let job_info = assert_ok!(app
+            .worker
+            .wait(job_id, owner_id, Some(Duration::from_secs(5))));
+        assert_eq!(
+            Status::Stopped(stop_type),
+            job_info.status(),
+            "job was not in expected state when {}",
+            error_case
+        );
+        app.log_handler.consume(format!("sh_{}.log", job_id));
+    }
+}
+
+#[test]
+pub fn test_pid_namespace_reaps_orphans() {
+    let app = pid_namespace_app();
+    let owner_id = Uuid::new_v4();
+
+    // the orphaned sleep is killed with the namespace once the job exits, so
+    // its output never makes it to the log
+    let command = sh("(sleep 1; echo orphan) & echo started");
+    let started = Instant::now();
+    let job_id = assert_ok!(app.worker.start(command, owner_id));
+    let job_info = assert_ok!(app
+        .worker
+        .wait(job_id, owner_id, Some(Duration::from_secs(5))));
+    assert_eq!(Status::Exited(Some(0)), job_info.status());
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    std::thread::sleep(Duration::from_millis(1500));
+    let output = app.log_handler.consume(format!("sh_{}.log", job_id));
+    assert_eq!("started\n", String::from_utf8_lossy(&output));
+}
+
+#[test]
+pub fn test_pid_namespace_start_error() {
+    let app = pid_namespace_app();
+
+    let command = Command::new("whatever-madeup-command".into(), vec![]);
+    let error = assert_err!(app.worker.start(command, Uuid::new_v4()));
+    assert_eq!(
+        "failed to spawn child process: Os { code: 2, kind: NotFound, message: \"No such file or directory\" }",
+        error.as_str()
+    );
+}
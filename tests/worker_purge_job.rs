@@ -0,0 +1,53 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::job::Command;
+use std::path::Path;
+use utils::{app::TestApp, logs::LOG_DIR};
+use uuid::Uuid;
+
+const STATE_DIR: &str = "./tests/.state/purge";
+
+#[test]
+pub fn test_purge_job() {
+    let _ = std::fs::remove_dir_all(STATE_DIR);
+    let app = TestApp::with_state_dir(STATE_DIR);
+    let owner_id = Uuid::new_v4();
+
+    let echo = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let echo_id = app.worker.start(echo, owner_id).unwrap();
+    assert_ok!(app.wait());
+    let infinite_loop = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let loop_id = app.worker.start(infinite_loop, owner_id).unwrap();
+
+    let e = assert_err!(app.worker.purge(loop_id, owner_id));
+    assert_eq!(format!("job {} has not finished yet", loop_id), e.as_str());
+    let e = assert_err!(app.worker.purge(echo_id, Uuid::new_v4()));
+    assert_eq!(
+        format!("no job with id {} found for user", echo_id),
+        e.as_str()
+    );
+
+    assert_ok!(app.worker.purge(echo_id, owner_id));
+    assert_err!(app.worker.query(echo_id, owner_id));
+    assert!(
+        !Path::new(LOG_DIR)
+            .join(format!("echo_{}.log", echo_id))
+            .exists(),
+        "log of purged job was not removed",
+    );
+    let e = assert_err!(app.worker.purge(echo_id, owner_id));
+    assert_eq!(
+        format!("no job with id {} found for user", echo_id),
+        e.as_str()
+    );
+
+    app.worker.stop(loop_id, owner_id, false).unwrap();
+    assert_ok!(app.wait());
+    assert_ok!(app.worker.purge(loop_id, owner_id));
+    drop(app);
+
+    // purged jobs stay gone once the journal is replayed
+    let app = TestApp::with_state_dir(STATE_DIR);
+    assert_eq!(0, assert_ok!(app.worker.list(owner_id)).len());
+}
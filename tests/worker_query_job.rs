@@ -66,7 +66,7 @@ pub fn test_query_success() {
             assert_ok!(app.wait());
             let job_info = assert_ok!(app.worker.query(job_id, owner_id), "query request failed");
             assert_eq!(
-                expected_status.to_string(),
+                expected_status,
                 job_info.status(),
                 "job was not in expected state when {}",
                 error_case
@@ -82,7 +82,7 @@ pub fn test_query_success() {
             if close_after {
                 let job_info = assert_ok!(app.worker.query(job_id, owner_id));
                 assert_eq!(
-                    expected_status.to_string(),
+                    expected_status,
                     job_info.status(),
                     "job was not in expected state when {}",
                     error_case,
@@ -99,7 +99,7 @@ pub fn test_query_success() {
                 assert_ok!(app.wait());
                 let job_info = assert_ok!(app.worker.query(job_id, owner_id));
                 assert_eq!(
-                    expected_status.to_string(),
+                    expected_status,
                     job_info.status(),
                     "job was not in expected state when {}",
                     error_case
@@ -157,3 +157,45 @@ pub fn test_query_error() {
 
     app.log_handler.consume(format!("echo_{}.log", job));
 }
+
+#[test]
+pub fn test_list_jobs() {
+    let app = TestApp::new();
+
+    let owner_id = Uuid::new_v4();
+    let other_owner_id = Uuid::new_v4();
+    let command = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let first = app.worker.start(command.clone(), owner_id).unwrap();
+    assert_ok!(app.wait());
+    let second = app.worker.start(command.clone(), owner_id).unwrap();
+    assert_ok!(app.wait());
+    let other = app.worker.start(command.clone(), other_owner_id).unwrap();
+    assert_ok!(app.wait());
+
+    let test_cases = [
+        (
+            owner_id,
+            vec![first, second],
+            "listing an owner with two jobs",
+        ),
+        (other_owner_id, vec![other], "listing an owner with one job"),
+        (Uuid::new_v4(), vec![], "listing an owner without jobs"),
+    ];
+
+    for (owner_id, expected_ids, error_case) in test_cases {
+        let jobs = assert_ok!(app.worker.list(owner_id));
+        let ids: Vec<Uuid> = jobs.iter().map(|job_info| job_info.id()).collect();
+        assert_eq!(
+            expected_ids, ids,
+            "listed jobs did not match expected jobs when {}",
+            error_case
+        );
+        for job_info in jobs {
+            assert_eq!(Status::Exited(Some(0)), job_info.status());
+        }
+    }
+
+    for job_id in [first, second, other] {
+        app.log_handler.consume(format!("echo_{}.log", job_id));
+    }
+}
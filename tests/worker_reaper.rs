@@ -0,0 +1,72 @@
+mod utils;
+
+use std::{collections::HashSet, time::Duration};
+
+use claim::assert_ok;
+use iron_exec::{
+    job::{Command, Status},
+    worker::Config,
+};
+use utils::{app::TestApp, logs::LOG_DIR};
+use uuid::Uuid;
+
+#[test]
+pub fn test_exit_noticed_while_pool_is_busy() {
+    let app = TestApp::with_config(Config::new(LOG_DIR, 1));
+    let owner_id = Uuid::new_v4();
+
+    let long_runtime = Command::new("sh".into(), vec!["./tests/scripts/long_runtime.sh".into()]);
+    let running_id = app.worker.start(long_runtime.clone(), owner_id).unwrap();
+    let echo = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let exited_id = app.worker.start(echo.clone(), owner_id).unwrap();
+
+    let res = assert_ok!(
+        app.worker
+            .notify_receiver()
+            .recv_timeout(Duration::from_secs(5)),
+        "exit of job was not noticed while another job was running",
+    );
+    assert_eq!(exited_id, assert_ok!(res).0);
+    assert_eq!(
+        Status::Exited(Some(0)),
+        assert_ok!(app.worker.query(exited_id, owner_id)).status(),
+    );
+
+    app.worker.stop(running_id, owner_id, false).unwrap();
+    assert_ok!(app.wait());
+    app.log_handler
+        .consume(format!("{}_{}.log", echo.name(), exited_id));
+    app.log_handler
+        .consume(format!("{}_{}.log", long_runtime.name(), running_id));
+}
+
+#[test]
+pub fn test_reap_many_concurrent_jobs() {
+    let app = TestApp::with_config(Config::new(LOG_DIR, 2));
+    let owner_id = Uuid::new_v4();
+    let command = Command::new("sleep".into(), vec!["1".into()]);
+
+    let job_ids: HashSet<Uuid> = (0..64)
+        .map(|_| app.worker.start(command.clone(), owner_id).unwrap())
+        .collect();
+
+    let receiver = app.worker.notify_receiver();
+    let mut reaped = HashSet::new();
+    for _ in 0..job_ids.len() {
+        let res = assert_ok!(
+            receiver.recv_timeout(Duration::from_secs(10)),
+            "not every job exit was noticed",
+        );
+        reaped.insert(assert_ok!(res).0);
+    }
+    assert_eq!(job_ids, reaped, "reaped jobs did not match started jobs");
+
+    for job_id in job_ids {
+        assert_eq!(
+            Status::Exited(Some(0)),
+            assert_ok!(app.worker.query(job_id, owner_id)).status(),
+        );
+        app.log_handler
+            .consume(format!("{}_{}.log", command.name(), job_id));
+    }
+}
@@ -0,0 +1,102 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, Isolation, Status, StopType},
+    worker::{Config, Entry, Journal},
+};
+use utils::{app::TestApp, logs::LOG_DIR};
+use uuid::Uuid;
+
+const STATE_DIR: &str = "./tests/.state/recovery";
+
+#[test]
+pub fn test_recover_orphaned_jobs() {
+    let _ = std::fs::remove_dir_all(STATE_DIR);
+    let app = TestApp::with_state_dir(STATE_DIR);
+    let owner_id = Uuid::new_v4();
+
+    let sleep = Command::new("sleep".into(), vec!["1".into()]);
+    let exiting_id = app.worker.start(sleep.clone(), owner_id).unwrap();
+    let infinite_loop = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let looping_id = app.worker.start(infinite_loop.clone(), owner_id).unwrap();
+
+    // a job whose pid is alive but was recycled by an unrelated process
+    let recycled_id = Uuid::new_v4();
+    assert_ok!(assert_ok!(Journal::open(STATE_DIR)).record(Entry::Started {
+        job_id: recycled_id,
+        owner_id,
+        command: Command::new("true".into(), vec![]),
+        pid: std::process::id(),
+        start_time: Some(0),
+        isolation: Isolation::default(),
+    }));
+
+    let restarted = TestApp::with_config(
+        Config::new(LOG_DIR, 4)
+            .with_state_dir(STATE_DIR)
+            .with_orphan_recovery(),
+    );
+
+    let test_cases = [
+        (exiting_id, Status::Running, "job is still running"),
+        (looping_id, Status::Running, "job loops infinitely"),
+        (recycled_id, Status::UnknownState, "job pid was recycled"),
+    ];
+    for (job_id, expected_status, error_case) in test_cases {
+        let job_info = assert_ok!(restarted.worker.query(job_id, owner_id));
+        assert_eq!(
+            expected_status,
+            job_info.status(),
+            "orphaned job was not recovered as expected when {}",
+            error_case,
+        );
+    }
+
+    // the recycled pid is this test's own, which stop must not signal
+    let stop_res = assert_err!(restarted.worker.stop(recycled_id, owner_id, false));
+    assert_eq!(
+        format!("job {} has already finished", recycled_id),
+        stop_res.as_str(),
+    );
+    let job_info = assert_ok!(restarted.worker.query(recycled_id, owner_id));
+    assert_eq!(Status::UnknownState, job_info.status());
+
+    let (job_id, stopped) = assert_ok!(restarted.wait());
+    assert_eq!(
+        (exiting_id, false),
+        (job_id, stopped),
+        "restarted worker did not observe adopted job exit",
+    );
+    let job_info = assert_ok!(restarted.worker.query(exiting_id, owner_id));
+    assert_eq!(Status::Exited(None), job_info.status());
+
+    assert_ok!(restarted.worker.stop(looping_id, owner_id, false));
+    let (job_id, stopped) = assert_ok!(restarted.wait());
+    assert_eq!(
+        (looping_id, true),
+        (job_id, stopped),
+        "restarted worker did not observe adopted job stop",
+    );
+    let job_info = assert_ok!(restarted.worker.query(looping_id, owner_id));
+    assert_eq!(Status::Stopped(StopType::Kill), job_info.status());
+
+    let entries = assert_ok!(assert_ok!(Journal::open(STATE_DIR)).entries());
+    for job_id in [exiting_id, looping_id] {
+        assert!(
+            entries.contains(&Entry::Exited {
+                job_id,
+                exit_code: None,
+            }),
+            "journal did not record exit of adopted job",
+        );
+    }
+
+    assert_ok!(app.wait());
+    assert_ok!(app.wait());
+    app.log_handler
+        .consume(format!("{}_{}.log", sleep.name(), exiting_id));
+    app.log_handler
+        .consume(format!("{}_{}.log", infinite_loop.name(), looping_id));
+    std::fs::remove_dir_all(STATE_DIR).unwrap();
+}
@@ -0,0 +1,133 @@
+mod utils;
+
+use claim::assert_err;
+use iron_exec::{
+    job::{Isolation, Rlimits, Status},
+    worker::Config,
+};
+use utils::{
+    app::TestApp,
+    isolation::{pid_namespace_app, run, sh},
+    logs::LOG_DIR,
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_rlimits() {
+    let app = TestApp::new();
+    let spin = "while :; do :; done";
+
+    let test_cases = [
+        (
+            &app,
+            sh("ulimit -n; ulimit -Hn"),
+            Rlimits::default().with_nofile(16),
+            Status::Exited(Some(0)),
+            "16\n16\n",
+            "job reads its open file limit",
+        ),
+        (
+            &app,
+            sh("ulimit -c; ulimit -v; ulimit -p"),
+            Rlimits::default()
+                .with_core(0)
+                .with_address_space(1 << 30)
+                .with_nproc(64),
+            Status::Exited(Some(0)),
+            "0\n1048576\n64\n",
+            "job reads its core, address space and process limits",
+        ),
+        (
+            &app,
+            sh("ulimit -n 32 2>/dev/null || echo denied"),
+            Rlimits::default().with_nofile(16),
+            Status::Exited(Some(0)),
+            "denied\n",
+            "job raises its open file limit",
+        ),
+        (
+            &app,
+            sh(spin),
+            Rlimits::default().with_cpu(1),
+            Status::KilledByCpuLimit,
+            "",
+            "job runs out of cpu time",
+        ),
+        (
+            &pid_namespace_app(),
+            sh(spin),
+            Rlimits::default().with_cpu(1),
+            Status::KilledByCpuLimit,
+            "",
+            "job in a pid namespace runs out of cpu time",
+        ),
+    ];
+
+    for (app, command, rlimits, status, output, error_case) in test_cases {
+        let (job_info, job_output) = run(app, command.with_rlimits(rlimits.clone()));
+        assert_eq!(status, job_info.status(), "{}", error_case);
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(
+            Some(&rlimits),
+            job_info.isolation().rlimits(),
+            "{}",
+            error_case
+        );
+    }
+
+    // more than the kernel allows any process to open
+    let res = app.worker.start(
+        sh("true").with_rlimits(Rlimits::default().with_nofile(1 << 40)),
+        Uuid::new_v4(),
+    );
+    assert_eq!(
+        "failed to set the job's resource limits: Os { code: 1, kind: PermissionDenied, message: \"Operation not permitted\" }",
+        assert_err!(res).as_str()
+    );
+}
+
+#[test]
+pub fn test_rlimits_worker_limits() {
+    let worker = Rlimits::default().with_nofile(16).with_cpu(5);
+    let app = TestApp::with_config(
+        Config::new(LOG_DIR, 4).with_isolation(Isolation::default().with_rlimits(worker.clone())),
+    );
+
+    let test_cases = [
+        (
+            None,
+            "16\n5\n",
+            worker.clone(),
+            "job without limits of its own",
+        ),
+        (
+            Some(Rlimits::default().with_nofile(64).with_core(0)),
+            "16\n5\n",
+            worker.clone().with_core(0),
+            "job asks for a higher limit than the worker's",
+        ),
+        (
+            Some(Rlimits::default().with_nofile(8).with_cpu(3)),
+            "8\n3\n",
+            Rlimits::default().with_nofile(8).with_cpu(3),
+            "job asks for lower limits than the worker's",
+        ),
+    ];
+
+    for (rlimits, output, expected, error_case) in test_cases {
+        let command = sh("ulimit -Hn; ulimit -t");
+        let command = match rlimits {
+            Some(rlimits) => command.with_rlimits(rlimits),
+            None => command,
+        };
+        let (job_info, job_output) = run(&app, command);
+        assert_eq!(Some(0), job_info.exit_code(), "{}", error_case);
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(
+            Some(&expected),
+            job_info.isolation().rlimits(),
+            "{}",
+            error_case
+        );
+    }
+}
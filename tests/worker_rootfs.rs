@@ -0,0 +1,180 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::job::{IdMap, Isolation, Rootfs, UserNamespace};
+use utils::{
+    app::TestApp,
+    isolation::{mount_app, mount_source, rootfs, run, sh},
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_rootfs() {
+    let app = mount_app(
+        Isolation::default()
+            .with_pid_namespace()
+            .with_mount_namespace(),
+    );
+    let (dir, mounts) = rootfs();
+    let suid = format!("{}/suid", dir);
+    assert_ok!(std::fs::write(&suid, ""));
+    assert_ok!(std::fs::set_permissions(
+        &suid,
+        std::os::unix::fs::PermissionsExt::from_mode(0o4755)
+    ));
+    let tarball = format!("{}.tar", dir);
+    let output = assert_ok!(std::process::Command::new("tar")
+        .args(["-cf", &tarball, "-C", &dir, "."])
+        .output());
+    assert!(
+        output.status.success(),
+        "failed to create tarball: {:?}",
+        output
+    );
+
+    // the root is writable but keeps its writes to itself, and has neither
+    // devices nor setuid binaries
+    let script = |checks: &str| {
+        format!(
+            "cat /marker && test ! -e {}/Cargo.toml && echo discarded > /dev/null && touch /written && {}",
+            env!("CARGO_MANIFEST_DIR"),
+            checks
+        )
+    };
+    let mounted = "grep -q ' / / [^ ]*nosuid,nodev' /proc/self/mountinfo";
+    // without a pid namespace, as a user namespace may not mount a /proc
+    // once no other is visible
+    let user_namespace_app = mount_app(
+        Isolation::default()
+            .with_user_namespace(
+                UserNamespace::new()
+                    .with_uid_map(vec![IdMap::new(0, 100000, 65536)])
+                    .with_gid_map(vec![IdMap::new(0, 100000, 65536)]),
+            )
+            .with_mount_namespace(),
+    );
+    let test_cases = [
+        (
+            &app,
+            Rootfs::Directory(dir.clone()),
+            script(&format!("{} && test -u /suid", mounted)),
+            "job runs in a root filesystem directory",
+        ),
+        (
+            &app,
+            Rootfs::Tarball(tarball.clone()),
+            script(&format!("{} && test ! -u /suid", mounted)),
+            "job runs in a root filesystem unpacked from a tarball",
+        ),
+        (
+            &user_namespace_app,
+            Rootfs::Tarball(tarball.clone()),
+            script("test ! -u /suid"),
+            "job runs in a root filesystem unpacked from a tarball in a user namespace",
+        ),
+    ];
+
+    for (app, rootfs, script, error_case) in test_cases {
+        let command = sh(&script).with_mounts(mounts.clone()).with_rootfs(rootfs);
+        let (job_info, output) = run(app, command);
+        assert_eq!(
+            Some(0),
+            job_info.exit_code(),
+            "job did not exit with expected exit code when {}: {:?}",
+            error_case,
+            output
+        );
+        assert_eq!(
+            "rootfs\n", output,
+            "job did not print expected output when {}",
+            error_case
+        );
+        for path in ["written", "tmp", "dev", "proc"] {
+            assert!(
+                !std::path::Path::new(&format!("{}/{}", dir, path)).exists(),
+                "job created {} in the root filesystem's source when {}",
+                path,
+                error_case
+            );
+        }
+    }
+
+    let unpacked = assert_ok!(std::fs::read_dir(std::env::temp_dir()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("iron-exec-rootfs-")
+        })
+        .count();
+    assert_eq!(0, unpacked, "unpacked root filesystem was not removed");
+}
+
+#[test]
+pub fn test_rootfs_errors() {
+    let source = mount_source();
+    let rootfs_app = mount_app(Isolation::default().with_mount_namespace());
+    let app = TestApp::new();
+    let outside = format!("/tmp/iron_exec_rootfs_{}", Uuid::new_v4());
+    assert_ok!(std::fs::create_dir_all(&outside));
+
+    let missing = format!("{}/missing", source);
+    let file = format!("{}/file", source);
+    let test_cases = [
+        (
+            &app,
+            Rootfs::Directory(source.clone()),
+            "a root filesystem needs the job to have a mount namespace".to_string(),
+            "worker has no mount namespace",
+        ),
+        (
+            &rootfs_app,
+            Rootfs::Directory(missing.clone()),
+            format!(
+                "failed to read root filesystem {}: Os {{ code: 2, kind: NotFound, message: \"No such file or directory\" }}",
+                missing
+            ),
+            "root filesystem does not exist",
+        ),
+        (
+            &rootfs_app,
+            Rootfs::Directory(file.clone()),
+            format!("root filesystem {} is not a directory", file),
+            "root filesystem is a file",
+        ),
+        (
+            &rootfs_app,
+            Rootfs::Directory(outside.clone()),
+            format!(
+                "root filesystem {} is outside of the worker's allowed mount sources",
+                outside
+            ),
+            "root filesystem is outside of the allowed mount sources",
+        ),
+        (
+            &rootfs_app,
+            Rootfs::Directory("/".into()),
+            "root filesystem / is outside of the worker's allowed mount sources".to_string(),
+            "root filesystem is the host's root",
+        ),
+        (
+            &rootfs_app,
+            Rootfs::Tarball(file.clone()),
+            format!("failed to unpack root filesystem {}: ", file),
+            "tarball is not a tar archive",
+        ),
+    ];
+
+    for (app, rootfs, expected_error, error_case) in test_cases {
+        let command = sh("true").with_rootfs(rootfs);
+        let error = assert_err!(app.worker.start(command, Uuid::new_v4()));
+        assert!(
+            error.as_str().starts_with(&expected_error),
+            "start did not fail as expected when {}: {}",
+            error_case,
+            error.as_str()
+        );
+    }
+    assert_ok!(std::fs::remove_dir(&outside));
+}
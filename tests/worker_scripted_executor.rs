@@ -0,0 +1,184 @@
+mod utils;
+
+use std::time::Duration;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    executor::{Script, ScriptedExecutor},
+    job::{Command, Status, StopType},
+    worker::{Config, Worker},
+};
+use nix::sys::signal::Signal;
+use utils::logs::{TestLog, LOG_DIR};
+use uuid::Uuid;
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+fn scripted_worker() -> (Worker<ScriptedExecutor>, ScriptedExecutor) {
+    let executor = ScriptedExecutor::new();
+    let worker = Worker::with_executor(Config::new(LOG_DIR, 4), executor.clone()).unwrap();
+    (worker, executor)
+}
+
+#[test]
+pub fn test_scripted_status_changes() {
+    let (worker, executor) = scripted_worker();
+    let log_handler = TestLog::new();
+    executor.script("build", Script::Run);
+    executor.script("lint", Script::Exit(Some(1)));
+    let owner_id = Uuid::new_v4();
+
+    let build = Command::new("build".into(), vec![]);
+    let build_id = worker.start(build.clone(), owner_id).unwrap();
+    let lint = Command::new("lint".into(), vec![]);
+    let lint_id = worker.start(lint.clone(), owner_id).unwrap();
+
+    let job_info = assert_ok!(worker.wait(lint_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Exited(Some(1)), job_info.status());
+
+    let job_info = assert_ok!(worker.query(build_id, owner_id));
+    assert_eq!(Status::Running, job_info.status());
+    assert_eq!(vec![job_info.pid()], executor.running());
+
+    assert!(executor.exit(job_info.pid(), Some(2)));
+    let job_info = assert_ok!(worker.wait(build_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Exited(Some(2)), job_info.status());
+    assert!(executor.running().is_empty());
+
+    log_handler.consume(format!("{}_{}.log", build.name(), build_id));
+    log_handler.consume(format!("{}_{}.log", lint.name(), lint_id));
+}
+
+#[test]
+pub fn test_scripted_stop_races() {
+    let (worker, executor) = scripted_worker();
+    let log_handler = TestLog::new();
+    executor.script("stubborn", Script::IgnoreSignals);
+    executor.script("serve", Script::Run);
+    let owner_id = Uuid::new_v4();
+
+    // the stop is recorded even though the process outlives the signal, and
+    // its later exit code does not replace the stop
+    let stubborn = Command::new("stubborn".into(), vec![]);
+    let stubborn_id = worker.start(stubborn.clone(), owner_id).unwrap();
+    let pid = worker.query(stubborn_id, owner_id).unwrap().pid();
+    assert_ok!(worker.stop(stubborn_id, owner_id, true));
+    assert_eq!(vec![(pid, StopType::Term.sig())], executor.signals());
+    assert_eq!(
+        Status::Stopped(StopType::Term),
+        worker.query(stubborn_id, owner_id).unwrap().status(),
+    );
+    let e = assert_err!(worker.wait(stubborn_id, owner_id, Some(Duration::from_millis(50))));
+    assert_eq!(
+        format!("timed out waiting for job {}", stubborn_id),
+        e.as_str()
+    );
+    assert!(executor.exit(pid, Some(0)));
+    let job_info = assert_ok!(worker.wait(stubborn_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Stopped(StopType::Term), job_info.status());
+    assert_eq!(None, job_info.exit_code());
+
+    // a stop that loses the race against the exit fails and leaves the exit,
+    // whether or not the worker has recorded the exit yet
+    let serve = Command::new("serve".into(), vec![]);
+    let serve_id = worker.start(serve.clone(), owner_id).unwrap();
+    let pid = worker.query(serve_id, owner_id).unwrap().pid();
+    assert!(executor.exit(pid, Some(0)));
+    let e = assert_err!(worker.stop(serve_id, owner_id, false));
+    assert!(
+        [
+            "failed to send SIGKILL to job: ESRCH".to_string(),
+            format!("job {} has already finished", serve_id),
+        ]
+        .contains(&e.as_str().to_string()),
+        "unexpected stop error: {}",
+        e.as_str()
+    );
+    let job_info = assert_ok!(worker.wait(serve_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Exited(Some(0)), job_info.status());
+
+    // once the exit is recorded the pid is not signalled at all
+    let signals = executor.signals().len();
+    let e = assert_err!(worker.stop(serve_id, owner_id, false));
+    assert_eq!(format!("job {} has already finished", serve_id), e.as_str());
+    assert_eq!(signals, executor.signals().len());
+
+    log_handler.consume(format!("{}_{}.log", stubborn.name(), stubborn_id));
+    log_handler.consume(format!("{}_{}.log", serve.name(), serve_id));
+}
+
+#[test]
+pub fn test_scripted_signal() {
+    let (worker, executor) = scripted_worker();
+    let log_handler = TestLog::new();
+    executor.script("reload", Script::Run);
+    let owner_id = Uuid::new_v4();
+
+    let reload = Command::new("reload".into(), vec![]);
+    let reload_id = worker.start(reload.clone(), owner_id).unwrap();
+    let pid = worker.query(reload_id, owner_id).unwrap().pid();
+
+    // other signals reach the process without ending the job
+    assert_ok!(worker.signal(reload_id, owner_id, Signal::SIGHUP as i32));
+    assert_eq!(vec![(pid, Signal::SIGHUP as usize)], executor.signals());
+    assert_eq!(
+        Status::Running,
+        worker.query(reload_id, owner_id).unwrap().status()
+    );
+
+    let test_cases = [
+        (
+            Signal::SIGTERM as i32,
+            "SIGTERM stops the job, which is what stop is for".to_string(),
+            "signalling with SIGTERM",
+        ),
+        (
+            Signal::SIGKILL as i32,
+            "SIGKILL stops the job, which is what stop is for".to_string(),
+            "signalling with SIGKILL",
+        ),
+        (
+            1000,
+            "invalid signal 1000".to_string(),
+            "signalling with an unknown signal",
+        ),
+    ];
+    for (signal, error_message, error_case) in test_cases {
+        let e = assert_err!(
+            worker.signal(reload_id, owner_id, signal),
+            "signal did not error when {}",
+            error_case
+        );
+        assert_eq!(
+            error_message,
+            e.as_str(),
+            "unexpected error when {}",
+            error_case
+        );
+    }
+    assert_eq!(1, executor.signals().len());
+
+    assert!(executor.exit(pid, Some(0)));
+    assert_ok!(worker.wait(reload_id, owner_id, TIMEOUT));
+    let e = assert_err!(worker.signal(reload_id, owner_id, Signal::SIGHUP as i32));
+    assert_eq!(
+        format!("job {} has already finished", reload_id),
+        e.as_str()
+    );
+    assert_eq!(1, executor.signals().len());
+
+    log_handler.consume(format!("{}_{}.log", reload.name(), reload_id));
+}
+
+#[test]
+pub fn test_scripted_spawn_failure() {
+    let (worker, executor) = scripted_worker();
+    let log_handler = TestLog::new();
+    executor.script("broken", Script::FailSpawn("exec format error".into()));
+
+    let e = assert_err!(worker.start(Command::new("broken".into(), vec![]), Uuid::new_v4()));
+    assert_eq!("exec format error", e.as_str());
+    assert!(executor.running().is_empty());
+    assert!(worker.jobs.lock().unwrap().is_empty());
+    let _ = log_handler;
+}
@@ -0,0 +1,246 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, Isolation, Seccomp, SeccompProfile, Status},
+    worker::Config,
+};
+use std::net::TcpListener;
+use utils::{
+    app::TestApp,
+    isolation::{pid_namespace_app, run, sh},
+    logs::LOG_DIR,
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_seccomp() {
+    let app = TestApp::new();
+    let listener = assert_ok!(TcpListener::bind("127.0.0.1:0"));
+    let connect = format!(
+        "exec bash -c 'echo > /dev/tcp/127.0.0.1/{}'",
+        assert_ok!(listener.local_addr()).port()
+    );
+    let profile = |profile| Seccomp::Profile(profile);
+    let deny_mkdir = Seccomp::Deny(vec!["mkdir".into(), "mkdirat".into()]);
+    let clone_user = format!(
+        "echo before; exec perl -e 'syscall({}, {}, 0, 0, 0, 0); print \"cloned\\n\"'",
+        syscalls::Sysno::clone.id(),
+        nix::libc::CLONE_NEWUSER | nix::libc::SIGCHLD
+    );
+    let clone3 = format!(
+        "perl -e 'syscall({}, 0, 0) < 0 and print $!{{ENOSYS}} ? \"enosys\\n\" : \"$!\\n\"'",
+        syscalls::Sysno::clone3.id()
+    );
+
+    let test_cases = [
+        (
+            &app,
+            sh("echo before; exec unshare -U true"),
+            profile(SeccompProfile::DefaultDenyDangerous),
+            Status::KilledBySeccomp,
+            "before\n",
+            "job creates a namespace under the default profile",
+        ),
+        (
+            &app,
+            sh("echo ok"),
+            profile(SeccompProfile::DefaultDenyDangerous),
+            Status::Exited(Some(0)),
+            "ok\n",
+            "job makes harmless calls under the default profile",
+        ),
+        (
+            &app,
+            sh(&clone_user),
+            profile(SeccompProfile::DefaultDenyDangerous),
+            Status::KilledBySeccomp,
+            "before\n",
+            "job clones into a user namespace under the default profile",
+        ),
+        (
+            &app,
+            sh(&clone3),
+            profile(SeccompProfile::DefaultDenyDangerous),
+            Status::Exited(Some(0)),
+            "enosys\n",
+            "job calls clone3 under the default profile",
+        ),
+        (
+            &app,
+            sh("(echo forked)"),
+            profile(SeccompProfile::DefaultDenyDangerous),
+            Status::Exited(Some(0)),
+            "forked\n",
+            "job forks under the default profile",
+        ),
+        (
+            &app,
+            sh(&connect),
+            profile(SeccompProfile::NoNetworkSyscalls),
+            Status::KilledBySeccomp,
+            "",
+            "job opens a tcp socket without network calls",
+        ),
+        (
+            &app,
+            sh(&format!("{} 2>/dev/null", connect)),
+            profile(SeccompProfile::NoPtrace),
+            Status::Exited(Some(0)),
+            "",
+            "job opens a tcp socket without ptrace",
+        ),
+        (
+            &app,
+            sh("echo before; exec mkdir /tmp/denied"),
+            deny_mkdir.clone(),
+            Status::KilledBySeccomp,
+            "before\n",
+            "job makes a denied call",
+        ),
+        (
+            &pid_namespace_app(),
+            sh("echo before; exec mkdir /tmp/denied"),
+            deny_mkdir.clone(),
+            Status::KilledBySeccomp,
+            "before\n",
+            "job in a pid namespace makes a denied call",
+        ),
+        (
+            &app,
+            Command::new("true".into(), vec![]),
+            Seccomp::Allow(vec!["exit_group".into()]),
+            Status::KilledBySeccomp,
+            "",
+            "job makes a call that is not allowed",
+        ),
+    ];
+
+    for (app, command, seccomp, status, output, error_case) in test_cases {
+        let (job_info, job_output) = run(app, command.with_seccomp(seccomp.clone()));
+        assert_eq!(status, job_info.status(), "{}", error_case);
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(
+            Some(&seccomp),
+            job_info.isolation().seccomp(),
+            "{}",
+            error_case
+        );
+    }
+    assert!(!std::path::Path::new("/tmp/denied").exists());
+
+    let res = app.worker.start(
+        sh("true").with_seccomp(Seccomp::Deny(vec!["nope".into()])),
+        Uuid::new_v4(),
+    );
+    assert_eq!(
+        "unknown system call in seccomp filter: \"nope\"",
+        assert_err!(res).as_str()
+    );
+}
+
+#[test]
+pub fn test_seccomp_worker_filter() {
+    let dangerous = Seccomp::Profile(SeccompProfile::DefaultDenyDangerous);
+    let app = TestApp::with_config(
+        Config::new(LOG_DIR, 4)
+            .with_isolation(Isolation::default().with_seccomp(dangerous.clone())),
+    );
+    let allow_exit = Seccomp::Allow(vec!["exit_group".into()]);
+
+    let test_cases = [
+        (
+            sh("echo before; exec unshare -U true"),
+            dangerous.clone(),
+            Status::KilledBySeccomp,
+            "before\n",
+            "job without a filter of its own creates a namespace",
+        ),
+        (
+            Command::new("true".into(), vec![]).with_seccomp(allow_exit.clone()),
+            allow_exit.clone(),
+            Status::KilledBySeccomp,
+            "",
+            "job asks for a stricter filter",
+        ),
+    ];
+
+    for (command, seccomp, status, output, error_case) in test_cases {
+        let (job_info, job_output) = run(&app, command);
+        assert_eq!(status, job_info.status(), "{}", error_case);
+        assert_eq!(output, job_output, "{}", error_case);
+        assert_eq!(
+            Some(&seccomp),
+            job_info.isolation().seccomp(),
+            "{}",
+            error_case
+        );
+    }
+
+    let test_cases = [
+        (
+            Seccomp::Profile(SeccompProfile::NoPtrace),
+            "seccomp filter no-ptrace is less strict than the worker's default-deny-dangerous",
+            "job asks for a weaker profile",
+        ),
+        (
+            Seccomp::Deny(vec!["mkdir".into()]),
+            "seccomp filter custom is less strict than the worker's default-deny-dangerous",
+            "job asks for a weaker custom filter",
+        ),
+        (
+            Seccomp::Allow(vec!["exit_group".into(), "unshare".into()]),
+            "seccomp filter custom is less strict than the worker's default-deny-dangerous",
+            "job asks to allow a call the worker denies",
+        ),
+    ];
+
+    for (seccomp, expected_error, error_case) in test_cases {
+        let res = app
+            .worker
+            .start(sh("true").with_seccomp(seccomp), Uuid::new_v4());
+        assert_eq!(expected_error, assert_err!(res).as_str(), "{}", error_case);
+    }
+}
+
+#[test]
+pub fn test_seccomp_load() {
+    let dir = format!(
+        "{}/tests/.state/seccomp_{}",
+        env!("CARGO_MANIFEST_DIR"),
+        Uuid::new_v4()
+    );
+    assert_ok!(std::fs::create_dir_all(&dir));
+
+    let test_cases = [
+        (
+            "deny = [\"mkdir\", \"ptrace\"]",
+            Ok(Seccomp::Deny(vec!["mkdir".into(), "ptrace".into()])),
+            "file lists calls to deny",
+        ),
+        (
+            "allow = [\"read\"]",
+            Ok(Seccomp::Allow(vec!["read".into()])),
+            "file lists calls to allow",
+        ),
+        (
+            "allow = [\"read\"]\ndeny = [\"mkdir\"]",
+            Err("must list either allow or deny"),
+            "file lists calls to allow and deny",
+        ),
+        ("", Err("must list either allow or deny"), "file is empty"),
+    ];
+
+    for (i, (contents, expected, error_case)) in test_cases.into_iter().enumerate() {
+        let path = format!("{}/filter_{}.toml", dir, i);
+        assert_ok!(std::fs::write(&path, contents));
+        match expected {
+            Ok(seccomp) => assert_eq!(seccomp, assert_ok!(Seccomp::load(&path)), "{}", error_case),
+            Err(e) => assert!(
+                assert_err!(Seccomp::load(&path)).as_str().contains(e),
+                "{}",
+                error_case
+            ),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::job::{Command, Status};
+use nix::sys::signal::Signal;
+use std::{fs, thread, time::Duration};
+use utils::{app::TestApp, logs::LOG_DIR};
+use uuid::Uuid;
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+#[test]
+pub fn test_signal_success() {
+    let app = TestApp::new();
+    let owner_id = Uuid::new_v4();
+
+    let job_id = assert_ok!(app.worker.start(
+        Command::new("sh".into(), vec!["./tests/scripts/trap_usr1.sh".into()]),
+        owner_id,
+    ));
+    // the trap has to be set before the signal arrives
+    let log_path = format!("{}/sh_{}.log", LOG_DIR, job_id);
+    while !fs::read_to_string(&log_path)
+        .unwrap_or_default()
+        .contains("ready")
+    {
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_ok!(app.worker.signal(job_id, owner_id, Signal::SIGUSR1 as i32));
+    let job_info = assert_ok!(app.worker.wait(job_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Exited(Some(3)), job_info.status());
+
+    let logs = app.log_handler.consume(format!("sh_{}.log", job_id));
+    assert_eq!("ready\nreloaded\n", String::from_utf8(logs).unwrap());
+}
+
+#[test]
+pub fn test_signal_error() {
+    let app = TestApp::new();
+    let owner_id = Uuid::new_v4();
+
+    let job_id = assert_ok!(app
+        .worker
+        .start(Command::new("echo".into(), vec!["hello".into()]), owner_id));
+    assert_ok!(app.worker.wait(job_id, owner_id, TIMEOUT));
+
+    let unknown_id = Uuid::new_v4();
+    let test_cases = [
+        (
+            job_id,
+            Signal::SIGHUP as i32,
+            format!("job {} has already finished", job_id),
+            "signalling a finished job",
+        ),
+        (
+            job_id,
+            Signal::SIGTERM as i32,
+            "SIGTERM stops the job, which is what stop is for".to_string(),
+            "signalling with a stop signal",
+        ),
+        (
+            unknown_id,
+            Signal::SIGHUP as i32,
+            format!("no job with id {} found for user", unknown_id),
+            "signalling an unknown job",
+        ),
+    ];
+
+    for (job_id, signal, error_message, error_case) in test_cases {
+        let e = assert_err!(
+            app.worker.signal(job_id, owner_id, signal),
+            "signal did not error when {}",
+            error_case
+        );
+        assert_eq!(
+            error_message,
+            e.as_str(),
+            "unexpected error when {}",
+            error_case
+        );
+    }
+
+    app.log_handler.consume(format!("echo_{}.log", job_id));
+}
@@ -2,7 +2,8 @@ mod utils;
 
 use claim::{assert_err, assert_ok};
 use iron_exec::job::{Command, Status};
-use utils::app::TestApp;
+use std::{fs, path::Path};
+use utils::{app::TestApp, logs::LOG_DIR};
 use uuid::Uuid;
 
 #[test]
@@ -83,3 +84,40 @@ pub fn test_start_job_error() {
         assert_eq!(error_message, e.as_str(),)
     }
 }
+
+#[test]
+pub fn test_start_job_log_path() {
+    let app = TestApp::new();
+
+    // only the command's file name makes it into the log's
+    let job_id = assert_ok!(app.worker.start(
+        Command::new("/bin/echo".into(), vec!["hello".into()]),
+        Uuid::new_v4(),
+    ));
+    assert_ok!(app.wait());
+    assert!(!Path::new(&format!("/bin/echo_{}.log", job_id)).exists());
+    let logs = app.log_handler.consume(format!("echo_{}.log", job_id));
+    assert_eq!("hello\n", String::from_utf8(logs).unwrap());
+
+    let name = format!("escape_{}", Uuid::new_v4());
+    assert_err!(app.worker.start(
+        Command::new(format!("../../../../../tmp/{}", name), vec![]),
+        Uuid::new_v4(),
+    ));
+    let escaped = |dir: &str| {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&name))
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        Vec::<std::path::PathBuf>::new(),
+        escaped("/tmp"),
+        "log was written outside of the log directory"
+    );
+    let logs = escaped(LOG_DIR);
+    assert_eq!(1, logs.len(), "log was not written to the log directory");
+    fs::remove_file(&logs[0]).unwrap();
+}
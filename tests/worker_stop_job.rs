@@ -82,14 +82,14 @@ pub fn test_stop_error() {
             owner_id,
             false,
             "kill an exited process",
-            "failed to send SIGKILL to job: ESRCH".into(),
+            format!("job {} has already finished", job),
         ),
         (
             job,
             owner_id,
             true,
             "terminate an exited process",
-            "failed to send SIGTERM to job: ESRCH".into(),
+            format!("job {} has already finished", job),
         ),
     ];
 
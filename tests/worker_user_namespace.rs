@@ -0,0 +1,141 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{IdMap, Isolation, Mount, UserNamespace},
+    worker::Config,
+};
+use utils::{
+    app::TestApp,
+    isolation::{mount_source, run, sh},
+    logs::LOG_DIR,
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_user_namespace() {
+    let mapped = UserNamespace::new()
+        .with_uid_map(vec![IdMap::new(0, 100000, 65536)])
+        .with_gid_map(vec![IdMap::new(0, 100000, 65536)]);
+    // the job's ids cannot reach into the test's own directories
+    let dir = format!("/tmp/iron_exec_userns_{}", Uuid::new_v4());
+    let app = TestApp::with_config(
+        Config::new(LOG_DIR, 4)
+            .with_isolation(
+                Isolation::default()
+                    .with_user_namespace(mapped.clone())
+                    .with_pid_namespace()
+                    .with_mount_namespace(),
+            )
+            .with_mount_sources(vec![dir.clone()]),
+    );
+    let (source, shared) = (format!("{}/source", dir), format!("{}/shared", dir));
+    assert_ok!(std::fs::create_dir_all(&source));
+    assert_ok!(std::fs::create_dir_all(&shared));
+    assert_ok!(std::fs::set_permissions(
+        &shared,
+        std::os::unix::fs::PermissionsExt::from_mode(0o777)
+    ));
+    let mounts = vec![
+        Mount::new(source.clone(), "/tmp/source".into()),
+        Mount::new(shared.clone(), "/tmp/shared".into()),
+    ];
+
+    let test_cases = [
+        (
+            sh("echo $$; id -u; id -g"),
+            0,
+            "2\n0\n0\n",
+            "job runs as root of its user namespace",
+        ),
+        (
+            sh("touch /tmp/source/new 2>/dev/null"),
+            1,
+            "",
+            "job writes to a directory of the host's root",
+        ),
+        (
+            sh("touch /tmp/shared/new"),
+            0,
+            "",
+            "job writes to a directory anybody may write to",
+        ),
+    ];
+
+    for (command, expected_code, expected_output, error_case) in test_cases {
+        let (job_info, output) = run(&app, command.with_mounts(mounts.clone()));
+        assert_eq!(
+            Some(expected_code),
+            job_info.exit_code(),
+            "job did not exit with expected exit code when {}: {:?}",
+            error_case,
+            output
+        );
+        assert_eq!(
+            expected_output, output,
+            "job did not print expected output when {}",
+            error_case
+        );
+        assert_eq!(Some(&mapped), job_info.isolation().user_namespace());
+    }
+
+    // the job's root is an unprivileged id on the host
+    let metadata = assert_ok!(std::fs::metadata(format!("{}/new", shared)));
+    assert_eq!(
+        100000,
+        std::os::unix::fs::MetadataExt::uid(&metadata),
+        "job's file was not owned by its mapped uid"
+    );
+    assert_ok!(std::fs::remove_dir_all(dir));
+}
+
+#[test]
+pub fn test_subordinate_ids() {
+    let dir = mount_source();
+    let uid = nix::unistd::getuid().as_raw();
+    let gid = nix::unistd::getgid().as_raw();
+    let subids = format!("{}/subids", dir);
+    assert_ok!(std::fs::write(
+        &subids,
+        "# user:start:count\nalice:100000:65536\nbob:200000:65536\nalice:300000:10\n"
+    ));
+    let malformed = format!("{}/malformed", dir);
+    assert_ok!(std::fs::write(&malformed, "alice:100000\n"));
+
+    let user_namespace =
+        assert_ok!(UserNamespace::new().with_subordinate_ids("alice", &subids, &subids));
+    assert_eq!(
+        vec![
+            IdMap::new(0, uid, 1),
+            IdMap::new(1, 100000, 65536),
+            IdMap::new(65537, 300000, 10),
+        ],
+        user_namespace.uid_map()
+    );
+    assert_eq!(IdMap::new(0, gid, 1), user_namespace.gid_map()[0]);
+
+    let test_cases = [
+        (
+            "carol",
+            subids.clone(),
+            format!("{} has no subordinate ids for carol", subids),
+            "user has no subordinate ids",
+        ),
+        (
+            "alice",
+            malformed.clone(),
+            format!("malformed line in {}: \"alice:100000\"", malformed),
+            "file is malformed",
+        ),
+    ];
+
+    for (user, path, expected_error, error_case) in test_cases {
+        let error = assert_err!(UserNamespace::new().with_subordinate_ids(user, &path, &path));
+        assert_eq!(
+            expected_error,
+            error.as_str(),
+            "subordinate ids did not fail as expected when {}",
+            error_case
+        );
+    }
+}
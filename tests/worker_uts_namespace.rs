@@ -0,0 +1,88 @@
+mod utils;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, Isolation},
+    worker::Config,
+};
+use utils::{
+    app::TestApp,
+    isolation::{pid_namespace_app, run},
+    logs::LOG_DIR,
+};
+use uuid::Uuid;
+
+#[test]
+pub fn test_uts_namespace() {
+    let app = TestApp::new();
+    let uts_app = TestApp::with_config(
+        Config::new(LOG_DIR, 4).with_isolation(Isolation::default().with_uts_namespace()),
+    );
+    let hostname = || assert_ok!(std::fs::read_to_string("/proc/sys/kernel/hostname"));
+    let host = hostname().trim().to_string();
+    // None stands for the job's id
+    let job_id = None;
+
+    let test_cases = [
+        (
+            &app,
+            None,
+            Some(host.as_str()),
+            false,
+            "job without a uts namespace",
+        ),
+        (&uts_app, None, job_id, true, "job named after its job id"),
+        (
+            &app,
+            Some("builder"),
+            Some("builder"),
+            true,
+            "job asks for a hostname",
+        ),
+        (
+            &uts_app,
+            Some("builder.test"),
+            Some("builder.test"),
+            true,
+            "job asks for a hostname in a uts namespace",
+        ),
+        (
+            &pid_namespace_app(),
+            Some("builder"),
+            Some("builder"),
+            true,
+            "job in a pid namespace asks for a hostname",
+        ),
+    ];
+
+    for (app, hostname, expected, uts_namespace, error_case) in test_cases {
+        let command = Command::new("uname".into(), vec!["-n".into()]);
+        let command = match hostname {
+            Some(hostname) => command.with_hostname(hostname.into()),
+            None => command,
+        };
+        let (job_info, output) = run(app, command);
+        let expected = expected
+            .map(String::from)
+            .unwrap_or_else(|| job_info.id().to_string());
+        assert_eq!(format!("{}\n", expected), output, "{}", error_case);
+        let isolation = job_info.isolation();
+        assert_eq!(uts_namespace, isolation.uts_namespace(), "{}", error_case);
+        assert_eq!(
+            uts_namespace.then_some(expected.as_str()),
+            isolation.hostname(),
+            "{}",
+            error_case
+        );
+    }
+    assert_eq!(host, hostname().trim());
+
+    let res = app.worker.start(
+        Command::new("true".into(), vec![]).with_hostname("not a hostname".into()),
+        Uuid::new_v4(),
+    );
+    assert_eq!(
+        "invalid hostname \"not a hostname\"",
+        assert_err!(res).as_str()
+    );
+}
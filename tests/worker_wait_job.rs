@@ -0,0 +1,105 @@
+mod utils;
+
+use std::time::Duration;
+
+use claim::{assert_err, assert_ok};
+use iron_exec::{
+    job::{Command, Status, StopType},
+    worker::Error,
+};
+use utils::app::TestApp;
+use uuid::Uuid;
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+#[test]
+pub fn test_wait_success() {
+    let app = TestApp::new();
+    let owner_id = Uuid::new_v4();
+
+    let sleep = Command::new("sh".into(), vec!["-c".into(), "sleep 1; exit 3".into()]);
+    let sleep_id = app.worker.start(sleep.clone(), owner_id).unwrap();
+    let echo = Command::new("echo".into(), vec!["hello".into(), "world".into()]);
+    let echo_id = app.worker.start(echo.clone(), owner_id).unwrap();
+    let infinite_loop = Command::new("sh".into(), vec!["./tests/scripts/infinite_loop.sh".into()]);
+    let loop_id = app.worker.start(infinite_loop.clone(), owner_id).unwrap();
+
+    // the later, faster job does not satisfy a wait on the slower one
+    let job_info = assert_ok!(app.worker.wait(sleep_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Exited(Some(3)), job_info.status());
+    assert_eq!(Some(3), job_info.exit_code());
+
+    // the echo job finished long before this call
+    let job_info = assert_ok!(app.worker.wait(echo_id, owner_id, None));
+    assert_eq!(Status::Exited(Some(0)), job_info.status());
+
+    app.worker.stop(loop_id, owner_id, true).unwrap();
+    let job_info = assert_ok!(app.worker.wait(loop_id, owner_id, TIMEOUT));
+    assert_eq!(Status::Stopped(StopType::Term), job_info.status());
+
+    app.log_handler
+        .consume(format!("{}_{}.log", sleep.name(), sleep_id));
+    app.log_handler
+        .consume(format!("{}_{}.log", echo.name(), echo_id));
+    app.log_handler
+        .consume(format!("{}_{}.log", infinite_loop.name(), loop_id));
+}
+
+#[test]
+pub fn test_wait_error() {
+    let app = TestApp::new();
+    let owner_id = Uuid::new_v4();
+
+    let command = Command::new("sh".into(), vec!["./tests/scripts/long_runtime.sh".into()]);
+    let job_id = app.worker.start(command.clone(), owner_id).unwrap();
+    let missing_id = Uuid::new_v4();
+
+    let test_cases = [
+        (
+            missing_id,
+            owner_id,
+            TIMEOUT,
+            Error::NotFound(format!("no job with id {} found for user", missing_id)),
+            "wait on a non-existent job",
+        ),
+        (
+            job_id,
+            Uuid::new_v4(),
+            TIMEOUT,
+            Error::NotFound(format!("no job with id {} found for user", job_id)),
+            "wait on a job the current user does not own",
+        ),
+        (
+            job_id,
+            owner_id,
+            Some(Duration::from_millis(100)),
+            Error::Timeout(format!("timed out waiting for job {}", job_id)),
+            "wait on a job that outlives the timeout",
+        ),
+    ];
+
+    for (job_id, owner_id, timeout, expected_error, error_case) in test_cases {
+        let e = assert_err!(
+            app.worker.wait(job_id, owner_id, timeout),
+            "wait did not error when trying to {}",
+            error_case,
+        );
+        assert_eq!(
+            expected_error.as_str(),
+            e.as_str(),
+            "error message did not match expected message when trying to {}",
+            error_case,
+        );
+        assert_eq!(
+            std::mem::discriminant(&expected_error),
+            std::mem::discriminant(&e),
+            "error was not of the expected kind when trying to {}",
+            error_case,
+        );
+    }
+
+    app.worker.stop(job_id, owner_id, false).unwrap();
+    assert_ok!(app.worker.wait(job_id, owner_id, TIMEOUT));
+    app.log_handler
+        .consume(format!("{}_{}.log", command.name(), job_id));
+}